@@ -1,33 +1,25 @@
-use reqwest::{
-    blocking::Client,
-    header::{HeaderMap, HeaderValue, AUTHORIZATION},
-};
-use std::{env, error::Error};
-
-use dotenv::dotenv;
-
-pub fn initialize() -> Result<(), Box<dyn Error>> {
-    dotenv()?;
-    Ok(())
-}
-
-pub fn fetch_profile_information(user_id: &str) -> Result<(), Box<dyn Error>> {
-    let authorization_token = env::var("DISCORD_TOKEN")?;
-    let url = format!("https://discord.com/api/v9/users/{}/profile?with_mutual_guilds=false&with_mutual_friends=false&with_mutual_friends_count=false", user_id);
-
-    let client = Client::new();
-
-    let mut headers = HeaderMap::new();
-    headers.insert(AUTHORIZATION, HeaderValue::from_str(&authorization_token)?);
-
-    let response = client.get(&url).headers(headers).send()?;
-
-    if response.status().is_success() {
-        let text = response.text()?;
-        println!("{}", text);
+use std::path::Path;
+use tracing::warn;
+
+use crate::utils::app_dirs::app_dirs;
+
+/// Loads .env from the config directory, or from the working directory like older versions did.
+/// Neither is needed, without DISCORD_TOKEN the login screen asks for a token.
+pub fn initialize() {
+    let path = app_dirs().config.join(".env");
+    let result = if path.exists() {
+        dotenv::from_path(&path)
+    } else if Path::new(".env").exists() {
+        warn!(
+            ".env is read from the working directory, move it to {}",
+            path.display()
+        );
+        dotenv::from_path(".env")
     } else {
-        println!("Request failed with status: {}", response.status());
-    }
+        Ok(())
+    };
 
-    Ok(())
+    if let Err(e) = result {
+        warn!("Failed to read .env: {}", e);
+    }
 }
@@ -0,0 +1,100 @@
+use std::{io::Cursor, rc::Rc, time::Duration};
+
+use image::{
+    codecs::{gif::GifDecoder, webp::WebPDecoder},
+    AnimationDecoder, Frame, ImageFormat, ImageResult,
+};
+use slint::{Image, ModelRc, Rgba8Pixel, SharedPixelBuffer, VecModel};
+
+/// Frames shorter than this are shown for DEFAULT_FRAME_DELAY, like browsers do.
+const MIN_FRAME_DELAY: Duration = Duration::from_millis(20);
+const DEFAULT_FRAME_DELAY: Duration = Duration::from_millis(100);
+
+/// Repeating frames to a common frame rate stops at this many frames,
+/// longer animations use their average delay instead.
+const MAX_FRAMES: usize = 1000;
+
+/// An animated gif or webp as slint plays it, every frame is shown for frame_ms.
+///
+/// Frames that are shown longer than others are repeated, the repeats share their pixels.
+#[derive(Clone, Default)]
+pub struct Animation {
+    pub frames: ModelRc<Image>,
+    pub frame_ms: i32,
+    /// Decoded RGBA bytes of the distinct frames.
+    pub bytes: usize,
+}
+
+impl Animation {
+    /// None for still images, and for formats that can not be animated.
+    pub fn decode(bytes: &[u8]) -> ImageResult<Option<Self>> {
+        let frames = match image::guess_format(bytes)? {
+            ImageFormat::Gif => GifDecoder::new(Cursor::new(bytes))?
+                .into_frames()
+                .collect_frames()?,
+            ImageFormat::WebP => {
+                let decoder = WebPDecoder::new(Cursor::new(bytes))?;
+                if !decoder.has_animation() {
+                    return Ok(None);
+                }
+                decoder.into_frames().collect_frames()?
+            }
+            _ => return Ok(None),
+        };
+
+        if frames.len() < 2 {
+            return Ok(None);
+        }
+        Ok(Some(Self::from_frames(frames)))
+    }
+
+    fn from_frames(frames: Vec<Frame>) -> Self {
+        let delays: Vec<u64> = frames.iter().map(frame_delay_ms).collect();
+
+        let mut frame_ms = delays.iter().copied().fold(0, gcd).max(1);
+        let mut repeats: Vec<u64> = delays.iter().map(|delay| delay / frame_ms).collect();
+        if repeats.iter().sum::<u64>() as usize > MAX_FRAMES {
+            frame_ms = delays.iter().sum::<u64>() / delays.len() as u64;
+            repeats = vec![1; delays.len()];
+        }
+
+        let mut bytes = 0;
+        let mut images = Vec::new();
+        for (frame, repeat) in frames.into_iter().zip(repeats) {
+            let buffer = frame.into_buffer();
+            bytes += buffer.as_raw().len();
+
+            let image = Image::from_rgba8(SharedPixelBuffer::<Rgba8Pixel>::clone_from_slice(
+                buffer.as_raw(),
+                buffer.width(),
+                buffer.height(),
+            ));
+            images.extend(std::iter::repeat_n(image, repeat as usize));
+        }
+
+        Self {
+            frames: ModelRc::from(Rc::new(VecModel::from(images))),
+            frame_ms: frame_ms as i32,
+            bytes,
+        }
+    }
+}
+
+fn frame_delay_ms(frame: &Frame) -> u64 {
+    let (numerator, denominator) = frame.delay().numer_denom_ms();
+    let delay = Duration::from_millis((numerator / denominator.max(1)) as u64);
+
+    if delay < MIN_FRAME_DELAY {
+        DEFAULT_FRAME_DELAY.as_millis() as u64
+    } else {
+        delay.as_millis() as u64
+    }
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
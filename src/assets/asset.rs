@@ -0,0 +1,216 @@
+use super::scale_factor::scale_factor;
+use crate::config::load_config::config;
+
+/// Kinds of assets, each is kept in its own folder of the cache.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AssetKind {
+    Avatar,
+    ChannelIcon,
+    GuildIcon,
+    Emoji,
+    Sticker,
+    /// Embed images, and previews of image and video attachments.
+    Preview,
+}
+
+impl AssetKind {
+    pub const ALL: [AssetKind; 6] = [
+        AssetKind::Avatar,
+        AssetKind::ChannelIcon,
+        AssetKind::GuildIcon,
+        AssetKind::Emoji,
+        AssetKind::Sticker,
+        AssetKind::Preview,
+    ];
+
+    pub fn folder(self) -> &'static str {
+        match self {
+            AssetKind::Avatar => "avatars",
+            AssetKind::ChannelIcon => "channel_icons",
+            AssetKind::GuildIcon => "guild_icons",
+            AssetKind::Emoji => "emojis",
+            AssetKind::Sticker => "stickers",
+            AssetKind::Preview => "previews",
+        }
+    }
+}
+
+/// Sizes in logical pixels that images are shown at,
+/// the size requested from the CDN is chosen from these and the scale factor.
+pub const LIST_IMAGE_SIZE: u32 = 30;
+pub const PROFILE_AVATAR_SIZE: u32 = 50;
+pub const GUILD_ICON_SIZE: u32 = 50;
+/// Emojis are shown at most 48px large, as jumbo emojis.
+const EMOJI_SIZE: u32 = 48;
+/// Stickers are shown 160px large.
+const STICKER_SIZE: u32 = 160;
+
+/// format_type of stickers, lottie stickers are vector animations that can not be shown.
+const STICKER_PNG: u64 = 1;
+const STICKER_APNG: u64 = 2;
+const STICKER_GIF: u64 = 4;
+
+/// The CDN serves powers of two in this range.
+const MIN_CDN_SIZE: u32 = 16;
+const MAX_CDN_SIZE: u32 = 4096;
+
+/// Number of default avatars, users without an avatar get one of them by their id.
+const DEFAULT_AVATARS: u64 = 6;
+
+/// An image from the discord CDN or media proxy, and where it is kept in the AssetCache.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Asset {
+    pub kind: AssetKind,
+    /// File name in the folder of the kind, unique for the url.
+    pub name: String,
+    pub url: String,
+    /// Width in pixels requested from the CDN, 0 if the url has no size.
+    pub size: u32,
+    /// Can have more than one frame.
+    pub animated: bool,
+}
+
+impl Asset {
+    /// The default avatar if the user has none, size is in logical pixels.
+    pub fn avatar(user_id: &str, hash: &str, size: u32) -> Option<Self> {
+        if hash.is_empty() {
+            return Self::default_avatar(user_id);
+        }
+        Self::cdn_icon(AssetKind::Avatar, "avatars", user_id, hash, size)
+    }
+
+    /// None if the group has no icon.
+    pub fn channel_icon(channel_id: &str, hash: &str, size: u32) -> Option<Self> {
+        Self::cdn_icon(
+            AssetKind::ChannelIcon,
+            "channel-icons",
+            channel_id,
+            hash,
+            size,
+        )
+    }
+
+    /// None if the guild has no icon.
+    pub fn guild_icon(guild_id: &str, hash: &str, size: u32) -> Option<Self> {
+        Self::cdn_icon(AssetKind::GuildIcon, "icons", guild_id, hash, size)
+    }
+
+    /// The still image of a custom emoji, or all frames of an animated one.
+    pub fn emoji(emoji_id: &str, animated: bool) -> Self {
+        let size = cdn_size(EMOJI_SIZE);
+
+        Self {
+            kind: AssetKind::Emoji,
+            name: if animated {
+                format!("{}_{}_animated.webp", emoji_id, size)
+            } else {
+                format!("{}_{}.webp", emoji_id, size)
+            },
+            url: format!(
+                "{}/emojis/{}.webp?size={}{}",
+                config().endpoints.cdn,
+                emoji_id,
+                size,
+                if animated { "&animated=true" } else { "" }
+            ),
+            size,
+            animated,
+        }
+    }
+
+    /// PNG and APNG stickers are served as png, of which only the first frame is shown,
+    /// GIF stickers with all their frames. None for lottie stickers.
+    pub fn sticker(sticker_id: &str, format_type: u64) -> Option<Self> {
+        let (extension, animated) = match format_type {
+            STICKER_PNG | STICKER_APNG => ("png", false),
+            STICKER_GIF => ("gif", true),
+            _ => return None,
+        };
+        let size = cdn_size(STICKER_SIZE);
+
+        Some(Self {
+            kind: AssetKind::Sticker,
+            name: format!("{}_{}.{}", sticker_id, size, extension),
+            url: format!(
+                "{}/stickers/{}.{}?size={}",
+                config().endpoints.cdn,
+                sticker_id,
+                extension,
+                size
+            ),
+            size,
+            animated,
+        })
+    }
+
+    /// An image from the media proxy, its format is detected from its content.
+    pub fn preview(url: &str) -> Self {
+        Self {
+            kind: AssetKind::Preview,
+            name: format!("{:016x}", fnv1a(url)),
+            url: url.to_string(),
+            size: 0,
+            animated: false,
+        }
+    }
+
+    /// Path relative to the cache folder, also the key in its index.
+    pub fn key(&self) -> String {
+        format!("{}/{}", self.kind.folder(), self.name)
+    }
+
+    /// Discord picks the default avatar from the account creation time in the user id.
+    /// They are only served as png.
+    fn default_avatar(user_id: &str) -> Option<Self> {
+        let index = (user_id.parse::<u64>().ok()? >> 22) % DEFAULT_AVATARS;
+
+        Some(Self {
+            kind: AssetKind::Avatar,
+            name: format!("default_{}.png", index),
+            url: format!("{}/embed/avatars/{}.png", config().endpoints.cdn, index),
+            size: 0,
+            animated: false,
+        })
+    }
+
+    /// Icons are requested as webp, those whose hash starts with a_ with all their frames.
+    /// Every size is its own file, so a changed scale factor downloads them again.
+    fn cdn_icon(kind: AssetKind, route: &str, id: &str, hash: &str, size: u32) -> Option<Self> {
+        if id.is_empty() || hash.is_empty() {
+            return None;
+        }
+
+        let size = cdn_size(size);
+        let animated = hash.starts_with("a_");
+
+        Some(Self {
+            kind,
+            name: format!("{}_{}_{}.webp", id, hash, size),
+            url: format!(
+                "{}/{}/{}/{}.webp?size={}{}",
+                config().endpoints.cdn,
+                route,
+                id,
+                hash,
+                size,
+                if animated { "&animated=true" } else { "" }
+            ),
+            size,
+            animated,
+        })
+    }
+}
+
+/// The smallest CDN size that is at least as large as the image is shown on screen.
+fn cdn_size(size: u32) -> u32 {
+    let pixels = (size as f32 * scale_factor()).ceil() as u32;
+    pixels.next_power_of_two().clamp(MIN_CDN_SIZE, MAX_CDN_SIZE)
+}
+
+/// 64 bit FNV-1a, unlike DefaultHasher it is the same in every build,
+/// so file names stay valid across restarts and updates.
+fn fnv1a(text: &str) -> u64 {
+    text.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
@@ -0,0 +1,341 @@
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+    io,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex, MutexGuard, PoisonError,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use futures_util::{
+    future::{BoxFuture, Shared, WeakShared},
+    FutureExt,
+};
+use once_cell::sync::Lazy;
+use slint::{Image, Rgba8Pixel, SharedPixelBuffer};
+use tokio::io::AsyncWriteExt;
+use tracing::{error, warn};
+
+use super::{
+    animation::Animation,
+    asset::{Asset, AssetKind},
+    asset_index::AssetIndex,
+    priority_semaphore::PrioritySemaphore,
+};
+use crate::{config::load_config::config, state::HTTP_CLIENT, utils::app_dirs::app_dirs};
+
+/// Eviction removes files until the cache is this fraction of its budget,
+/// so it does not run again for every new file.
+const EVICTION_TARGET: f64 = 0.9;
+
+/// Changes to the index are saved this long after the first unsaved change.
+const SAVE_DELAY: Duration = Duration::from_secs(5);
+
+const INDEX_FILE: &str = "index.json";
+
+/// Downloads running at once, more wait for a free slot.
+const MAX_PARALLEL_DOWNLOADS: usize = 6;
+
+pub static ASSET_CACHE: Lazy<AssetCache> =
+    Lazy::new(|| AssetCache::open(app_dirs().assets(), config().cache.assets_mb * 1024 * 1024));
+
+/// Unique names for temporary files.
+static TEMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Who is waiting for a download.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Priority {
+    /// Prefetching, like the icons of all guilds.
+    Background,
+    /// Shown right now, downloads before all background requests.
+    Visible,
+}
+
+/// The download of one asset, shared by everyone requesting it.
+/// The error is a String, so it can be cloned for each of them.
+type Download = Shared<BoxFuture<'static, Result<PathBuf, String>>>;
+
+struct InFlight {
+    /// Weak so the download is cancelled when every request for it is dropped.
+    download: WeakShared<BoxFuture<'static, Result<PathBuf, String>>>,
+    /// Raised when a visible request joins a background download.
+    visible: Arc<AtomicBool>,
+}
+
+/// Downloaded avatars, icons, emojis and previews on disk.
+///
+/// Files are written to a temporary file and renamed, so a file in the cache is always complete.
+/// When the cache grows over its budget the least recently used files are deleted.
+/// Concurrent requests for the same asset share one download,
+/// and at most MAX_PARALLEL_DOWNLOADS run at once with visible assets first.
+/// The index of files is saved in the cache folder, files that are not in it are deleted on startup.
+pub struct AssetCache {
+    root: PathBuf,
+    /// In bytes, changed with the config file.
+    budget: AtomicU64,
+    index: Mutex<AssetIndex>,
+    save_scheduled: AtomicBool,
+    /// Running downloads per asset key.
+    in_flight: Mutex<HashMap<String, InFlight>>,
+    slots: PrioritySemaphore,
+}
+
+impl AssetCache {
+    pub fn open(root: PathBuf, budget: u64) -> Self {
+        let mut index = AssetIndex::load(&root.join(INDEX_FILE));
+
+        // Files without a valid entry are leftovers of a crash, or of older versions.
+        let mut found = HashSet::new();
+        for kind in AssetKind::ALL {
+            let Ok(entries) = std::fs::read_dir(root.join(kind.folder())) else {
+                continue;
+            };
+
+            for entry in entries.flatten() {
+                let key = format!("{}/{}", kind.folder(), entry.file_name().to_string_lossy());
+                let size = entry.metadata().map(|v| v.len()).unwrap_or_default();
+
+                if index.is_valid(&key, size) {
+                    found.insert(key);
+                } else if let Err(e) = std::fs::remove_file(entry.path()) {
+                    warn!("Failed to remove {}: {}", entry.path().display(), e);
+                }
+            }
+        }
+        index.retain(|key| found.contains(key));
+
+        Self {
+            root,
+            budget: AtomicU64::new(budget),
+            index: Mutex::new(index),
+            save_scheduled: AtomicBool::new(false),
+            in_flight: Mutex::new(HashMap::new()),
+            slots: PrioritySemaphore::new(MAX_PARALLEL_DOWNLOADS),
+        }
+    }
+
+    /// The file of the asset if it is cached, it counts as used.
+    pub fn path(&self, asset: &Asset) -> Option<PathBuf> {
+        let key = asset.key();
+        self.index()
+            .touch(&key, now_ms())
+            .then(|| self.root.join(key))
+    }
+
+    /// A smaller budget evicts files with the next download.
+    pub fn set_budget(&self, budget: u64) {
+        self.budget.store(budget, Ordering::Relaxed);
+    }
+
+    /// Whether the asset is cached, without counting as used.
+    pub fn contains(&self, asset: &Asset) -> bool {
+        self.index().contains(&asset.key())
+    }
+
+    /// The decoded image, empty if it is not cached or invalid.
+    pub fn load_image(&self, asset: &Asset) -> Image {
+        let Some(bytes) = self.path(asset).and_then(|path| std::fs::read(path).ok()) else {
+            return Image::default();
+        };
+        let Ok(image) = image::load_from_memory(&bytes) else {
+            return Image::default();
+        };
+
+        let image = image.to_rgba8();
+        Image::from_rgba8(SharedPixelBuffer::<Rgba8Pixel>::clone_from_slice(
+            image.as_raw(),
+            image.width(),
+            image.height(),
+        ))
+    }
+
+    /// All frames of an animated image, None if it is not cached or not animated.
+    pub fn load_animation(&self, asset: &Asset) -> Option<Animation> {
+        let bytes = std::fs::read(self.path(asset)?).ok()?;
+        match Animation::decode(&bytes) {
+            Ok(animation) => animation,
+            Err(e) => {
+                warn!("Failed to decode {}: {}", asset.key(), e);
+                None
+            }
+        }
+    }
+
+    /// Downloads the asset unless it is cached, and returns its file.
+    ///
+    /// Dropping the returned future cancels the download,
+    /// unless someone else is waiting for the same asset.
+    pub async fn fetch(
+        &'static self,
+        asset: &Asset,
+        priority: Priority,
+    ) -> Result<PathBuf, Box<dyn Error + Send + Sync>> {
+        if let Some(path) = self.path(asset) {
+            return Ok(path);
+        }
+
+        Ok(self.join_download(asset, priority).await?)
+    }
+
+    /// The running download of the asset, or a new one.
+    fn join_download(&'static self, asset: &Asset, priority: Priority) -> Download {
+        let key = asset.key();
+        let mut in_flight = self.in_flight();
+
+        if let Some(running) = in_flight.get(&key) {
+            if let Some(download) = running.download.upgrade() {
+                if priority == Priority::Visible {
+                    running.visible.store(true, Ordering::Relaxed);
+                }
+                return download;
+            }
+        }
+
+        let visible = Arc::new(AtomicBool::new(priority == Priority::Visible));
+        let download = self
+            .download(asset.clone(), visible.clone())
+            .boxed()
+            .shared();
+
+        if let Some(weak) = download.downgrade() {
+            in_flight.insert(
+                key,
+                InFlight {
+                    download: weak,
+                    visible,
+                },
+            );
+        }
+        download
+    }
+
+    async fn download(
+        &'static self,
+        asset: Asset,
+        visible: Arc<AtomicBool>,
+    ) -> Result<PathBuf, String> {
+        let result = async {
+            let _slot = self.slots.acquire(visible).await;
+
+            let bytes = HTTP_CLIENT
+                .get(&asset.url)
+                .send()
+                .await?
+                .error_for_status()?
+                .bytes()
+                .await?;
+
+            let key = asset.key();
+            let path = self.root.join(&key);
+            write_atomic(&path, &bytes).await?;
+
+            let evicted = {
+                let mut index = self.index();
+                index.insert(key.clone(), bytes.len() as u64, now_ms());
+
+                let budget = self.budget.load(Ordering::Relaxed);
+                if index.total_size() > budget {
+                    index.evict((budget as f64 * EVICTION_TARGET) as u64, &key)
+                } else {
+                    Vec::new()
+                }
+            };
+            for key in evicted {
+                let _ = tokio::fs::remove_file(self.root.join(key)).await;
+            }
+
+            self.schedule_save();
+            Ok::<_, Box<dyn Error + Send + Sync>>(path)
+        }
+        .await;
+
+        self.in_flight().remove(&asset.key());
+        result.map_err(|e| e.to_string())
+    }
+
+    /// Writes the index if it changed since it was last saved.
+    pub fn save(&self) -> io::Result<()> {
+        let json = {
+            let mut index = self.index();
+            if !index.dirty {
+                return Ok(());
+            }
+            index.dirty = false;
+            serde_json::to_vec(&*index)?
+        };
+
+        std::fs::create_dir_all(&self.root)?;
+        let temp_path = temp_path(&self.root.join(INDEX_FILE));
+        std::fs::write(&temp_path, json)?;
+        std::fs::rename(&temp_path, self.root.join(INDEX_FILE))
+    }
+
+    /// Saves the index a while after it changed, so a crash loses little of it
+    /// without writing it for every download.
+    fn schedule_save(&'static self) {
+        if self.save_scheduled.swap(true, Ordering::Relaxed) {
+            return;
+        }
+
+        tokio::spawn(async move {
+            tokio::time::sleep(SAVE_DELAY).await;
+            self.save_scheduled.store(false, Ordering::Relaxed);
+
+            if let Err(e) = tokio::task::spawn_blocking(|| self.save()).await? {
+                error!("Failed to save the asset index: {}", e);
+            }
+            Ok::<_, tokio::task::JoinError>(())
+        });
+    }
+
+    fn index(&self) -> MutexGuard<'_, AssetIndex> {
+        self.index.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn in_flight(&self) -> MutexGuard<'_, HashMap<String, InFlight>> {
+        self.in_flight
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Writes a temporary file next to path and renames it, readers never see a partial file.
+async fn write_atomic(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    if let Some(folder) = path.parent() {
+        tokio::fs::create_dir_all(folder).await?;
+    }
+
+    let temp_path = temp_path(path);
+    let result = async {
+        let mut file = tokio::fs::File::create(&temp_path).await?;
+        file.write_all(bytes).await?;
+        file.sync_all().await?;
+        tokio::fs::rename(&temp_path, path).await
+    }
+    .await;
+
+    if result.is_err() {
+        let _ = tokio::fs::remove_file(&temp_path).await;
+    }
+    result
+}
+
+fn temp_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(
+        ".{}-{}.tmp",
+        std::process::id(),
+        TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    PathBuf::from(name)
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|v| v.as_millis() as u64)
+        .unwrap_or_default()
+}
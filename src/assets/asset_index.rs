@@ -0,0 +1,119 @@
+use std::{collections::HashMap, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct IndexEntry {
+    /// In bytes.
+    size: u64,
+    /// Unix timestamp in milliseconds of the last time the asset was loaded.
+    last_used: u64,
+}
+
+/// The files in the asset cache with their size and when they were last used,
+/// saved next to them so the least recently used files can be evicted after restarts.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct AssetIndex {
+    /// By path relative to the cache folder.
+    entries: HashMap<String, IndexEntry>,
+    #[serde(skip)]
+    total_size: u64,
+    /// Changed since it was last saved.
+    #[serde(skip)]
+    pub dirty: bool,
+}
+
+impl AssetIndex {
+    /// An empty index if the file is missing or invalid, the cache is then rebuilt.
+    pub fn load(path: &Path) -> Self {
+        let mut index: Self = std::fs::read(path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default();
+
+        index.total_size = index.entries.values().map(|v| v.size).sum();
+        index
+    }
+
+    pub fn total_size(&self) -> u64 {
+        self.total_size
+    }
+
+    /// Whether the file is complete, a file with another size was not written by the cache.
+    pub fn is_valid(&self, key: &str, size: u64) -> bool {
+        self.entries.get(key).is_some_and(|v| v.size == size)
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.entries.contains_key(key)
+    }
+
+    /// Keeps the entries for which keep returns true.
+    pub fn retain(&mut self, mut keep: impl FnMut(&str) -> bool) {
+        let count = self.entries.len();
+        self.entries.retain(|key, _| keep(key));
+
+        if self.entries.len() != count {
+            self.total_size = self.entries.values().map(|v| v.size).sum();
+            self.dirty = true;
+        }
+    }
+
+    /// Marks an asset as used, returns false if it is not in the index.
+    pub fn touch(&mut self, key: &str, now: u64) -> bool {
+        let Some(entry) = self.entries.get_mut(key) else {
+            return false;
+        };
+
+        entry.last_used = now;
+        self.dirty = true;
+        true
+    }
+
+    pub fn insert(&mut self, key: String, size: u64, now: u64) {
+        let entry = IndexEntry {
+            size,
+            last_used: now,
+        };
+        if let Some(old) = self.entries.insert(key, entry) {
+            self.total_size -= old.size;
+        }
+
+        self.total_size += size;
+        self.dirty = true;
+    }
+
+    /// Removes the least recently used entries until the total size is at most target,
+    /// except keep. Returns the keys of the removed entries, their files are deleted by the caller.
+    pub fn evict(&mut self, target: u64, keep: &str) -> Vec<String> {
+        if self.total_size <= target {
+            return Vec::new();
+        }
+
+        let mut by_age: Vec<(&String, &IndexEntry)> = self
+            .entries
+            .iter()
+            .filter(|(key, _)| key.as_str() != keep)
+            .collect();
+        by_age.sort_by_key(|(_, entry)| entry.last_used);
+
+        let mut size = self.total_size;
+        let mut evicted = Vec::new();
+
+        for (key, entry) in by_age {
+            if size <= target {
+                break;
+            }
+            size -= entry.size;
+            evicted.push(key.clone());
+        }
+
+        for key in &evicted {
+            self.entries.remove(key);
+        }
+        self.total_size = size;
+        self.dirty = true;
+
+        evicted
+    }
+}
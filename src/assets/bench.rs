@@ -0,0 +1,92 @@
+//! Cost of loading the avatars of the channel list on an update, with 500 private channels.
+//!
+//! cargo test --release bench_channel_list -- --ignored --nocapture
+//!
+//! It only prints the timings, the crate has no library for a benches/ target to link.
+
+use std::{
+    hint::black_box,
+    io::Cursor,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use image::{ImageFormat, Rgba, RgbaImage};
+
+use super::{
+    asset::{Asset, LIST_IMAGE_SIZE},
+    asset_cache::AssetCache,
+    asset_index::AssetIndex,
+    image_cache::ImageCache,
+};
+
+const CHANNELS: usize = 500;
+
+const UPDATES: u32 = 20;
+
+#[test]
+#[ignore]
+fn bench_channel_list_avatars() {
+    let root = std::env::temp_dir().join(format!("asset-bench-{}", std::process::id()));
+    let assets = write_avatars(&root);
+    let cache = AssetCache::open(root.clone(), u64::MAX);
+
+    // Before, every update read and decoded the file of every row.
+    let before = per_update(|| {
+        for asset in &assets {
+            black_box(cache.load_image(asset));
+        }
+    });
+
+    let mut images = ImageCache::new(64 * 1024 * 1024);
+    // Decodes once, the updates measured below only clone.
+    for asset in &assets {
+        images.get(&cache, asset);
+    }
+    let after = per_update(|| {
+        for asset in &assets {
+            black_box(images.get(&cache, asset));
+        }
+    });
+
+    println!(
+        "{} channels, per update: decoding {:?}, image cache {:?}",
+        CHANNELS, before, after
+    );
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+fn per_update(mut update: impl FnMut()) -> Duration {
+    let start = Instant::now();
+    for _ in 0..UPDATES {
+        update();
+    }
+    start.elapsed() / UPDATES
+}
+
+/// A 64px avatar png per channel, indexed like downloaded ones.
+fn write_avatars(root: &Path) -> Vec<Asset> {
+    let mut index = AssetIndex::default();
+    let mut assets = Vec::new();
+
+    for user in 0..CHANNELS {
+        let asset = Asset::avatar(&user.to_string(), "0123456789abcdef", LIST_IMAGE_SIZE).unwrap();
+
+        let image = RgbaImage::from_fn(64, 64, |x, y| {
+            Rgba([(x * 4) as u8, (y * 4) as u8, user as u8, 255])
+        });
+        let mut png = Cursor::new(Vec::new());
+        image.write_to(&mut png, ImageFormat::Png).unwrap();
+        let png = png.into_inner();
+
+        let path = root.join(asset.key());
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, &png).unwrap();
+        index.insert(asset.key(), png.len() as u64, 0);
+
+        assets.push(asset);
+    }
+
+    std::fs::write(root.join("index.json"), serde_json::to_vec(&index).unwrap()).unwrap();
+    assets
+}
@@ -0,0 +1,165 @@
+use std::{cell::RefCell, collections::HashMap};
+
+use slint::Image;
+
+use super::{
+    animation::Animation,
+    asset::Asset,
+    asset_cache::{AssetCache, ASSET_CACHE},
+};
+use crate::config::load_config::config;
+
+thread_local! {
+    /// slint images are not Send, they are only decoded and shown on the UI thread.
+    static IMAGE_CACHE: RefCell<ImageCache> = RefCell::new(ImageCache::new(
+        config().cache.images_mb as usize * 1024 * 1024,
+    ));
+}
+
+/// The decoded image of a cached asset, empty if it is not downloaded yet.
+///
+/// Only the first call reads and decodes the file,
+/// later calls clone the shared pixel buffer.
+pub fn load_image(asset: &Asset) -> Image {
+    IMAGE_CACHE.with(|cache| cache.borrow_mut().get(&ASSET_CACHE, asset))
+}
+
+/// The frames of a cached animated asset, empty if it is not downloaded or not animated.
+pub fn load_animation(asset: &Asset) -> Animation {
+    IMAGE_CACHE.with(|cache| cache.borrow_mut().get_animation(&ASSET_CACHE, asset))
+}
+
+/// Drops images right away if the new budget is smaller.
+pub fn set_image_cache_budget(budget: usize) {
+    IMAGE_CACHE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        cache.budget = budget;
+        cache.evict();
+    })
+}
+
+#[derive(Clone)]
+enum Decoded {
+    Still(Image),
+    Animated(Animation),
+}
+
+struct Entry {
+    decoded: Decoded,
+    /// Decoded RGBA bytes.
+    bytes: usize,
+    /// Value of the use counter when the image was last returned.
+    last_used: u64,
+}
+
+/// Decoded avatars, icons and animations by asset key and size.
+///
+/// When the pixels of all images grow over the budget the least recently used are dropped,
+/// rows that still show them keep their copy.
+pub struct ImageCache {
+    /// In bytes.
+    budget: usize,
+    used: usize,
+    /// By asset key, size, and whether all frames were decoded.
+    entries: HashMap<(String, u32, bool), Entry>,
+    uses: u64,
+}
+
+impl ImageCache {
+    pub fn new(budget: usize) -> Self {
+        Self {
+            budget,
+            used: 0,
+            entries: HashMap::new(),
+            uses: 0,
+        }
+    }
+
+    /// The image from memory, or decoded from the asset cache.
+    pub fn get(&mut self, source: &AssetCache, asset: &Asset) -> Image {
+        let decoded = self.get_or_decode(asset, false, || {
+            let image = source.load_image(asset);
+            let size = image.size();
+            // Not downloaded yet, it is loaded again when it is ready.
+            (size.width > 0).then(|| {
+                let bytes = size.width as usize * size.height as usize * 4;
+                (Decoded::Still(image), bytes)
+            })
+        });
+
+        match decoded {
+            Some(Decoded::Still(image)) => image,
+            _ => Image::default(),
+        }
+    }
+
+    /// The animation from memory, or decoded from the asset cache.
+    pub fn get_animation(&mut self, source: &AssetCache, asset: &Asset) -> Animation {
+        if !asset.animated {
+            return Animation::default();
+        }
+
+        let decoded = self.get_or_decode(asset, true, || {
+            let animation = source.load_animation(asset)?;
+            let bytes = animation.bytes;
+            Some((Decoded::Animated(animation), bytes))
+        });
+
+        match decoded {
+            Some(Decoded::Animated(animation)) => animation,
+            _ => Animation::default(),
+        }
+    }
+
+    fn get_or_decode(
+        &mut self,
+        asset: &Asset,
+        animated: bool,
+        decode: impl FnOnce() -> Option<(Decoded, usize)>,
+    ) -> Option<Decoded> {
+        self.uses += 1;
+
+        let key = (asset.key(), asset.size, animated);
+        if let Some(entry) = self.entries.get_mut(&key) {
+            entry.last_used = self.uses;
+            return Some(entry.decoded.clone());
+        }
+
+        let (decoded, bytes) = decode()?;
+        self.used += bytes;
+        self.entries.insert(
+            key,
+            Entry {
+                decoded: decoded.clone(),
+                bytes,
+                last_used: self.uses,
+            },
+        );
+        self.evict();
+
+        Some(decoded)
+    }
+
+    /// Drops the least recently used images until the rest fit in the budget.
+    fn evict(&mut self) {
+        if self.used <= self.budget {
+            return;
+        }
+
+        let mut keys: Vec<_> = self
+            .entries
+            .iter()
+            .map(|(key, entry)| (entry.last_used, key.clone()))
+            .collect();
+        keys.sort_unstable();
+
+        for (_, key) in keys {
+            if self.used <= self.budget {
+                break;
+            }
+            if let Some(entry) = self.entries.remove(&key) {
+                self.used -= entry.bytes;
+            }
+        }
+    }
+}
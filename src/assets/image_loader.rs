@@ -0,0 +1,141 @@
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    error::Error,
+    future::Future,
+    marker::PhantomData,
+};
+
+use slint::Image;
+use tokio::runtime::Handle;
+use tracing::warn;
+
+use super::asset_cache::Priority;
+use crate::state::{CustomEmoji, EmbedMedia, Sticker, UiUpdate, UpdateSender};
+
+/// An image that is downloaded the first time it is shown.
+pub trait LoadableImage: Clone + Send + 'static {
+    /// Identifies the image in the loader, and in the update sent when it is downloaded.
+    fn image_key(&self) -> &str;
+
+    /// The decoded image, empty if it is not downloaded yet.
+    fn load_image(&self) -> Image;
+
+    fn get_image(
+        &self,
+        priority: Priority,
+    ) -> impl Future<Output = Result<(), Box<dyn Error + Send + Sync>>> + Send;
+}
+
+/// Loads the images of one kind, missing ones are downloaded in the background
+/// and ready is sent with their key when they are done.
+///
+/// Used from the UI thread, by the message list.
+pub struct ImageLoader<T> {
+    runtime: Handle,
+    update_sender: UpdateSender,
+    /// Update sent with the key of a downloaded image, like EmojiReady.
+    ready: fn(String) -> UiUpdate,
+    /// Decoded images by key.
+    images: RefCell<HashMap<String, Image>>,
+    /// Keys of images that are downloading, or failed to download.
+    requested: RefCell<HashSet<String>>,
+    kind: PhantomData<fn(&T)>,
+}
+
+impl<T: LoadableImage> ImageLoader<T> {
+    pub fn new(
+        runtime: Handle,
+        update_sender: UpdateSender,
+        ready: fn(String) -> UiUpdate,
+    ) -> Self {
+        Self {
+            runtime,
+            update_sender,
+            ready,
+            images: RefCell::new(HashMap::new()),
+            requested: RefCell::new(HashSet::new()),
+            kind: PhantomData,
+        }
+    }
+
+    /// The image, empty until it is downloaded.
+    pub fn load(&self, item: &T) -> Image {
+        if let Some(image) = self.images.borrow().get(item.image_key()) {
+            return image.clone();
+        }
+
+        let image = item.load_image();
+        let key = item.image_key().to_string();
+
+        if image.size().width > 0 {
+            self.images.borrow_mut().insert(key, image.clone());
+        } else if self.requested.borrow_mut().insert(key.clone()) {
+            let item = item.clone();
+            let update_sender = self.update_sender.clone();
+            let ready = self.ready;
+
+            self.runtime.spawn(async move {
+                match item.get_image(Priority::Visible).await {
+                    Ok(()) => {
+                        let _ = update_sender.send(ready(key));
+                    }
+                    Err(e) => warn!("Failed to download {}: {}", key, e),
+                }
+            });
+        }
+
+        image
+    }
+}
+
+impl LoadableImage for CustomEmoji {
+    fn image_key(&self) -> &str {
+        &self.id
+    }
+
+    fn load_image(&self) -> Image {
+        CustomEmoji::load_image(self)
+    }
+
+    fn get_image(
+        &self,
+        priority: Priority,
+    ) -> impl Future<Output = Result<(), Box<dyn Error + Send + Sync>>> + Send {
+        CustomEmoji::get_image(self, priority)
+    }
+}
+
+impl LoadableImage for EmbedMedia {
+    fn image_key(&self) -> &str {
+        &self.url
+    }
+
+    fn load_image(&self) -> Image {
+        EmbedMedia::load_image(self)
+    }
+
+    fn get_image(
+        &self,
+        priority: Priority,
+    ) -> impl Future<Output = Result<(), Box<dyn Error + Send + Sync>>> + Send {
+        EmbedMedia::get_image(self, priority)
+    }
+}
+
+impl LoadableImage for Sticker {
+    fn image_key(&self) -> &str {
+        &self.id
+    }
+
+    fn load_image(&self) -> Image {
+        Sticker::load_image(self)
+    }
+
+    fn get_image(
+        &self,
+        priority: Priority,
+    ) -> impl Future<Output = Result<(), Box<dyn Error + Send + Sync>>> + Send {
+        Sticker::get_image(self, priority)
+    }
+}
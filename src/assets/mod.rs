@@ -0,0 +1,11 @@
+pub mod animation;
+pub mod asset;
+pub mod asset_cache;
+pub mod asset_index;
+pub mod image_cache;
+pub mod image_loader;
+pub mod priority_semaphore;
+pub mod scale_factor;
+
+#[cfg(test)]
+mod bench;
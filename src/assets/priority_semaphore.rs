@@ -0,0 +1,128 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, Mutex, MutexGuard, PoisonError,
+};
+
+use tokio::sync::oneshot;
+
+struct Waiter {
+    /// Order of arrival, earlier waiters of the same priority go first.
+    seq: u64,
+    /// Checked when a permit is handed out, so it can be raised while waiting.
+    urgent: Arc<AtomicBool>,
+    wake: oneshot::Sender<()>,
+}
+
+#[derive(Default)]
+struct State {
+    running: usize,
+    waiting: Vec<Waiter>,
+    next_seq: u64,
+}
+
+/// Limits how many tasks run at once, like tokio's Semaphore,
+/// but waiters that are urgent get the next permit before the others.
+pub struct PrioritySemaphore {
+    permits: usize,
+    state: Mutex<State>,
+}
+
+/// Held while running, the permit goes to the next waiter when it is dropped.
+pub struct Permit<'a> {
+    semaphore: &'a PrioritySemaphore,
+}
+
+impl PrioritySemaphore {
+    pub fn new(permits: usize) -> Self {
+        Self {
+            permits,
+            state: Mutex::new(State::default()),
+        }
+    }
+
+    pub async fn acquire(&self, urgent: Arc<AtomicBool>) -> Permit<'_> {
+        let (receiver, seq) = {
+            let mut state = self.state();
+            if state.running < self.permits {
+                state.running += 1;
+                return Permit { semaphore: self };
+            }
+
+            let (wake, receiver) = oneshot::channel();
+            let seq = state.next_seq;
+            state.next_seq += 1;
+            state.waiting.push(Waiter { seq, urgent, wake });
+            (receiver, seq)
+        };
+
+        // Dropped before the receiver if the task is cancelled while waiting.
+        let mut waiting = Waiting {
+            semaphore: self,
+            seq,
+            woken: false,
+        };
+        let _ = receiver.await;
+        waiting.woken = true;
+
+        Permit { semaphore: self }
+    }
+
+    /// Hands the permit of a finished task to the most urgent waiter.
+    fn release(&self) {
+        let mut state = self.state();
+
+        while let Some(index) = next_waiter(&state.waiting) {
+            let waiter = state.waiting.swap_remove(index);
+            if waiter.wake.send(()).is_ok() {
+                return;
+            }
+        }
+
+        state.running -= 1;
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        self.semaphore.release();
+    }
+}
+
+struct Waiting<'a> {
+    semaphore: &'a PrioritySemaphore,
+    seq: u64,
+    woken: bool,
+}
+
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        if self.woken {
+            return;
+        }
+
+        let mut state = self.semaphore.state();
+        match state.waiting.iter().position(|v| v.seq == self.seq) {
+            Some(index) => {
+                state.waiting.swap_remove(index);
+            }
+            // The permit was handed to this waiter as it was cancelled, pass it on.
+            None => {
+                drop(state);
+                self.semaphore.release();
+            }
+        }
+    }
+}
+
+/// Urgent waiters first, then in order of arrival.
+fn next_waiter(waiting: &[Waiter]) -> Option<usize> {
+    waiting
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, waiter)| (!waiter.urgent.load(Ordering::Relaxed), waiter.seq))
+        .map(|(index, _)| index)
+}
@@ -0,0 +1,15 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+
+/// Physical pixels per logical pixel of the window, as f32 bits.
+static SCALE_FACTOR: AtomicU32 = AtomicU32::new(1.0f32.to_bits());
+
+/// Set by the UI when the window is created and when it moves to a screen with another scale.
+pub fn set_scale_factor(scale_factor: f32) {
+    if scale_factor.is_finite() && scale_factor > 0.0 {
+        SCALE_FACTOR.store(scale_factor.to_bits(), Ordering::Relaxed);
+    }
+}
+
+pub fn scale_factor() -> f32 {
+    f32::from_bits(SCALE_FACTOR.load(Ordering::Relaxed))
+}
@@ -0,0 +1,38 @@
+use std::error::Error;
+
+use reqwest::StatusCode;
+use serde_json::Value;
+use tracing::instrument;
+
+use crate::{
+    config::load_config::config,
+    messages::parse_message::parse_user,
+    state::{User, HTTP_CLIENT},
+};
+
+/// GET /users/@me
+///
+/// Checks the token before it is saved or used to connect.
+#[instrument(
+    level = "debug",
+    name = "request",
+    skip_all,
+    fields(endpoint = "current_user")
+)]
+pub async fn fetch_current_user(
+    authorization_token: &str,
+) -> Result<User, Box<dyn Error + Send + Sync>> {
+    let url = format!("{}/users/@me", config().endpoints.api);
+
+    let response = HTTP_CLIENT
+        .get(&url)
+        .header("Authorization", authorization_token)
+        .send()
+        .await?;
+    if response.status() == StatusCode::UNAUTHORIZED {
+        return Err("The token is not valid".into());
+    }
+
+    let json = response.error_for_status()?.json::<Value>().await?;
+    Ok(parse_user(&json))
+}
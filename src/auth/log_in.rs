@@ -0,0 +1,294 @@
+use std::{env, error::Error};
+
+use super::{
+    fetch_current_user::fetch_current_user,
+    password_login::{post_login, post_mfa_code, PasswordLogin},
+    token::Token,
+    token_store::{has_saved_token, load_tokens, save_token},
+};
+use crate::{
+    assets::{asset::PROFILE_AVATAR_SIZE, asset_cache::Priority},
+    config::load_config::config,
+    sessions::sessions::{Session, Sessions},
+    state::{AppState, LoginStep, UiUpdate, UpdateSender},
+    websocket::websocket::spawn_connection,
+};
+
+/// Passphrases shorter than this are rejected when saving a token.
+const MIN_PASSPHRASE_LENGTH: usize = 8;
+
+/// Picks the first step of the login screen of the first session.
+///
+/// DISCORD_TOKEN in the environment or .env skips the login screen and connects right away.
+pub fn start_login(sessions: &Sessions) {
+    let Session {
+        app_state,
+        update_sender,
+        ..
+    } = &sessions.active();
+    let token = env::var("DISCORD_TOKEN")
+        .map(|v| Token::new(&v))
+        .unwrap_or_default();
+
+    let mut app_data = app_state.blocking_write();
+    if !token.is_empty() {
+        app_data.token = token;
+        app_data.login.step = LoginStep::Done;
+        drop(app_data);
+
+        spawn_connection(app_state.clone(), update_sender.clone());
+        return;
+    }
+
+    app_data.login.step = if has_saved_token() {
+        LoginStep::Unlock
+    } else {
+        LoginStep::Token
+    };
+}
+
+/// Switches between the forms, like from the token to email and password.
+pub fn show_login_step(app_state: &AppState, update_sender: &UpdateSender, step: LoginStep) {
+    let mut app_data = app_state.blocking_write();
+    if app_data.login.busy || app_data.login.step == LoginStep::Done {
+        return;
+    }
+    app_data.login.step = step;
+    app_data.login.error = None;
+    drop(app_data);
+
+    let _ = update_sender.send(UiUpdate::LoginChanged);
+}
+
+/// Decrypts the saved tokens and connects with the first one,
+/// the other accounts are logged in as background sessions.
+pub async fn unlock_saved_token(
+    sessions: Sessions,
+    app_state: AppState,
+    update_sender: UpdateSender,
+    passphrase: String,
+) {
+    begin(&app_state, &update_sender).await;
+
+    let result = tokio::task::spawn_blocking(move || load_tokens(&passphrase))
+        .await
+        .map_err(|e| e.into())
+        .and_then(|v| v);
+    let result = match result {
+        Ok(tokens) => {
+            let mut tokens = tokens.into_iter();
+            match tokens.next() {
+                Some(token) => {
+                    for token in tokens {
+                        tokio::spawn(restore_session(sessions.add(), token));
+                    }
+                    accept_token(&app_state, &update_sender, token).await
+                }
+                None => Err("No account is saved".into()),
+            }
+        }
+        Err(e) => Err(e),
+    };
+
+    match result {
+        Ok(()) => connect(&app_state, &update_sender).await,
+        Err(e) => fail(&app_state, &update_sender, e).await,
+    }
+}
+
+/// Checks a pasted token, then asks for a passphrase to save it with.
+pub async fn log_in_with_token(app_state: AppState, update_sender: UpdateSender, token: String) {
+    begin(&app_state, &update_sender).await;
+
+    let token = Token::new(&token);
+    if token.is_empty() {
+        fail(&app_state, &update_sender, "Enter a token".into()).await;
+        return;
+    }
+
+    let result = accept_token(&app_state, &update_sender, token).await;
+    finish_step(&app_state, &update_sender, result, LoginStep::Save).await;
+}
+
+/// Logs in with email and password, asks for the MFA code if the account has one.
+pub async fn log_in_with_password(
+    app_state: AppState,
+    update_sender: UpdateSender,
+    email: String,
+    password: String,
+) {
+    begin(&app_state, &update_sender).await;
+
+    let result = match post_login(email.trim(), &password).await {
+        Ok(PasswordLogin::Token(token)) => accept_token(&app_state, &update_sender, token)
+            .await
+            .map(|_| LoginStep::Save),
+        Ok(PasswordLogin::Mfa(ticket)) => {
+            app_state.write().await.login.mfa_ticket = ticket;
+            Ok(LoginStep::Mfa)
+        }
+        Err(e) => Err(e),
+    };
+
+    match result {
+        Ok(step) => finish_step(&app_state, &update_sender, Ok(()), step).await,
+        Err(e) => fail(&app_state, &update_sender, e).await,
+    }
+}
+
+/// Sends the code of the authenticator app with the ticket of the password login.
+pub async fn submit_mfa_code(app_state: AppState, update_sender: UpdateSender, code: String) {
+    begin(&app_state, &update_sender).await;
+
+    let ticket = app_state.read().await.login.mfa_ticket.clone();
+    let result = match post_mfa_code(&ticket, &code).await {
+        Ok(token) => accept_token(&app_state, &update_sender, token).await,
+        Err(e) => Err(e),
+    };
+
+    finish_step(&app_state, &update_sender, result, LoginStep::Save).await;
+}
+
+/// Encrypts the new token with the passphrase, then connects.
+pub async fn save_login(
+    app_state: AppState,
+    update_sender: UpdateSender,
+    passphrase: String,
+    repeated: String,
+) {
+    if passphrase != repeated {
+        fail(
+            &app_state,
+            &update_sender,
+            "The passphrases are not the same".into(),
+        )
+        .await;
+        return;
+    }
+    if passphrase.chars().count() < MIN_PASSPHRASE_LENGTH {
+        let error = format!("Use at least {} characters", MIN_PASSPHRASE_LENGTH);
+        fail(&app_state, &update_sender, error.into()).await;
+        return;
+    }
+
+    begin(&app_state, &update_sender).await;
+
+    let app_data = app_state.read().await;
+    let token = app_data.token.clone();
+    let user_id = app_data
+        .current_user
+        .as_ref()
+        .map(|v| v.id.clone())
+        .unwrap_or_default();
+    drop(app_data);
+    let result = tokio::task::spawn_blocking(move || save_token(&user_id, &token, &passphrase))
+        .await
+        .map_err(|e| e.into())
+        .and_then(|v| v);
+
+    match result {
+        Ok(()) => connect(&app_state, &update_sender).await,
+        Err(e) => fail(&app_state, &update_sender, e).await,
+    }
+}
+
+/// Connects without saving the token, the login screen is shown again on the next start.
+pub async fn skip_saving_login(app_state: AppState, update_sender: UpdateSender) {
+    connect(&app_state, &update_sender).await;
+}
+
+/// Validates the token with GET /users/@me and keeps it with the user it belongs to.
+async fn accept_token(
+    app_state: &AppState,
+    update_sender: &UpdateSender,
+    token: Token,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let user = fetch_current_user(&token).await?;
+
+    let mut app_data = app_state.write().await;
+    app_data.token = token;
+    app_data.set_current_user(user.clone());
+    drop(app_data);
+
+    let _ = update_sender.send(UiUpdate::CurrentUserChanged);
+    if user
+        .get_avatar(PROFILE_AVATAR_SIZE, Priority::Visible)
+        .await
+        .is_ok()
+    {
+        let _ = update_sender.send(UiUpdate::AvatarReady(user.id));
+    }
+    Ok(())
+}
+
+/// Logs a saved account in as a background session,
+/// it connects now or when it is shown, see accounts.keep_background_connected.
+async fn restore_session(session: Session, token: Token) {
+    let Session {
+        app_state,
+        update_sender,
+        ..
+    } = &session;
+
+    if let Err(e) = accept_token(app_state, update_sender, token).await {
+        fail(app_state, update_sender, e).await;
+        return;
+    }
+
+    app_state.write().await.login.step = LoginStep::Done;
+    if config().accounts.keep_background_connected {
+        spawn_connection(app_state.clone(), update_sender.clone());
+    }
+}
+
+async fn begin(app_state: &AppState, update_sender: &UpdateSender) {
+    let mut app_data = app_state.write().await;
+    app_data.login.busy = true;
+    app_data.login.error = None;
+    drop(app_data);
+
+    let _ = update_sender.send(UiUpdate::LoginChanged);
+}
+
+async fn finish_step(
+    app_state: &AppState,
+    update_sender: &UpdateSender,
+    result: Result<(), Box<dyn Error + Send + Sync>>,
+    next: LoginStep,
+) {
+    if let Err(e) = result {
+        fail(app_state, update_sender, e).await;
+        return;
+    }
+
+    let mut app_data = app_state.write().await;
+    app_data.login.busy = false;
+    app_data.login.step = next;
+    drop(app_data);
+
+    let _ = update_sender.send(UiUpdate::LoginChanged);
+}
+
+async fn fail(
+    app_state: &AppState,
+    update_sender: &UpdateSender,
+    error: Box<dyn Error + Send + Sync>,
+) {
+    let mut app_data = app_state.write().await;
+    app_data.login.busy = false;
+    app_data.login.error = Some(error.to_string());
+    drop(app_data);
+
+    let _ = update_sender.send(UiUpdate::LoginChanged);
+}
+
+/// Closes the login screen and connects to the gateway with the token.
+async fn connect(app_state: &AppState, update_sender: &UpdateSender) {
+    let mut app_data = app_state.write().await;
+    app_data.login = Default::default();
+    app_data.login.step = LoginStep::Done;
+    drop(app_data);
+
+    let _ = update_sender.send(UiUpdate::LoginChanged);
+    spawn_connection(app_state.clone(), update_sender.clone());
+}
@@ -0,0 +1,8 @@
+pub mod fetch_current_user;
+pub mod log_in;
+pub mod password_login;
+pub mod token;
+pub mod token_store;
+
+#[cfg(test)]
+mod tests;
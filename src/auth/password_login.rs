@@ -0,0 +1,109 @@
+use std::error::Error;
+
+use serde_json::{json, Value};
+use tracing::instrument;
+
+use super::token::Token;
+use crate::{config::load_config::config, state::HTTP_CLIENT};
+
+/// What discord answers to an email and password.
+pub enum PasswordLogin {
+    Token(Token),
+    /// The account has two-factor authentication, the code is sent with this ticket.
+    Mfa(String),
+}
+
+/// POST /auth/login
+#[instrument(
+    level = "debug",
+    name = "request",
+    skip_all,
+    fields(endpoint = "login")
+)]
+pub async fn post_login(
+    email: &str,
+    password: &str,
+) -> Result<PasswordLogin, Box<dyn Error + Send + Sync>> {
+    let url = format!("{}/auth/login", config().endpoints.api);
+    let json = post_json(
+        &url,
+        json!({
+            "login": email,
+            "password": password,
+            "undelete": false,
+        }),
+    )
+    .await?;
+
+    if let Some(token) = json.get("token").and_then(|v| v.as_str()) {
+        return Ok(PasswordLogin::Token(Token::new(token)));
+    }
+
+    let ticket = json
+        .get("ticket")
+        .and_then(|v| v.as_str())
+        .filter(|_| json.get("mfa").and_then(|v| v.as_bool()) == Some(true))
+        .ok_or("Discord did not return a token")?;
+    if json.get("totp").and_then(|v| v.as_bool()) == Some(false) {
+        return Err(
+            "Only codes from an authenticator app are supported, log in with a token".into(),
+        );
+    }
+
+    Ok(PasswordLogin::Mfa(ticket.to_string()))
+}
+
+/// POST /auth/mfa/totp
+#[instrument(level = "debug", name = "request", skip_all, fields(endpoint = "mfa"))]
+pub async fn post_mfa_code(
+    ticket: &str,
+    code: &str,
+) -> Result<Token, Box<dyn Error + Send + Sync>> {
+    let url = format!("{}/auth/mfa/totp", config().endpoints.api);
+    let json = post_json(
+        &url,
+        json!({
+            "code": code.trim(),
+            "ticket": ticket,
+        }),
+    )
+    .await?;
+
+    json.get("token")
+        .and_then(|v| v.as_str())
+        .map(Token::new)
+        .ok_or_else(|| "Discord did not return a token".into())
+}
+
+/// The response, or the message of discords error.
+async fn post_json(url: &str, body: Value) -> Result<Value, Box<dyn Error + Send + Sync>> {
+    let response = HTTP_CLIENT.post(url).json(&body).send().await?;
+    let status = response.status();
+    let json = response.json::<Value>().await.unwrap_or_default();
+
+    if status.is_success() {
+        Ok(json)
+    } else {
+        Err(error_message(&json)
+            .unwrap_or_else(|| format!("Login failed with status {}", status))
+            .into())
+    }
+}
+
+/// Discord puts field errors in errors.{field}._errors, and asks for a captcha
+/// when it does not trust the client.
+fn error_message(json: &Value) -> Option<String> {
+    if json.get("captcha_key").is_some() {
+        return Some("Discord asks for a captcha, log in with a token instead".to_string());
+    }
+
+    json.get("errors")
+        .and_then(|errors| errors.as_object())
+        .and_then(|errors| {
+            errors
+                .values()
+                .find_map(|v| v.pointer("/_errors/0/message").and_then(|v| v.as_str()))
+        })
+        .or_else(|| json.get("message").and_then(|v| v.as_str()))
+        .map(str::to_string)
+}
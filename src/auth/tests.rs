@@ -0,0 +1,40 @@
+use super::token_store::{decrypt, encrypt, SavedAccount};
+
+fn accounts() -> Vec<SavedAccount> {
+    vec![
+        SavedAccount {
+            user_id: "100".to_string(),
+            token: "MTAw.first.token".to_string(),
+        },
+        SavedAccount {
+            user_id: "200".to_string(),
+            token: "MjAw.second.token".to_string(),
+        },
+    ]
+}
+
+#[test]
+fn decrypts_what_was_encrypted() {
+    let json = encrypt(&accounts(), "correct horse").unwrap();
+    assert!(!json.contains("first.token"));
+
+    let decrypted = decrypt(&json, "correct horse").unwrap();
+    let decrypted: Vec<_> = decrypted
+        .iter()
+        .map(|v| (v.user_id.as_str(), v.token.as_str()))
+        .collect();
+    assert_eq!(
+        decrypted,
+        [("100", "MTAw.first.token"), ("200", "MjAw.second.token")]
+    );
+}
+
+#[test]
+fn rejects_a_wrong_passphrase() {
+    let json = encrypt(&accounts(), "correct horse").unwrap();
+
+    for passphrase in ["correct horse ", "Correct horse", ""] {
+        let error = decrypt(&json, passphrase).err().unwrap();
+        assert_eq!(error.to_string(), "Wrong passphrase");
+    }
+}
@@ -0,0 +1,32 @@
+use std::{fmt, ops::Deref};
+
+/// An authorization token, sent as the Authorization header and when identifying.
+///
+/// Debug does not show it, so it can not end up in a printed AppData.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct Token(String);
+
+impl Token {
+    /// Without the whitespace and quotes that are often copied with it.
+    pub fn new(token: &str) -> Self {
+        Self(token.trim().trim_matches('"').to_string())
+    }
+}
+
+impl Deref for Token {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.is_empty() {
+            f.write_str("Token(none)")
+        } else {
+            f.write_str("Token(redacted)")
+        }
+    }
+}
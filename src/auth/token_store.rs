@@ -0,0 +1,159 @@
+use std::{
+    error::Error,
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use argon2::Argon2;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chacha20poly1305::{
+    aead::{rand_core::RngCore, Aead, AeadCore, KeyInit, OsRng},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use serde::{Deserialize, Serialize};
+
+use super::token::Token;
+use crate::utils::app_dirs::app_dirs;
+
+const SALT_LENGTH: usize = 16;
+
+/// The saved accounts encrypted with a key derived from the users passphrase.
+///
+/// The key is derived with Argon2id and a random salt, the accounts are encrypted
+/// with ChaCha20-Poly1305, so a wrong passphrase fails instead of returning garbage.
+#[derive(Serialize, Deserialize)]
+struct EncryptedToken {
+    /// Base64, like the other fields.
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+/// The plaintext of EncryptedToken, one entry per account.
+#[derive(Serialize, Deserialize)]
+pub(super) struct SavedAccount {
+    pub user_id: String,
+    pub token: String,
+}
+
+fn token_path() -> PathBuf {
+    app_dirs().data.join("token.json")
+}
+
+/// Whether a token was saved, so the login screen asks for the passphrase.
+pub fn has_saved_token() -> bool {
+    token_path().exists()
+}
+
+/// Adds the token to the saved accounts, or replaces the token of the same user.
+///
+/// All accounts share one passphrase, so it has to match when accounts are already saved.
+/// Slow on purpose, call it from a blocking task.
+pub fn save_token(
+    user_id: &str,
+    token: &Token,
+    passphrase: &str,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let mut accounts = if has_saved_token() {
+        decrypt_accounts(passphrase)
+            .map_err(|e| format!("{}, use the passphrase of the saved accounts", e))?
+    } else {
+        Vec::new()
+    };
+    accounts.retain(|v| v.user_id != user_id);
+    accounts.push(SavedAccount {
+        user_id: user_id.to_string(),
+        token: token.to_string(),
+    });
+
+    let json = encrypt(&accounts, passphrase)?;
+
+    let path = token_path();
+    if let Some(folder) = path.parent() {
+        fs::create_dir_all(folder)?;
+    }
+    write_private(&path, json.as_bytes())?;
+    Ok(())
+}
+
+/// Decrypts the tokens of the saved accounts, fails with a readable error for a wrong passphrase.
+///
+/// Slow on purpose, call it from a blocking task.
+pub fn load_tokens(passphrase: &str) -> Result<Vec<Token>, Box<dyn Error + Send + Sync>> {
+    Ok(decrypt_accounts(passphrase)?
+        .iter()
+        .map(|v| Token::new(&v.token))
+        .collect())
+}
+
+fn decrypt_accounts(passphrase: &str) -> Result<Vec<SavedAccount>, Box<dyn Error + Send + Sync>> {
+    decrypt(&fs::read_to_string(token_path())?, passphrase)
+}
+
+/// The contents of token.json for the accounts.
+pub(super) fn encrypt(
+    accounts: &[SavedAccount],
+    passphrase: &str,
+) -> Result<String, Box<dyn Error + Send + Sync>> {
+    let mut salt = [0; SALT_LENGTH];
+    OsRng.fill_bytes(&mut salt);
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+
+    let cipher = ChaCha20Poly1305::new(&derive_key(passphrase, &salt)?);
+    let ciphertext = cipher
+        .encrypt(&nonce, serde_json::to_vec(accounts)?.as_slice())
+        .map_err(|_| "Failed to encrypt the token")?;
+
+    Ok(serde_json::to_string(&EncryptedToken {
+        salt: STANDARD.encode(salt),
+        nonce: STANDARD.encode(nonce),
+        ciphertext: STANDARD.encode(ciphertext),
+    })?)
+}
+
+/// The accounts in the contents of token.json.
+pub(super) fn decrypt(
+    json: &str,
+    passphrase: &str,
+) -> Result<Vec<SavedAccount>, Box<dyn Error + Send + Sync>> {
+    let encrypted: EncryptedToken = serde_json::from_str(json)?;
+    let salt = STANDARD.decode(encrypted.salt)?;
+    let nonce = STANDARD.decode(encrypted.nonce)?;
+    let ciphertext = STANDARD.decode(encrypted.ciphertext)?;
+    if nonce.len() != 12 {
+        return Err("The saved token is damaged".into());
+    }
+
+    let cipher = ChaCha20Poly1305::new(&derive_key(passphrase, &salt)?);
+    let plaintext = cipher
+        .decrypt(Nonce::from_slice(&nonce), ciphertext.as_slice())
+        .map_err(|_| "Wrong passphrase")?;
+
+    // Files of single account versions only hold the token.
+    match serde_json::from_slice(&plaintext) {
+        Ok(accounts) => Ok(accounts),
+        Err(_) => Ok(vec![SavedAccount {
+            user_id: String::new(),
+            token: String::from_utf8(plaintext)?,
+        }]),
+    }
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<Key, Box<dyn Error + Send + Sync>> {
+    let mut key = Key::default();
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| format!("Failed to derive the key: {}", e))?;
+    Ok(key)
+}
+
+/// Only readable by the user on unix.
+fn write_private(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+    options.open(path)?.write_all(bytes)
+}
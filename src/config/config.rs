@@ -0,0 +1,422 @@
+use serde::{de::Error as _, Deserialize, Deserializer};
+
+use super::{intents::Intents, key_binding::KeyBinding};
+
+/// Settings from config.toml, every key can be left out to use its default.
+///
+/// Values are checked while parsing, so an error points at the line of the wrong value.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub endpoints: Endpoints,
+    pub gateway: Gateway,
+    pub cache: Cache,
+    pub theme: Theme,
+    pub fonts: Fonts,
+    pub keybindings: Keybindings,
+    pub notifications: Notifications,
+    pub accounts: Accounts,
+    pub downloads: Downloads,
+    pub logging: Logging,
+}
+
+/// Base urls without a trailing slash, used for new requests and the next connection.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Endpoints {
+    #[serde(deserialize_with = "http_url")]
+    pub api: String,
+    #[serde(deserialize_with = "websocket_url")]
+    pub gateway: String,
+    #[serde(deserialize_with = "http_url")]
+    pub cdn: String,
+}
+
+impl Default for Endpoints {
+    fn default() -> Self {
+        Self {
+            api: "https://discord.com/api/v9".to_string(),
+            gateway: "wss://gateway.discord.gg".to_string(),
+            cdn: "https://cdn.discordapp.com".to_string(),
+        }
+    }
+}
+
+/// Used when connecting, changes apply to the next connection.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Gateway {
+    pub intents: Intents,
+    pub encoding: Encoding,
+    pub compression: Compression,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Encoding {
+    /// etf is not supported.
+    #[default]
+    Json,
+}
+
+impl Encoding {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Encoding::Json => "json",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Compression {
+    #[default]
+    None,
+    /// All messages of a connection are one zlib stream, which compresses much better.
+    ZlibStream,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Cache {
+    /// Disk budget of downloaded avatars, icons, emojis and previews.
+    #[serde(deserialize_with = "megabytes")]
+    pub assets_mb: u64,
+    /// Memory budget of decoded avatars and icons.
+    #[serde(deserialize_with = "megabytes")]
+    pub images_mb: u64,
+}
+
+impl Default for Cache {
+    fn default() -> Self {
+        Self {
+            assets_mb: 512,
+            images_mb: 64,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Theme {
+    pub primary: Color,
+    pub background: Color,
+    pub card: Color,
+    pub text: Color,
+    pub animate_images: AnimateImages,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            primary: Color::rgb(0x58, 0x65, 0xf2),
+            background: Color::rgb(0x36, 0x39, 0x3f),
+            card: Color::rgb(0x2f, 0x31, 0x36),
+            text: Color::rgb(0xff, 0xff, 0xff),
+            animate_images: AnimateImages::Hover,
+        }
+    }
+}
+
+/// A color written as "#rrggbb".
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Color {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+}
+
+impl Color {
+    const fn rgb(red: u8, green: u8, blue: u8) -> Self {
+        Self { red, green, blue }
+    }
+}
+
+impl TryFrom<String> for Color {
+    type Error = String;
+
+    fn try_from(text: String) -> Result<Self, Self::Error> {
+        let error = || format!("expected a color like \"#5865f2\", got \"{}\"", text);
+
+        let hex = text.strip_prefix('#').ok_or_else(error)?;
+        if hex.len() != 6 {
+            return Err(error());
+        }
+        let rgb = u32::from_str_radix(hex, 16).map_err(|_| error())?;
+
+        Ok(Self::rgb((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8))
+    }
+}
+
+/// When animated avatars, icons and emojis play, the profile card can change it until the next reload.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AnimateImages {
+    Always,
+    #[default]
+    Hover,
+    Never,
+}
+
+impl AnimateImages {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AnimateImages::Always => "always",
+            AnimateImages::Hover => "hover",
+            AnimateImages::Never => "never",
+        }
+    }
+}
+
+/// Sizes in logical pixels, smaller text like timestamps is scaled with them.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Fonts {
+    /// Empty for the default font of the platform.
+    pub family: String,
+    #[serde(deserialize_with = "font_size")]
+    pub message_size: u32,
+    #[serde(deserialize_with = "font_size")]
+    pub composer_size: u32,
+    #[serde(deserialize_with = "font_size")]
+    pub channel_list_size: u32,
+}
+
+impl Default for Fonts {
+    fn default() -> Self {
+        Self {
+            family: String::new(),
+            message_size: 14,
+            composer_size: 14,
+            channel_list_size: 14,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Keybindings {
+    pub send_message: KeyBinding,
+    /// Only while the composer is empty.
+    pub edit_last_message: KeyBinding,
+    /// Stops editing or replying.
+    pub cancel: KeyBinding,
+    pub close_image_viewer: KeyBinding,
+    /// Shows the next logged in account.
+    pub switch_account: KeyBinding,
+}
+
+impl Default for Keybindings {
+    fn default() -> Self {
+        let binding = |text: &str| KeyBinding::parse(text).unwrap_or_default();
+
+        Self {
+            send_message: binding("Enter"),
+            edit_last_message: binding("Up"),
+            cancel: binding("Escape"),
+            close_image_viewer: binding("Escape"),
+            switch_account: binding("Ctrl+Tab"),
+        }
+    }
+}
+
+/// Which new messages ask for attention while the window is in the background.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Notifications {
+    pub direct_messages: NotifyMode,
+    pub guild_messages: NotifyMode,
+    /// Messages containing one of these words count as mentions, ignoring case.
+    pub keywords: Vec<String>,
+    /// Channel ids that never notify.
+    #[serde(deserialize_with = "snowflakes")]
+    pub muted_channels: Vec<String>,
+}
+
+impl Default for Notifications {
+    fn default() -> Self {
+        Self {
+            direct_messages: NotifyMode::All,
+            guild_messages: NotifyMode::Mentions,
+            keywords: Vec::new(),
+            muted_channels: Vec::new(),
+        }
+    }
+}
+
+impl Notifications {
+    /// Whether a message of someone else should notify.
+    pub fn should_notify(&self, channel_id: &str, is_private: bool, mentioned: bool) -> bool {
+        if self.muted_channels.iter().any(|v| v == channel_id) {
+            return false;
+        }
+
+        let mode = if is_private {
+            self.direct_messages
+        } else {
+            self.guild_messages
+        };
+        match mode {
+            NotifyMode::All => true,
+            NotifyMode::Mentions => mentioned,
+            NotifyMode::None => false,
+        }
+    }
+
+    pub fn matches_keyword(&self, content: &str) -> bool {
+        if self.keywords.is_empty() {
+            return false;
+        }
+
+        let content = content.to_lowercase();
+        self.keywords
+            .iter()
+            .any(|keyword| content.contains(&keyword.to_lowercase()))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NotifyMode {
+    All,
+    /// Mentions of the user, @everyone, and keywords.
+    Mentions,
+    None,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Accounts {
+    /// Accounts that are not shown stay connected, so their messages still notify.
+    /// Otherwise they disconnect when another account is shown, applies at the next switch.
+    pub keep_background_connected: bool,
+}
+
+impl Default for Accounts {
+    fn default() -> Self {
+        Self {
+            keep_background_connected: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Downloads {
+    /// Where attachments are saved, empty for the Downloads folder in the home directory.
+    #[serde(deserialize_with = "folder")]
+    pub folder: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Logging {
+    /// Levels like "info" or "warn,slint_rust_template::websocket=debug", RUST_LOG replaces it.
+    #[serde(deserialize_with = "log_filter")]
+    pub filter: String,
+    /// Writes a file per day to the logs folder in the data directory.
+    pub file: bool,
+    /// Older log files are deleted.
+    #[serde(deserialize_with = "log_files")]
+    pub max_files: usize,
+    /// Logged payloads and messages keep their text, otherwise only its length is logged.
+    /// Tokens are never logged.
+    pub show_message_content: bool,
+}
+
+impl Default for Logging {
+    fn default() -> Self {
+        Self {
+            filter: "info".to_string(),
+            file: true,
+            max_files: 7,
+            show_message_content: false,
+        }
+    }
+}
+
+fn http_url<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    url(deserializer, &["https://", "http://"])
+}
+
+fn websocket_url<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    url(deserializer, &["wss://", "ws://"])
+}
+
+fn url<'de, D: Deserializer<'de>>(deserializer: D, schemes: &[&str]) -> Result<String, D::Error> {
+    let url = String::deserialize(deserializer)?;
+
+    let host = schemes
+        .iter()
+        .find_map(|scheme| url.strip_prefix(scheme))
+        .ok_or_else(|| {
+            D::Error::custom(format!(
+                "expected an url starting with {}, got \"{}\"",
+                schemes.join(" or "),
+                url
+            ))
+        })?;
+    if host.trim_end_matches('/').is_empty() {
+        return Err(D::Error::custom(format!("\"{}\" has no host", url)));
+    }
+
+    Ok(url.trim_end_matches('/').to_string())
+}
+
+fn megabytes<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    let megabytes = u64::deserialize(deserializer)?;
+    if megabytes == 0 {
+        return Err(D::Error::custom("must be at least 1 MB"));
+    }
+    Ok(megabytes)
+}
+
+fn font_size<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u32, D::Error> {
+    let size = u32::deserialize(deserializer)?;
+    if !(8..=32).contains(&size) {
+        return Err(D::Error::custom(format!(
+            "expected a font size from 8 to 32, got {}",
+            size
+        )));
+    }
+    Ok(size)
+}
+
+fn snowflakes<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    let ids = Vec::<String>::deserialize(deserializer)?;
+    if let Some(id) = ids.iter().find(|id| id.parse::<u64>().is_err()) {
+        return Err(D::Error::custom(format!(
+            "expected channel ids like \"1234567890123456789\", got \"{}\"",
+            id
+        )));
+    }
+    Ok(ids)
+}
+
+fn folder<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    let folder = String::deserialize(deserializer)?;
+    if !folder.is_empty() && !std::path::Path::new(&folder).is_absolute() {
+        return Err(D::Error::custom(format!(
+            "expected an absolute path, got \"{}\"",
+            folder
+        )));
+    }
+    Ok(folder)
+}
+
+fn log_filter<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    let filter = String::deserialize(deserializer)?;
+    tracing_subscriber::EnvFilter::builder()
+        .parse(&filter)
+        .map_err(|e| D::Error::custom(format!("invalid log filter \"{}\": {}", filter, e)))?;
+    Ok(filter)
+}
+
+fn log_files<'de, D: Deserializer<'de>>(deserializer: D) -> Result<usize, D::Error> {
+    let files = usize::deserialize(deserializer)?;
+    if files == 0 {
+        return Err(D::Error::custom("must keep at least 1 file"));
+    }
+    Ok(files)
+}
@@ -0,0 +1,61 @@
+use serde::Deserialize;
+
+/// Gateway intents by name, the number is the bit of the intent.
+const INTENTS: &[(&str, u32)] = &[
+    ("GUILDS", 0),
+    ("GUILD_MEMBERS", 1),
+    ("GUILD_MODERATION", 2),
+    ("GUILD_EXPRESSIONS", 3),
+    ("GUILD_INTEGRATIONS", 4),
+    ("GUILD_WEBHOOKS", 5),
+    ("GUILD_INVITES", 6),
+    ("GUILD_VOICE_STATES", 7),
+    ("GUILD_PRESENCES", 8),
+    ("GUILD_MESSAGES", 9),
+    ("GUILD_MESSAGE_REACTIONS", 10),
+    ("GUILD_MESSAGE_TYPING", 11),
+    ("DIRECT_MESSAGES", 12),
+    ("DIRECT_MESSAGE_REACTIONS", 13),
+    ("DIRECT_MESSAGE_TYPING", 14),
+    ("MESSAGE_CONTENT", 15),
+    ("GUILD_SCHEDULED_EVENTS", 16),
+    ("AUTO_MODERATION_CONFIGURATION", 20),
+    ("AUTO_MODERATION_EXECUTION", 21),
+    ("GUILD_MESSAGE_POLLS", 24),
+    ("DIRECT_MESSAGE_POLLS", 25),
+];
+
+/// What the gateway sends, written as a list of intent names.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "Vec<String>")]
+pub struct Intents(pub u64);
+
+/// All of them.
+impl Default for Intents {
+    fn default() -> Self {
+        Self(INTENTS.iter().fold(0, |bits, (_, bit)| bits | 1 << bit))
+    }
+}
+
+impl TryFrom<Vec<String>> for Intents {
+    type Error = String;
+
+    fn try_from(names: Vec<String>) -> Result<Self, Self::Error> {
+        let mut bits = 0;
+        for name in names {
+            let Some((_, bit)) = INTENTS.iter().find(|(v, _)| *v == name) else {
+                return Err(format!(
+                    "unknown intent \"{}\", expected one of {}",
+                    name,
+                    INTENTS
+                        .iter()
+                        .map(|(v, _)| *v)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ));
+            };
+            bits |= 1 << bit;
+        }
+        Ok(Self(bits))
+    }
+}
@@ -0,0 +1,96 @@
+use serde::Deserialize;
+use slint::platform::Key;
+
+/// A shortcut like "Ctrl+Shift+K", matched against the text and modifiers of a slint KeyEvent.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct KeyBinding {
+    /// Text of the key event, lowercase for letters.
+    pub key: String,
+    pub control: bool,
+    pub shift: bool,
+    pub alt: bool,
+    pub meta: bool,
+}
+
+impl KeyBinding {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut binding = Self::default();
+
+        // "Ctrl++" is Ctrl and the plus key.
+        let (modifiers, key) = match text.strip_suffix("++") {
+            Some(modifiers) => (modifiers, "+"),
+            None => text.rsplit_once('+').unwrap_or(("", text)),
+        };
+
+        for modifier in modifiers.split('+').map(str::trim) {
+            match modifier.to_ascii_lowercase().as_str() {
+                "" if modifiers.is_empty() => {}
+                "ctrl" | "control" => binding.control = true,
+                "shift" => binding.shift = true,
+                "alt" | "option" => binding.alt = true,
+                "meta" | "super" | "cmd" | "command" => binding.meta = true,
+                _ => {
+                    return Err(format!(
+                        "unknown modifier \"{}\" in \"{}\", expected Ctrl, Shift, Alt or Meta",
+                        modifier, text
+                    ))
+                }
+            }
+        }
+
+        binding.key = key_text(key.trim())
+            .ok_or_else(|| format!("unknown key \"{}\" in \"{}\"", key.trim(), text))?;
+
+        Ok(binding)
+    }
+}
+
+impl TryFrom<String> for KeyBinding {
+    type Error = String;
+
+    fn try_from(text: String) -> Result<Self, Self::Error> {
+        Self::parse(&text)
+    }
+}
+
+/// The text slint puts in a KeyEvent for the named key.
+fn key_text(name: &str) -> Option<String> {
+    let key = match name.to_ascii_lowercase().as_str() {
+        "enter" | "return" => Key::Return,
+        "escape" | "esc" => Key::Escape,
+        "tab" => Key::Tab,
+        "space" => Key::Space,
+        "backspace" => Key::Backspace,
+        "delete" => Key::Delete,
+        "up" => Key::UpArrow,
+        "down" => Key::DownArrow,
+        "left" => Key::LeftArrow,
+        "right" => Key::RightArrow,
+        "home" => Key::Home,
+        "end" => Key::End,
+        "pageup" => Key::PageUp,
+        "pagedown" => Key::PageDown,
+        "f1" => Key::F1,
+        "f2" => Key::F2,
+        "f3" => Key::F3,
+        "f4" => Key::F4,
+        "f5" => Key::F5,
+        "f6" => Key::F6,
+        "f7" => Key::F7,
+        "f8" => Key::F8,
+        "f9" => Key::F9,
+        "f10" => Key::F10,
+        "f11" => Key::F11,
+        "f12" => Key::F12,
+        _ => {
+            let mut chars = name.chars();
+            return match (chars.next(), chars.next()) {
+                (Some(char), None) => Some(char.to_lowercase().collect()),
+                _ => None,
+            };
+        }
+    };
+
+    Some(char::from(key).to_string())
+}
@@ -0,0 +1,68 @@
+use std::{
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, PoisonError, RwLock},
+};
+
+use once_cell::sync::Lazy;
+
+use super::config::Config;
+use crate::utils::app_dirs::app_dirs;
+
+/// Written to the config directory when there is no config file yet.
+const DEFAULT_CONFIG: &str = include_str!("config.toml");
+
+/// Replaced as a whole when the file changes, readers keep the settings they started with.
+static CONFIG: Lazy<RwLock<Arc<Config>>> = Lazy::new(Default::default);
+
+/// The current settings, the defaults before init_config.
+pub fn config() -> Arc<Config> {
+    CONFIG
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone()
+}
+
+pub fn set_config(config: Config) {
+    *CONFIG.write().unwrap_or_else(PoisonError::into_inner) = Arc::new(config);
+}
+
+pub fn config_path() -> PathBuf {
+    app_dirs().config.join("config.toml")
+}
+
+/// Reads config.toml, after writing one with the defaults on the first start.
+///
+/// Called once at startup after init_app_dirs, fails if the file has errors.
+pub fn init_config() -> Result<(), Box<dyn Error + Send + Sync>> {
+    let path = config_path();
+    if !path.exists() {
+        let result = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| fs::write(&path, DEFAULT_CONFIG));
+        if let Err(e) = result {
+            eprintln!("Failed to write {}: {}", path.display(), e);
+        }
+    }
+
+    set_config(load_config(&path)?);
+    Ok(())
+}
+
+/// The settings in the file, or the defaults if it does not exist.
+pub fn load_config(path: &Path) -> Result<Config, Box<dyn Error + Send + Sync>> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Config::default()),
+        Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e).into()),
+    };
+
+    parse_config(&text).map_err(|e| format!("Invalid config {}:\n{}", path.display(), e).into())
+}
+
+/// The error shows the line and column of the wrong value.
+pub fn parse_config(text: &str) -> Result<Config, toml::de::Error> {
+    toml::from_str(text)
+}
@@ -0,0 +1,9 @@
+#[allow(clippy::module_inception)]
+pub mod config;
+pub mod intents;
+pub mod key_binding;
+pub mod load_config;
+pub mod watch_config;
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,102 @@
+use super::{
+    config::{Compression, Config, NotifyMode},
+    intents::Intents,
+    key_binding::KeyBinding,
+    load_config::parse_config,
+};
+
+#[test]
+fn default_file_matches_defaults() {
+    let config = parse_config(include_str!("config.toml")).unwrap();
+    assert_eq!(config, Config::default());
+}
+
+#[test]
+fn missing_keys_use_defaults() {
+    let config = parse_config("[theme]\nprimary = \"#ff0000\"\n").unwrap();
+
+    assert_eq!(config.theme.primary.red, 0xff);
+    assert_eq!(config.theme.primary.blue, 0);
+    assert_eq!(config.theme.card, Config::default().theme.card);
+    assert_eq!(config.endpoints, Config::default().endpoints);
+}
+
+#[test]
+fn parses_sections() {
+    let config = parse_config(
+        r#"
+[endpoints]
+api = "http://localhost:8080/api/v9/"
+
+[gateway]
+intents = ["GUILDS", "DIRECT_MESSAGES"]
+compression = "zlib-stream"
+
+[notifications]
+guild_messages = "none"
+muted_channels = ["1234"]
+"#,
+    )
+    .unwrap();
+
+    assert_eq!(config.endpoints.api, "http://localhost:8080/api/v9");
+    assert_eq!(config.gateway.intents, Intents(1 | 1 << 12));
+    assert_eq!(config.gateway.compression, Compression::ZlibStream);
+    assert_eq!(config.notifications.guild_messages, NotifyMode::None);
+    assert!(!config.notifications.should_notify("1234", true, true));
+    assert!(config.notifications.should_notify("5678", true, false));
+}
+
+#[test]
+fn errors_point_at_the_value() {
+    let cases = [
+        ("[theme]\ncard = \"blue\"\n", "line 2", "expected a color"),
+        ("[fonts]\nmessage_size = 80\n", "line 2", "from 8 to 32"),
+        ("[cache]\nassets_mb = 0\n", "line 2", "at least 1 MB"),
+        ("[endpoints]\ngateway = \"https://a\"\n", "line 2", "wss://"),
+        (
+            "[downloads]\nfolder = \"downloads\"\n",
+            "line 2",
+            "expected an absolute path",
+        ),
+        (
+            "\n[gateway]\nintents = [\"GUILD\"]\n",
+            "line 3",
+            "unknown intent",
+        ),
+        (
+            "[gateway]\nencoding = \"etf\"\n",
+            "line 2",
+            "unknown variant",
+        ),
+        (
+            "[keybindings]\ncancel = \"Hyper+X\"\n",
+            "line 2",
+            "unknown modifier",
+        ),
+        (
+            "[logging]\nfilter = \"info,websocket=loud\"\n",
+            "line 2",
+            "invalid log filter",
+        ),
+        ("[theme]\nprimay = \"#000000\"\n", "line 2", "unknown field"),
+    ];
+
+    for (text, line, message) in cases {
+        let error = parse_config(text).unwrap_err().to_string();
+        assert!(error.contains(line), "{}: {}", text, error);
+        assert!(error.contains(message), "{}: {}", text, error);
+    }
+}
+
+#[test]
+fn parses_key_bindings() {
+    let binding = KeyBinding::parse("Ctrl+Shift+K").unwrap();
+    assert_eq!(binding.key, "k");
+    assert!(binding.control && binding.shift && !binding.alt && !binding.meta);
+
+    assert_eq!(KeyBinding::parse("Enter").unwrap().key, "\n");
+    assert_eq!(KeyBinding::parse("Alt++").unwrap().key, "+");
+    assert!(KeyBinding::parse("Ctrl+").is_err());
+    assert!(KeyBinding::parse("Ctrl+Enterr").is_err());
+}
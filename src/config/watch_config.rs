@@ -0,0 +1,52 @@
+use std::{fs, path::Path, thread, time::Duration, time::SystemTime};
+use tracing::{info, warn};
+
+use super::load_config::{config, config_path, load_config, set_config};
+use crate::{
+    logging::init_logging::set_log_filter,
+    state::{UiUpdate, UpdateSender},
+};
+
+/// How often the config file is checked for changes.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Reloads the config file when it is changed, and tells the UI to apply it.
+///
+/// A file with errors is reported and the previous settings stay,
+/// so a half saved file does not reset anything.
+pub fn watch_config(update_sender: UpdateSender) {
+    thread::spawn(move || {
+        let path = config_path();
+        let mut modified = modified_time(&path);
+
+        while !update_sender.is_closed() {
+            thread::sleep(POLL_INTERVAL);
+
+            let current = modified_time(&path);
+            if current == modified {
+                continue;
+            }
+            modified = current;
+
+            // Removed, or being replaced by an editor.
+            if current.is_none() {
+                continue;
+            }
+
+            match load_config(&path) {
+                Ok(new_config) if new_config != *config() => {
+                    set_log_filter(&new_config.logging);
+                    set_config(new_config);
+                    info!("Reloaded {}", path.display());
+                    let _ = update_sender.send(UiUpdate::ConfigChanged);
+                }
+                Ok(_) => {}
+                Err(e) => warn!("{}", e),
+            }
+        }
+    });
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|v| v.modified()).ok()
+}
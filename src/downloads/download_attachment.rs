@@ -0,0 +1,241 @@
+use std::{
+    error::Error,
+    ffi::OsString,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Instant,
+};
+
+use reqwest::{header::RANGE, StatusCode};
+use tokio::{fs::OpenOptions, io::AsyncWriteExt};
+use tracing::warn;
+
+use super::download_folder::{download_folder, unique_path};
+use crate::{
+    state::{
+        AppState, Attachment, Download, DownloadState, UiUpdate, UpdateSender, HTTP_CLIENT,
+        PROGRESS_INTERVAL,
+    },
+    utils::open_url::open_url,
+};
+
+/// Saves an attachment to the download folder.
+///
+/// If the attachment was downloaded before in this session and cancelled or failed,
+/// the download is resumed instead. If it is done the file is opened,
+/// or downloaded again when it was moved or deleted.
+pub async fn download_attachment(
+    app_state: AppState,
+    update_sender: UpdateSender,
+    attachment: Attachment,
+) {
+    let existing = app_state
+        .read()
+        .await
+        .downloads
+        .iter()
+        .find(|v| v.id == attachment.id)
+        .cloned();
+
+    if let Some(download) = existing {
+        match download.state {
+            DownloadState::Downloading => return,
+            DownloadState::Cancelled | DownloadState::Failed(_) => {
+                resume_download(app_state, update_sender, attachment.id).await;
+                return;
+            }
+            DownloadState::Done => {
+                if tokio::fs::try_exists(&download.path).await.unwrap_or(false) {
+                    if let Err(e) = open_url(&download.path.to_string_lossy()) {
+                        warn!("Failed to open {}: {}", download.path.display(), e);
+                    }
+                    return;
+                }
+                app_state
+                    .write()
+                    .await
+                    .downloads
+                    .retain(|v| v.id != attachment.id);
+            }
+        }
+    }
+
+    {
+        let mut app_data = app_state.write().await;
+        // Another click started it in the meantime.
+        if app_data.downloads.iter().any(|v| v.id == attachment.id) {
+            return;
+        }
+
+        let used: Vec<PathBuf> = app_data.downloads.iter().map(|v| v.path.clone()).collect();
+        // Discord strips separators from file names, this is just in case.
+        let filename = attachment.filename.replace(['/', '\\'], "_");
+
+        app_data.downloads.push(Download {
+            id: attachment.id.clone(),
+            path: unique_path(&download_folder(), &filename, &used),
+            filename,
+            url: attachment.url.clone(),
+            received: 0,
+            total: attachment.size,
+            state: DownloadState::Downloading,
+            cancel: Arc::new(AtomicBool::new(false)),
+        });
+    }
+    let _ = update_sender.send(UiUpdate::DownloadsChanged);
+
+    run_download(app_state, update_sender, attachment.id).await;
+}
+
+/// Continues a cancelled or failed download from where it stopped.
+pub async fn resume_download(app_state: AppState, update_sender: UpdateSender, id: String) {
+    {
+        let mut app_data = app_state.write().await;
+        let Some(download) = app_data.downloads.iter_mut().find(|v| v.id == id) else {
+            return;
+        };
+        if !matches!(
+            download.state,
+            DownloadState::Cancelled | DownloadState::Failed(_)
+        ) {
+            return;
+        }
+
+        download.state = DownloadState::Downloading;
+        download.cancel = Arc::new(AtomicBool::new(false));
+    }
+    let _ = update_sender.send(UiUpdate::DownloadsChanged);
+
+    run_download(app_state, update_sender, id).await;
+}
+
+/// Stops a download, it keeps its partial file and can be resumed.
+pub async fn cancel_download(app_state: AppState, id: String) {
+    if let Some(download) = app_state.read().await.downloads.iter().find(|v| v.id == id) {
+        download.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+async fn run_download(app_state: AppState, update_sender: UpdateSender, id: String) {
+    let Some(download) = app_state
+        .read()
+        .await
+        .downloads
+        .iter()
+        .find(|v| v.id == id)
+        .cloned()
+    else {
+        return;
+    };
+
+    let state = match fetch_to_file(&app_state, &update_sender, &download).await {
+        Ok(true) => DownloadState::Done,
+        Ok(false) => DownloadState::Cancelled,
+        Err(e) => {
+            warn!("Failed to download {}: {}", download.filename, e);
+            DownloadState::Failed(e.to_string())
+        }
+    };
+
+    if let Some(download) = app_state
+        .write()
+        .await
+        .downloads
+        .iter_mut()
+        .find(|v| v.id == id)
+    {
+        download.state = state;
+    }
+    let _ = update_sender.send(UiUpdate::DownloadsChanged);
+}
+
+/// Downloads into path.part, continuing after the bytes that are already there,
+/// and renames it to path when it is complete.
+///
+/// Returns false if the download was cancelled.
+async fn fetch_to_file(
+    app_state: &AppState,
+    update_sender: &UpdateSender,
+    download: &Download,
+) -> Result<bool, Box<dyn Error + Send + Sync>> {
+    let mut part_path = OsString::from(download.path.as_os_str());
+    part_path.push(".part");
+    let part_path = PathBuf::from(part_path);
+
+    if let Some(folder) = download.path.parent() {
+        tokio::fs::create_dir_all(folder).await?;
+    }
+
+    let offset = tokio::fs::metadata(&part_path)
+        .await
+        .map(|v| v.len())
+        .unwrap_or_default();
+
+    let mut request = HTTP_CLIENT.get(&download.url);
+    if offset > 0 {
+        request = request.header(RANGE, format!("bytes={}-", offset));
+    }
+    let mut response = request.send().await?;
+
+    // The server ignored the range, or the partial file is not valid for it anymore.
+    let resumed = offset > 0 && response.status() == StatusCode::PARTIAL_CONTENT;
+    if offset > 0 && !resumed {
+        response = HTTP_CLIENT.get(&download.url).send().await?;
+    }
+    let mut response = response.error_for_status()?;
+
+    let mut received = if resumed { offset } else { 0 };
+    let total = response
+        .content_length()
+        .map(|v| v + received)
+        .unwrap_or(download.total);
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(resumed)
+        .truncate(!resumed)
+        .open(&part_path)
+        .await?;
+
+    let mut last_progress = Instant::now();
+
+    while let Some(chunk) = response.chunk().await? {
+        if download.cancel.load(Ordering::Relaxed) {
+            file.flush().await?;
+            return Ok(false);
+        }
+
+        file.write_all(&chunk).await?;
+        received += chunk.len() as u64;
+
+        if last_progress.elapsed() >= PROGRESS_INTERVAL {
+            last_progress = Instant::now();
+            set_progress(app_state, &download.id, received, total).await;
+            let _ = update_sender.send(UiUpdate::DownloadsChanged);
+        }
+    }
+
+    file.flush().await?;
+    drop(file);
+    tokio::fs::rename(&part_path, &download.path).await?;
+    set_progress(app_state, &download.id, received, total).await;
+
+    Ok(true)
+}
+
+async fn set_progress(app_state: &AppState, id: &str, received: u64, total: u64) {
+    if let Some(download) = app_state
+        .write()
+        .await
+        .downloads
+        .iter_mut()
+        .find(|v| v.id == id)
+    {
+        download.received = received;
+        download.total = total;
+    }
+}
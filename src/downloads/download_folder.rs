@@ -0,0 +1,40 @@
+use std::{
+    env,
+    path::{Path, PathBuf},
+};
+
+use crate::config::load_config::config;
+
+/// Where attachments are saved, downloads.folder if it is set, otherwise the users Downloads folder.
+pub fn download_folder() -> PathBuf {
+    let folder = &config().downloads.folder;
+    if !folder.is_empty() {
+        return PathBuf::from(folder);
+    }
+
+    env::var_os("HOME")
+        .or_else(|| env::var_os("USERPROFILE"))
+        .map(|home| PathBuf::from(home).join("Downloads"))
+        .unwrap_or_else(|| PathBuf::from("./downloads"))
+}
+
+/// A path in the folder for the file name that is not used yet, "name (1).ext" if "name.ext" is.
+///
+/// Paths of unfinished downloads count as used.
+pub fn unique_path(folder: &Path, filename: &str, used: &[PathBuf]) -> PathBuf {
+    let (stem, extension) = match filename.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => (stem, format!(".{}", extension)),
+        _ => (filename, String::new()),
+    };
+
+    (0..)
+        .map(|index| {
+            if index == 0 {
+                folder.join(filename)
+            } else {
+                folder.join(format!("{} ({}){}", stem, index, extension))
+            }
+        })
+        .find(|path| !path.exists() && !used.contains(path))
+        .unwrap_or_else(|| folder.join(filename))
+}
@@ -0,0 +1,2 @@
+pub mod download_attachment;
+pub mod download_folder;
@@ -0,0 +1,60 @@
+/// Discord names of emojis that are named differently in the bundled gemoji table.
+const DISCORD_ALIASES: &[(&str, &str)] = &[
+    ("slight_smile", "slightly_smiling_face"),
+    ("slight_frown", "slightly_frowning_face"),
+    ("upside_down", "upside_down_face"),
+    ("hugging", "hugs"),
+    ("nerd", "nerd_face"),
+    ("face_palm", "facepalm"),
+    ("thinking_face", "thinking"),
+    ("robot_face", "robot"),
+    ("rolling_eyes", "roll_eyes"),
+    ("zipper_mouth", "zipper_mouth_face"),
+    ("head_bandage", "face_with_head_bandage"),
+    ("thermometer_face", "face_with_thermometer"),
+    ("money_mouth", "money_mouth_face"),
+    ("skull_crossbones", "skull_and_crossbones"),
+];
+
+/// Longest emoji, in chars, like family emojis joined with zero width joiners.
+const MAX_EMOJI_CHARS: usize = 12;
+
+/// The unicode emoji for a shortcode without colons, like "thumbsup".
+pub fn find_shortcode(name: &str) -> Option<&'static str> {
+    let name = DISCORD_ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == name)
+        .map(|(_, name)| *name)
+        .unwrap_or(name);
+
+    emojis::get_by_shortcode(name).map(|v| v.as_str())
+}
+
+/// The unicode emoji for a reaction typed as an emoji or as a shortcode, with or without colons.
+pub fn parse_reaction_emoji(text: &str) -> Option<&'static str> {
+    let text = text.trim();
+    emojis::get(text)
+        .map(|v| v.as_str())
+        .or_else(|| find_shortcode(text.trim_matches(':')))
+}
+
+/// Number of emojis in text that only contains emojis and whitespace, None if it contains anything else.
+pub fn count_emojis(text: &str) -> Option<usize> {
+    let mut count = 0;
+    let mut rest = text.trim_start();
+
+    while !rest.is_empty() {
+        // The longest prefix that is an emoji, so emojis with skin tones are not split.
+        let length = rest
+            .char_indices()
+            .map(|(index, ch)| index + ch.len_utf8())
+            .take(MAX_EMOJI_CHARS)
+            .filter(|&length| emojis::get(&rest[..length]).is_some())
+            .last()?;
+
+        count += 1;
+        rest = rest[length..].trim_start();
+    }
+
+    Some(count)
+}
@@ -0,0 +1,365 @@
+use super::language::{find_language, Language};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    Plain,
+    Keyword,
+    Literal,
+    Type,
+    String,
+    Number,
+    Comment,
+    Function,
+    /// Object keys and variables.
+    Property,
+    /// Attributes, section headers and diff headers.
+    Meta,
+    Added,
+    Removed,
+}
+
+impl TokenKind {
+    /// Name of the color in the code theme of rich-text.slint.
+    pub fn name(self) -> &'static str {
+        match self {
+            TokenKind::Plain => "plain",
+            TokenKind::Keyword => "keyword",
+            TokenKind::Literal => "literal",
+            TokenKind::Type => "type",
+            TokenKind::String => "string",
+            TokenKind::Number => "number",
+            TokenKind::Comment => "comment",
+            TokenKind::Function => "function",
+            TokenKind::Property => "property",
+            TokenKind::Meta => "meta",
+            TokenKind::Added => "added",
+            TokenKind::Removed => "removed",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub text: String,
+}
+
+/// Splits code into lines of colored tokens.
+///
+/// Code in unknown languages is not colored.
+pub fn highlight_code(language: &str, code: &str) -> Vec<Vec<Token>> {
+    let tokens = match find_language(language) {
+        Some(language) if language.diff_syntax => tokenize_diff(code),
+        Some(language) => tokenize(language, code),
+        None => vec![Token {
+            kind: TokenKind::Plain,
+            text: code.to_string(),
+        }],
+    };
+
+    split_lines(tokens)
+}
+
+/// Splits tokens spanning lines, like block comments, and merges neighbours of the same kind.
+fn split_lines(tokens: Vec<Token>) -> Vec<Vec<Token>> {
+    let mut lines = vec![Vec::<Token>::new()];
+
+    for token in tokens {
+        for (index, part) in token.text.split('\n').enumerate() {
+            if index > 0 {
+                lines.push(Vec::new());
+            }
+            if part.is_empty() {
+                continue;
+            }
+
+            let Some(line) = lines.last_mut() else {
+                continue;
+            };
+            match line.last_mut() {
+                Some(last) if last.kind == token.kind => last.text.push_str(part),
+                _ => line.push(Token {
+                    kind: token.kind,
+                    text: part.to_string(),
+                }),
+            }
+        }
+    }
+
+    lines
+}
+
+fn tokenize_diff(code: &str) -> Vec<Token> {
+    code.split_inclusive('\n')
+        .map(|line| {
+            let kind = if ["+++", "---", "@@", "diff ", "index "]
+                .iter()
+                .any(|v| line.starts_with(v))
+            {
+                TokenKind::Meta
+            } else if line.starts_with('+') {
+                TokenKind::Added
+            } else if line.starts_with('-') {
+                TokenKind::Removed
+            } else {
+                TokenKind::Plain
+            };
+
+            Token {
+                kind,
+                text: line.to_string(),
+            }
+        })
+        .collect()
+}
+
+fn tokenize(language: &Language, code: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut index = 0;
+    // Only whitespace since the start of the line.
+    let mut line_start = true;
+
+    while index < code.len() {
+        let (kind, length) = next_token(language, &code[index..], line_start);
+        let text = &code[index..index + length];
+
+        if let Some((_, after_newline)) = text.rsplit_once('\n') {
+            line_start = after_newline.trim().is_empty();
+        } else if !text.trim().is_empty() {
+            line_start = false;
+        }
+
+        tokens.push(Token {
+            kind,
+            text: text.to_string(),
+        });
+        index += length;
+    }
+
+    tokens
+}
+
+/// Kind and length in bytes of the token at the start of rest.
+fn next_token(language: &Language, rest: &str, line_start: bool) -> (TokenKind, usize) {
+    let Some(ch) = rest.chars().next() else {
+        return (TokenKind::Plain, 0);
+    };
+
+    if ch.is_whitespace() {
+        return (TokenKind::Plain, take_while(rest, char::is_whitespace));
+    }
+
+    if language.dollar_variables && ch == '$' {
+        return (TokenKind::Property, variable_length(rest));
+    }
+
+    if language
+        .line_comments
+        .iter()
+        .any(|prefix| rest.starts_with(prefix))
+    {
+        return (TokenKind::Comment, rest.find('\n').unwrap_or(rest.len()));
+    }
+
+    if let Some((open, close)) = language.block_comment {
+        if let Some(comment) = rest.strip_prefix(open) {
+            let length = comment
+                .find(close)
+                .map(|v| open.len() + v + close.len())
+                .unwrap_or(rest.len());
+            return (TokenKind::Comment, length);
+        }
+    }
+
+    if language.rust_syntax {
+        if rest.starts_with("#[") || rest.starts_with("#![") {
+            return (TokenKind::Meta, attribute_length(rest));
+        }
+        if ch == '\'' {
+            return match char_literal_length(rest) {
+                Some(length) => (TokenKind::String, length),
+                // A lifetime like 'a or 'static.
+                None => (
+                    TokenKind::Type,
+                    1 + take_while(&rest[1..], is_identifier_char),
+                ),
+            };
+        }
+    }
+
+    if language.toml_syntax && line_start {
+        if ch == '[' {
+            return (TokenKind::Meta, rest.find('\n').unwrap_or(rest.len()));
+        }
+
+        let key_length = take_while(rest, |c| is_identifier_char(c) || c == '-' || c == '.');
+        if key_length > 0
+            && rest[key_length..]
+                .trim_start_matches([' ', '\t'])
+                .starts_with('=')
+        {
+            return (TokenKind::Property, key_length);
+        }
+    }
+
+    if language.quotes.contains(&ch) {
+        let length = string_length(language, rest, ch);
+        let is_key = language.string_keys
+            && rest[length..]
+                .trim_start_matches([' ', '\t'])
+                .starts_with(':');
+        let kind = if is_key {
+            TokenKind::Property
+        } else {
+            TokenKind::String
+        };
+        return (kind, length);
+    }
+
+    if ch.is_ascii_digit() {
+        return (TokenKind::Number, number_length(rest));
+    }
+
+    if ch.is_alphabetic() || ch == '_' {
+        let length = take_while(rest, is_identifier_char);
+        return identifier(language, rest, length);
+    }
+
+    (TokenKind::Plain, ch.len_utf8())
+}
+
+fn identifier(language: &Language, rest: &str, length: usize) -> (TokenKind, usize) {
+    let word = &rest[..length];
+    let after = &rest[length..];
+
+    let contains = |list: &[&str]| {
+        if language.case_insensitive {
+            list.iter().any(|v| v.eq_ignore_ascii_case(word))
+        } else {
+            list.contains(&word)
+        }
+    };
+
+    if contains(language.keywords) {
+        (TokenKind::Keyword, length)
+    } else if contains(language.literals) {
+        (TokenKind::Literal, length)
+    } else if contains(language.types) {
+        (TokenKind::Type, length)
+    } else if language.rust_syntax && after.starts_with('!') && !after.starts_with("!=") {
+        // Macro calls like println!
+        (TokenKind::Function, length + 1)
+    } else if after.trim_start_matches([' ', '\t']).starts_with('(') {
+        (TokenKind::Function, length)
+    } else if language.capitalized_types && word.starts_with(|c: char| c.is_uppercase()) {
+        (TokenKind::Type, length)
+    } else {
+        (TokenKind::Plain, length)
+    }
+}
+
+/// Strings end at the closing quote, or at the end of the line unless they can span lines.
+fn string_length(language: &Language, rest: &str, quote: char) -> usize {
+    let triple: String = [quote; 3].iter().collect();
+
+    if language.triple_quotes && rest.starts_with(&triple) {
+        return rest[3..]
+            .find(&triple)
+            .map(|v| 3 + v + 3)
+            .unwrap_or(rest.len());
+    }
+
+    let multi_line = quote == '`' || language.dollar_variables;
+    let mut chars = rest.char_indices().skip(1);
+
+    while let Some((index, ch)) = chars.next() {
+        match ch {
+            '\\' => {
+                chars.next();
+            }
+            '\n' if !multi_line => return index,
+            c if c == quote => return index + 1,
+            _ => {}
+        }
+    }
+
+    rest.len()
+}
+
+/// 'c', '\n' and '\u{1F600}', None for lifetimes.
+fn char_literal_length(rest: &str) -> Option<usize> {
+    let mut chars = rest.char_indices().skip(1);
+    let (_, first) = chars.next()?;
+
+    if first == '\\' {
+        let end = rest[2..].find('\'')?;
+        return (end <= 10).then_some(2 + end + 1);
+    }
+
+    let (index, ch) = chars.next()?;
+    (ch == '\'').then_some(index + 1)
+}
+
+/// #[...] with nested brackets, up to the end of the line.
+fn attribute_length(rest: &str) -> usize {
+    let mut depth = 0;
+
+    for (index, ch) in rest.char_indices() {
+        match ch {
+            '[' => depth += 1,
+            ']' => {
+                depth -= 1;
+                if depth == 0 {
+                    return index + 1;
+                }
+            }
+            '\n' => return index,
+            _ => {}
+        }
+    }
+
+    rest.len()
+}
+
+/// $name, ${name}, $1 and special variables like $?.
+fn variable_length(rest: &str) -> usize {
+    let after = &rest[1..];
+
+    if after.starts_with('{') {
+        return after.find('}').map(|v| 1 + v + 1).unwrap_or(rest.len());
+    }
+
+    match after.chars().next() {
+        Some(c) if "@#?$!*-".contains(c) || c.is_ascii_digit() => 2,
+        Some(_) => 1 + take_while(after, is_identifier_char),
+        None => 1,
+    }
+}
+
+/// Digits, hex, suffixes like 10u32 and exponents, a '.' only if a digit follows.
+fn number_length(rest: &str) -> usize {
+    let bytes = rest.as_bytes();
+    let mut index = 0;
+
+    while index < bytes.len() {
+        let b = bytes[index];
+        let is_decimal_point =
+            b == b'.' && bytes.get(index + 1).is_some_and(|v| v.is_ascii_digit());
+
+        if b.is_ascii_alphanumeric() || b == b'_' || is_decimal_point {
+            index += 1;
+        } else {
+            break;
+        }
+    }
+
+    index
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+fn take_while(text: &str, predicate: impl Fn(char) -> bool) -> usize {
+    text.find(|c: char| !predicate(c)).unwrap_or(text.len())
+}
@@ -0,0 +1,157 @@
+use std::{
+    collections::HashMap,
+    hash::{DefaultHasher, Hash, Hasher},
+    sync::{mpsc, Arc, Mutex},
+};
+
+use super::{
+    highlight_code::{highlight_code, Token},
+    language::find_language,
+};
+use crate::{sessions::sessions::Sessions, state::UiUpdate};
+
+/// Code blocks kept highlighted, the least recently shown are dropped first.
+const MAX_CACHED_BLOCKS: usize = 1000;
+
+pub type HighlightedLines = Arc<Vec<Vec<Token>>>;
+
+/// Nonce or id of the message, and the index of the code block in it.
+type CacheKey = (String, usize);
+
+struct CacheEntry {
+    /// Hash of the language and code, to notice edited messages.
+    hash: u64,
+    /// None while the code is being highlighted.
+    lines: Option<HighlightedLines>,
+    /// Value of Cache::clock when the block was last shown.
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct Cache {
+    entries: HashMap<CacheKey, CacheEntry>,
+    clock: u64,
+}
+
+struct Job {
+    /// The session the message belongs to, the account may be switched while it is highlighted.
+    session_id: u64,
+    channel_id: String,
+    key: CacheKey,
+    hash: u64,
+    language: String,
+    code: String,
+}
+
+/// Highlights code blocks on a background thread, and keeps the result per message.
+///
+/// When a code block is done, MessagesChanged is sent for its message so the row is rebuilt.
+pub struct Highlighter {
+    cache: Arc<Mutex<Cache>>,
+    jobs: mpsc::Sender<Job>,
+}
+
+impl Highlighter {
+    pub fn new(sessions: Sessions) -> Self {
+        let cache: Arc<Mutex<Cache>> = Arc::default();
+        let (jobs, receiver) = mpsc::channel::<Job>();
+
+        std::thread::spawn({
+            let cache = cache.clone();
+            move || {
+                for job in receiver {
+                    let lines = Arc::new(highlight_code(&job.language, &job.code));
+
+                    if let Ok(mut cache) = cache.lock() {
+                        match cache.entries.get_mut(&job.key) {
+                            Some(entry) if entry.hash == job.hash => entry.lines = Some(lines),
+                            // The message was edited or dropped from the cache in the meantime.
+                            _ => continue,
+                        }
+                    }
+
+                    let Some(session) = sessions.get(job.session_id) else {
+                        continue;
+                    };
+                    let index = session
+                        .app_state
+                        .blocking_read()
+                        .messages
+                        .get(&job.channel_id)
+                        .and_then(|messages| {
+                            messages
+                                .iter()
+                                .position(|v| v.id == job.key.0 || v.nonce == job.key.0)
+                        });
+
+                    // Only reaches the window while the session is shown.
+                    if let Some(index) = index {
+                        let _ = session
+                            .update_sender
+                            .send(UiUpdate::MessagesChanged(job.channel_id, index..index + 1));
+                    }
+                }
+            }
+        });
+
+        Self { cache, jobs }
+    }
+
+    /// The highlighted lines of a code block, None if the language is unknown
+    /// or the block is still being highlighted.
+    pub fn get(
+        &self,
+        session_id: u64,
+        channel_id: &str,
+        message_key: &str,
+        block_index: usize,
+        language: &str,
+        code: &str,
+    ) -> Option<HighlightedLines> {
+        find_language(language)?;
+
+        let mut hasher = DefaultHasher::new();
+        (language, code).hash(&mut hasher);
+        let hash = hasher.finish();
+
+        let key = (message_key.to_string(), block_index);
+        let mut cache = self.cache.lock().ok()?;
+        cache.clock += 1;
+        let clock = cache.clock;
+
+        if let Some(entry) = cache.entries.get_mut(&key).filter(|v| v.hash == hash) {
+            entry.last_used = clock;
+            return entry.lines.clone();
+        }
+
+        if cache.entries.len() >= MAX_CACHED_BLOCKS && !cache.entries.contains_key(&key) {
+            let oldest = cache
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                cache.entries.remove(&oldest);
+            }
+        }
+
+        cache.entries.insert(
+            key.clone(),
+            CacheEntry {
+                hash,
+                lines: None,
+                last_used: clock,
+            },
+        );
+        let _ = self.jobs.send(Job {
+            session_id,
+            channel_id: channel_id.to_string(),
+            key,
+            hash,
+            language: language.to_string(),
+            code: code.to_string(),
+        });
+
+        None
+    }
+}
@@ -0,0 +1,296 @@
+/// How the tokens of a language are recognized.
+#[derive(Debug)]
+pub struct Language {
+    pub line_comments: &'static [&'static str],
+    pub block_comment: Option<(&'static str, &'static str)>,
+    pub quotes: &'static [char],
+    /// Quotes that can be tripled for multi line strings, like """ in python.
+    pub triple_quotes: bool,
+    pub keywords: &'static [&'static str],
+    /// Constants like true, false and null.
+    pub literals: &'static [&'static str],
+    pub types: &'static [&'static str],
+    /// Identifiers starting with an uppercase letter are types.
+    pub capitalized_types: bool,
+    pub case_insensitive: bool,
+    /// 'c' is a character and 'a a lifetime, #[...] an attribute, name! a macro.
+    pub rust_syntax: bool,
+    /// $name and ${name} are variables.
+    pub dollar_variables: bool,
+    /// Strings before a ':' are object keys.
+    pub string_keys: bool,
+    /// name = value lines and [section] headers.
+    pub toml_syntax: bool,
+    /// Lines are colored by their first character.
+    pub diff_syntax: bool,
+}
+
+const PLAIN: Language = Language {
+    line_comments: &[],
+    block_comment: None,
+    quotes: &[],
+    triple_quotes: false,
+    keywords: &[],
+    literals: &[],
+    types: &[],
+    capitalized_types: false,
+    case_insensitive: false,
+    rust_syntax: false,
+    dollar_variables: false,
+    string_keys: false,
+    toml_syntax: false,
+    diff_syntax: false,
+};
+
+const RUST: Language = Language {
+    line_comments: &["//"],
+    block_comment: Some(("/*", "*/")),
+    quotes: &['"'],
+    keywords: &[
+        "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum",
+        "extern", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut",
+        "pub", "ref", "return", "self", "Self", "static", "struct", "super", "trait", "type",
+        "unsafe", "use", "where", "while", "yield",
+    ],
+    literals: &["true", "false", "None", "Some", "Ok", "Err"],
+    types: &[
+        "bool", "char", "str", "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32",
+        "i64", "i128", "isize", "f32", "f64",
+    ],
+    capitalized_types: true,
+    rust_syntax: true,
+    ..PLAIN
+};
+
+const PYTHON: Language = Language {
+    line_comments: &["#"],
+    quotes: &['"', '\''],
+    triple_quotes: true,
+    keywords: &[
+        "and", "as", "assert", "async", "await", "break", "class", "continue", "def", "del",
+        "elif", "else", "except", "finally", "for", "from", "global", "if", "import", "in", "is",
+        "lambda", "nonlocal", "not", "or", "pass", "raise", "return", "try", "while", "with",
+        "yield", "self",
+    ],
+    literals: &["True", "False", "None"],
+    types: &[
+        "int", "float", "str", "bool", "bytes", "list", "dict", "set", "tuple", "object",
+    ],
+    capitalized_types: true,
+    ..PLAIN
+};
+
+const JAVASCRIPT: Language = Language {
+    line_comments: &["//"],
+    block_comment: Some(("/*", "*/")),
+    quotes: &['"', '\'', '`'],
+    keywords: &[
+        "abstract",
+        "as",
+        "async",
+        "await",
+        "break",
+        "case",
+        "catch",
+        "class",
+        "const",
+        "continue",
+        "debugger",
+        "declare",
+        "default",
+        "delete",
+        "do",
+        "else",
+        "enum",
+        "export",
+        "extends",
+        "finally",
+        "for",
+        "from",
+        "function",
+        "get",
+        "if",
+        "implements",
+        "import",
+        "in",
+        "instanceof",
+        "interface",
+        "keyof",
+        "let",
+        "namespace",
+        "new",
+        "of",
+        "private",
+        "protected",
+        "public",
+        "readonly",
+        "return",
+        "set",
+        "static",
+        "super",
+        "switch",
+        "this",
+        "throw",
+        "try",
+        "type",
+        "typeof",
+        "var",
+        "void",
+        "while",
+        "with",
+        "yield",
+    ],
+    literals: &["true", "false", "null", "undefined", "NaN", "Infinity"],
+    types: &[
+        "any", "boolean", "never", "number", "object", "string", "symbol", "unknown", "bigint",
+    ],
+    capitalized_types: true,
+    ..PLAIN
+};
+
+const JSON: Language = Language {
+    line_comments: &["//"],
+    quotes: &['"'],
+    literals: &["true", "false", "null"],
+    string_keys: true,
+    ..PLAIN
+};
+
+const TOML: Language = Language {
+    line_comments: &["#"],
+    quotes: &['"', '\''],
+    triple_quotes: true,
+    literals: &["true", "false", "inf", "nan"],
+    toml_syntax: true,
+    ..PLAIN
+};
+
+const BASH: Language = Language {
+    line_comments: &["#"],
+    quotes: &['"', '\''],
+    keywords: &[
+        "if", "then", "else", "elif", "fi", "for", "while", "until", "do", "done", "case", "esac",
+        "in", "function", "return", "exit", "export", "local", "readonly", "source", "alias",
+        "unset", "shift", "break", "continue",
+    ],
+    literals: &["true", "false"],
+    dollar_variables: true,
+    ..PLAIN
+};
+
+const SQL: Language = Language {
+    line_comments: &["--"],
+    block_comment: Some(("/*", "*/")),
+    quotes: &['\'', '"'],
+    keywords: &[
+        "add",
+        "all",
+        "alter",
+        "and",
+        "as",
+        "asc",
+        "begin",
+        "between",
+        "by",
+        "case",
+        "check",
+        "column",
+        "commit",
+        "constraint",
+        "create",
+        "cross",
+        "database",
+        "default",
+        "delete",
+        "desc",
+        "distinct",
+        "drop",
+        "else",
+        "end",
+        "exists",
+        "foreign",
+        "from",
+        "full",
+        "group",
+        "having",
+        "if",
+        "in",
+        "index",
+        "inner",
+        "insert",
+        "into",
+        "is",
+        "join",
+        "key",
+        "left",
+        "like",
+        "limit",
+        "not",
+        "offset",
+        "on",
+        "or",
+        "order",
+        "outer",
+        "primary",
+        "references",
+        "returning",
+        "right",
+        "rollback",
+        "select",
+        "set",
+        "table",
+        "then",
+        "transaction",
+        "union",
+        "unique",
+        "update",
+        "values",
+        "view",
+        "when",
+        "where",
+        "with",
+    ],
+    literals: &["null", "true", "false"],
+    types: &[
+        "bigint",
+        "blob",
+        "boolean",
+        "char",
+        "date",
+        "decimal",
+        "float",
+        "int",
+        "integer",
+        "json",
+        "numeric",
+        "real",
+        "serial",
+        "smallint",
+        "text",
+        "timestamp",
+        "uuid",
+        "varchar",
+    ],
+    case_insensitive: true,
+    ..PLAIN
+};
+
+const DIFF: Language = Language {
+    diff_syntax: true,
+    ..PLAIN
+};
+
+/// The language of a code block, by the name or file extension written after the ```.
+pub fn find_language(name: &str) -> Option<&'static Language> {
+    match name.to_ascii_lowercase().as_str() {
+        "rust" | "rs" => Some(&RUST),
+        "python" | "py" => Some(&PYTHON),
+        "javascript" | "js" | "jsx" | "mjs" | "typescript" | "ts" | "tsx" => Some(&JAVASCRIPT),
+        "json" | "jsonc" => Some(&JSON),
+        "toml" => Some(&TOML),
+        "bash" | "sh" | "shell" | "zsh" | "console" => Some(&BASH),
+        "sql" | "postgresql" | "mysql" | "sqlite" => Some(&SQL),
+        "diff" | "patch" => Some(&DIFF),
+        _ => None,
+    }
+}
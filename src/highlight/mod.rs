@@ -0,0 +1,6 @@
+pub mod highlight_code;
+pub mod highlighter;
+pub mod language;
+
+#[cfg(test)]
+mod tests;
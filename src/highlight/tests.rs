@@ -0,0 +1,145 @@
+use super::highlight_code::{highlight_code, Token, TokenKind};
+
+/// Language, code and the expected lines, in the notation written by show_lines.
+const CORPUS: &[(&str, &str, &str)] = &[
+    // Unknown languages are not colored.
+    (
+        "brainfuck",
+        "+[-->-[>>+>-----<<]<--<---]",
+        r#""+[-->-[>>+>-----<<]<--<---]""#,
+    ),
+    // Rust
+    (
+        "rust",
+        "fn main() {}",
+        r#"keyword"fn" " " function"main" "() {}""#,
+    ),
+    (
+        "rs",
+        "let x: u32 = 10u32;",
+        r#"keyword"let" " x: " type"u32" " = " number"10u32" ";""#,
+    ),
+    (
+        "rust",
+        "println!(\"hi {}\", 'c');",
+        r#"function"println!" "(" string"\"hi {}\"" ", " string"'c'" ");""#,
+    ),
+    (
+        "rust",
+        "fn f<'a>(s: &'a str) -> Option<String>",
+        r#"keyword"fn" " f<" type"'a" ">(s: &" type"'a" " " type"str" ") -> " type"Option" "<" type"String" ">""#,
+    ),
+    (
+        "rust",
+        "#[derive(Debug)]\nstruct A;",
+        r##"meta"#[derive(Debug)]" | keyword"struct" " " type"A" ";""##,
+    ),
+    (
+        "rust",
+        "a /* one\ntwo */ b // end",
+        r#""a " comment"/* one" | comment"two */" " b " comment"// end""#,
+    ),
+    ("rust", "if a != b {}", r#"keyword"if" " a != b {}""#),
+    ("rust", "1.5 + x.0", r#"number"1.5" " + x." number"0""#),
+    // Python
+    (
+        "py",
+        "def f(x):\n    return None  # done",
+        r##"keyword"def" " " function"f" "(x):" | "    " keyword"return" " " literal"None" "  " comment"# done""##,
+    ),
+    (
+        "python",
+        "s = \"\"\"a\nb\"\"\"",
+        r#""s = " string"\"\"\"a" | string"b\"\"\"""#,
+    ),
+    // Strings end at the end of the line.
+    ("js", "'open\nnext", r#"string"'open" | "next""#),
+    ("js", "`a\nb`", r#"string"`a" | string"b`""#),
+    // JSON keys
+    (
+        "json",
+        "{\"key\": \"value\", \"n\": 1}",
+        r#""{" property"\"key\"" ": " string"\"value\"" ", " property"\"n\"" ": " number"1" "}""#,
+    ),
+    // TOML
+    (
+        "toml",
+        "[package]\nname = \"client\"",
+        r#"meta"[package]" | property"name" " = " string"\"client\"""#,
+    ),
+    // Bash
+    (
+        "sh",
+        "echo $HOME ${PATH} $?",
+        r#""echo " property"$HOME" " " property"${PATH}" " " property"$?""#,
+    ),
+    // SQL keywords are case insensitive.
+    (
+        "sql",
+        "select * FROM users",
+        r#"keyword"select" " * " keyword"FROM" " users""#,
+    ),
+    // Diff lines are colored by their first character.
+    (
+        "diff",
+        "@@ -1 +1 @@\n-old\n+new\n same",
+        r#"meta"@@ -1 +1 @@" | removed"-old" | added"+new" | " same""#,
+    ),
+    // Empty code is one empty line.
+    ("rust", "", ""),
+];
+
+/// Writes lines as tokens separated by spaces, the kind before each quoted text, lines separated by |.
+fn show_lines(lines: &[Vec<Token>]) -> String {
+    lines
+        .iter()
+        .map(|line| {
+            line.iter()
+                .map(|token| {
+                    let text = format!("{:?}", token.text);
+                    match token.kind {
+                        TokenKind::Plain => text,
+                        kind => format!("{}{}", kind.name(), text),
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .collect::<Vec<_>>()
+        .join(" | ")
+}
+
+#[test]
+fn highlights_corpus() {
+    let mut failures = Vec::new();
+
+    for (language, code, expected) in CORPUS {
+        let actual = show_lines(&highlight_code(language, code));
+        if actual != *expected {
+            failures.push(format!(
+                "{} {:?}\n  expected: {}\n  actual:   {}",
+                language, code, expected, actual
+            ));
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "{} of {} cases failed:\n{}",
+        failures.len(),
+        CORPUS.len(),
+        failures.join("\n")
+    );
+}
+
+#[test]
+fn keeps_the_code() {
+    for (language, code, _) in CORPUS {
+        let lines = highlight_code(language, code);
+        let text: Vec<String> = lines
+            .iter()
+            .map(|line| line.iter().map(|v| v.text.as_str()).collect())
+            .collect();
+        assert_eq!(text.join("\n"), *code, "{}", language);
+    }
+}
@@ -0,0 +1,90 @@
+use std::env;
+
+use once_cell::sync::OnceCell;
+use tracing_appender::{
+    non_blocking::WorkerGuard,
+    rolling::{RollingFileAppender, Rotation},
+};
+use tracing_subscriber::{
+    fmt::{self, format::FmtSpan},
+    prelude::*,
+    reload, EnvFilter, Registry,
+};
+
+use crate::{config::config::Logging, utils::app_dirs::app_dirs};
+
+/// Changes the filter when the config file changes.
+static FILTER: OnceCell<reload::Handle<EnvFilter, Registry>> = OnceCell::new();
+
+/// Logs to stderr and, with logging.file, to a file per day in the logs folder of the data directory.
+///
+/// RUST_LOG replaces logging.filter. Called once after init_config, what happens before
+/// is printed to stderr. The returned guard writes the rest of the file when it is dropped.
+pub fn init_logging(config: &Logging) -> Option<WorkerGuard> {
+    let (filter, handle) = reload::Layer::new(build_filter(config));
+    let _ = FILTER.set(handle);
+
+    let mut error = None;
+    let (file_layer, guard) = if config.file {
+        let appender = RollingFileAppender::builder()
+            .rotation(Rotation::DAILY)
+            .filename_prefix("client")
+            .filename_suffix("log")
+            .max_log_files(config.max_files)
+            .build(app_dirs().data.join("logs"));
+
+        match appender {
+            Ok(appender) => {
+                let (writer, guard) = tracing_appender::non_blocking(appender);
+                let layer = fmt::layer()
+                    .with_ansi(false)
+                    .with_span_events(FmtSpan::CLOSE)
+                    .with_writer(writer);
+                (Some(layer), Some(guard))
+            }
+            Err(e) => {
+                error = Some(e);
+                (None, None)
+            }
+        }
+    } else {
+        (None, None)
+    };
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(
+            fmt::layer()
+                .with_span_events(FmtSpan::CLOSE)
+                .with_writer(std::io::stderr),
+        )
+        .with(file_layer)
+        .init();
+
+    if let Some(e) = error {
+        tracing::error!("Failed to open the log file: {}", e);
+    }
+    guard
+}
+
+/// Applies a changed logging.filter, unless RUST_LOG is set.
+///
+/// The log file is only opened at startup, changes to the other settings apply after a restart.
+pub fn set_log_filter(config: &Logging) {
+    if env::var_os("RUST_LOG").is_some() {
+        return;
+    }
+    if let Some(handle) = FILTER.get() {
+        if let Err(e) = handle.reload(build_filter(config)) {
+            tracing::warn!("Failed to change the log filter: {}", e);
+        }
+    }
+}
+
+fn build_filter(config: &Logging) -> EnvFilter {
+    match env::var("RUST_LOG") {
+        Ok(directives) => EnvFilter::new(directives),
+        // Checked when the config is parsed.
+        Err(_) => EnvFilter::new(&config.filter),
+    }
+}
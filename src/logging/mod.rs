@@ -0,0 +1,5 @@
+pub mod init_logging;
+pub mod redact;
+
+#[cfg(test)]
+mod tests;
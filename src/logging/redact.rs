@@ -0,0 +1,61 @@
+use serde_json::Value;
+
+use crate::config::load_config::config;
+
+/// Keys whose values are never logged.
+const SECRET_KEYS: [&str; 4] = ["token", "password", "ticket", "authorization"];
+
+/// Keys of text written by users, logged with logging.show_message_content.
+const CONTENT_KEYS: [&str; 3] = ["content", "description", "bio"];
+
+/// A copy of a gateway or API payload that can be logged.
+///
+/// Tokens are always replaced, message content unless logging.show_message_content is set.
+pub fn redact_payload(json: &Value) -> Value {
+    redact_value(json, config().logging.show_message_content)
+}
+
+/// Message text for log lines, only its length unless logging.show_message_content is set.
+pub fn redact_content(content: &str) -> String {
+    if config().logging.show_message_content {
+        content.to_string()
+    } else {
+        redacted(content)
+    }
+}
+
+pub(super) fn redact_value(json: &Value, show_content: bool) -> Value {
+    match json {
+        Value::Object(object) => Value::Object(
+            object
+                .iter()
+                .map(|(key, value)| {
+                    let key_lowercase = key.to_ascii_lowercase();
+                    let value = match value.as_str() {
+                        Some(_) if SECRET_KEYS.contains(&key_lowercase.as_str()) => {
+                            Value::from("<redacted>")
+                        }
+                        Some(text)
+                            if !show_content && CONTENT_KEYS.contains(&key_lowercase.as_str()) =>
+                        {
+                            Value::from(redacted(text))
+                        }
+                        _ => redact_value(value, show_content),
+                    };
+                    (key.clone(), value)
+                })
+                .collect(),
+        ),
+        Value::Array(values) => Value::Array(
+            values
+                .iter()
+                .map(|v| redact_value(v, show_content))
+                .collect(),
+        ),
+        _ => json.clone(),
+    }
+}
+
+fn redacted(text: &str) -> String {
+    format!("<{} characters>", text.chars().count())
+}
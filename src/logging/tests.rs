@@ -0,0 +1,24 @@
+use serde_json::json;
+
+use super::redact::redact_value;
+
+#[test]
+fn redacts_tokens_and_content() {
+    let payload = json!({
+        "op": 2,
+        "d": {
+            "token": "secret",
+            "messages": [{ "id": "1", "content": "hello" }],
+        },
+    });
+
+    let redacted = redact_value(&payload, false);
+    assert_eq!(redacted["op"], 2);
+    assert_eq!(redacted["d"]["token"], "<redacted>");
+    assert_eq!(redacted["d"]["messages"][0]["id"], "1");
+    assert_eq!(redacted["d"]["messages"][0]["content"], "<5 characters>");
+
+    let shown = redact_value(&payload, true);
+    assert_eq!(shown["d"]["token"], "<redacted>");
+    assert_eq!(shown["d"]["messages"][0]["content"], "hello");
+}
@@ -2,6 +2,17 @@ use std::env;
 use std::error::Error;
 
 mod api;
+mod assets;
+mod auth;
+mod config;
+mod downloads;
+mod emoji;
+mod highlight;
+mod logging;
+mod markdown;
+mod media;
+mod messages;
+mod sessions;
 mod state;
 mod ui;
 mod utils;
@@ -10,27 +21,23 @@ mod websocket;
 slint::include_modules!();
 
 fn main() -> Result<(), Box<dyn Error>> {
-    api::initialize()?;
+    utils::app_dirs::init_app_dirs(env::args().skip(1));
+    if let Err(e) = config::load_config::init_config() {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+    // Kept until the end, the log file is written on a thread of its own.
+    let _log_guard = logging::init_logging::init_logging(&config::load_config::config().logging);
+    utils::app_dirs::migrate_old_files();
+    api::initialize();
 
-    api::fetch_profile_information("545218808806375439")?;
-
-    let app_state = state::create_app_state();
     let (update_sender, update_receiver) = state::create_update_channel();
+    config::watch_config::watch_config(update_sender.clone());
 
-    let app_state_clone = app_state.clone();
-    std::thread::spawn({
-        let update_sender = update_sender.clone();
-        move || {
-            let rt = tokio::runtime::Runtime::new().unwrap();
-            rt.block_on(async {
-                if let Err(e) = websocket::websocket::connect(app_state_clone, update_sender).await {
-                    eprintln!("WebSocket error: {}", e);
-                }
-            });
-        }
-    });
+    let sessions = sessions::sessions::Sessions::new(update_sender.clone());
+    auth::log_in::start_login(&sessions);
 
-    ui::run_app(app_state, update_receiver)?;
+    ui::run_app(sessions, update_sender, update_receiver)?;
 
     Ok(())
 }
\ No newline at end of file
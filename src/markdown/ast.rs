@@ -0,0 +1,54 @@
+/// A block of message content, in the order it appears in the message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Block {
+    /// Consecutive lines of text, lines are separated by Inline::LineBreak.
+    Paragraph(Vec<Inline>),
+    /// "# ", "## " or "### " at the start of a line.
+    Heading { level: u8, content: Vec<Inline> },
+    /// ```lang fenced code, language is empty if none was given.
+    Code { language: String, code: String },
+    /// "> " lines, or everything after ">>> ".
+    Quote(Vec<Block>),
+    /// "- " or "* " items, or "1. " items when start is set.
+    /// Every item is a paragraph, optionally followed by a nested list.
+    List {
+        start: Option<u64>,
+        items: Vec<Vec<Block>>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Inline {
+    Text(String),
+    LineBreak,
+    Bold(Vec<Inline>),
+    Italic(Vec<Inline>),
+    Underline(Vec<Inline>),
+    Strikethrough(Vec<Inline>),
+    Spoiler(Vec<Inline>),
+    Code(String),
+    /// A masked link [content](url), or a plain url, in which case content is the url.
+    Link {
+        content: Vec<Inline>,
+        url: String,
+    },
+    /// <@id> or <@!id>
+    UserMention(String),
+    /// <#id>
+    ChannelMention(String),
+    /// <@&id>
+    RoleMention(String),
+    /// <:name:id> or <a:name:id>
+    CustomEmoji {
+        name: String,
+        id: String,
+        animated: bool,
+    },
+    /// A unicode emoji from a :shortcode:.
+    Emoji(String),
+    /// <t:unix> or <t:unix:style>, style is one of "tTdDfFR", 'f' by default.
+    Timestamp {
+        unix: i64,
+        style: char,
+    },
+}
@@ -0,0 +1,55 @@
+use chrono::{Local, TimeZone};
+
+/// Formats the timestamp of a <t:unix:style> token in local time,
+/// the 'R' style is relative to now_ms.
+pub fn format_timestamp(unix: i64, style: char, now_ms: i64) -> String {
+    if style == 'R' {
+        return format_relative(unix.saturating_sub(now_ms / 1000));
+    }
+
+    let format = match style {
+        't' => "%H:%M",
+        'T' => "%H:%M:%S",
+        'd' => "%d/%m/%Y",
+        'D' => "%B %-d, %Y",
+        'F' => "%A, %B %-d, %Y %H:%M",
+        _ => "%B %-d, %Y %H:%M",
+    };
+
+    Local
+        .timestamp_opt(unix, 0)
+        .single()
+        .map(|v| v.format(format).to_string())
+        .unwrap_or_else(|| unix.to_string())
+}
+
+/// "in 5 minutes" or "3 hours ago".
+fn format_relative(seconds: i64) -> String {
+    const MINUTE: u64 = 60;
+    const HOUR: u64 = 60 * MINUTE;
+    const DAY: u64 = 24 * HOUR;
+    const MONTH: u64 = 30 * DAY;
+    const YEAR: u64 = 365 * DAY;
+
+    let distance = seconds.unsigned_abs();
+    let (value, unit) = match distance {
+        v if v < MINUTE => (v, "second"),
+        v if v < HOUR => (v / MINUTE, "minute"),
+        v if v < DAY => (v / HOUR, "hour"),
+        v if v < MONTH => (v / DAY, "day"),
+        v if v < YEAR => (v / MONTH, "month"),
+        v => (v / YEAR, "year"),
+    };
+
+    let amount = if value == 1 {
+        format!("1 {}", unit)
+    } else {
+        format!("{} {}s", value, unit)
+    };
+
+    if seconds > 0 {
+        format!("in {}", amount)
+    } else {
+        format!("{} ago", amount)
+    }
+}
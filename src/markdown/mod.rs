@@ -0,0 +1,8 @@
+pub mod ast;
+pub mod format_timestamp;
+pub mod parse_blocks;
+pub mod parse_inline;
+pub mod parse_markdown;
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,183 @@
+use super::{ast::Block, parse_inline::parse_inline};
+
+/// Parses text without code blocks into quotes, headings, lists and paragraphs.
+pub fn parse_blocks(text: &str) -> Vec<Block> {
+    let lines: Vec<&str> = text.split('\n').collect();
+    let mut blocks = Vec::new();
+    let mut paragraph: Vec<&str> = Vec::new();
+    let mut index = 0;
+
+    while index < lines.len() {
+        let line = lines[index];
+
+        // ">>> " quotes everything until the end of the message.
+        if let Some(quoted) = line.strip_prefix(">>> ") {
+            flush_paragraph(&mut paragraph, &mut blocks);
+
+            let mut content = vec![quoted];
+            content.extend(&lines[index + 1..]);
+            blocks.push(Block::Quote(parse_blocks(&content.join("\n"))));
+
+            return blocks;
+        }
+
+        if quote_line(line).is_some() {
+            flush_paragraph(&mut paragraph, &mut blocks);
+
+            let mut quoted = Vec::new();
+            while let Some(content) = lines.get(index).and_then(|v| quote_line(v)) {
+                quoted.push(content);
+                index += 1;
+            }
+            blocks.push(Block::Quote(parse_blocks(&quoted.join("\n"))));
+
+            continue;
+        }
+
+        if let Some((level, content)) = heading(line) {
+            flush_paragraph(&mut paragraph, &mut blocks);
+            blocks.push(Block::Heading {
+                level,
+                content: parse_inline(content),
+            });
+            index += 1;
+
+            continue;
+        }
+
+        if list_item(line).is_some() {
+            flush_paragraph(&mut paragraph, &mut blocks);
+
+            // Items and the indented lines below them.
+            let start = index;
+            index += 1;
+            while lines
+                .get(index)
+                .is_some_and(|v| list_item(v).is_some() || (indent(v) > 0 && !v.trim().is_empty()))
+            {
+                index += 1;
+            }
+            blocks.push(parse_list(&lines[start..index]));
+
+            continue;
+        }
+
+        paragraph.push(line);
+        index += 1;
+    }
+
+    flush_paragraph(&mut paragraph, &mut blocks);
+
+    blocks
+}
+
+/// Adds the collected lines as a paragraph, without empty lines at the start and end.
+fn flush_paragraph(paragraph: &mut Vec<&str>, blocks: &mut Vec<Block>) {
+    let start = paragraph.iter().position(|v| !v.trim().is_empty());
+    let end = paragraph.iter().rposition(|v| !v.trim().is_empty());
+
+    if let (Some(start), Some(end)) = (start, end) {
+        blocks.push(Block::Paragraph(parse_inline(
+            &paragraph[start..=end].join("\n"),
+        )));
+    }
+
+    paragraph.clear();
+}
+
+fn quote_line(line: &str) -> Option<&str> {
+    line.strip_prefix("> ")
+        .or_else(|| (line == ">").then_some(""))
+}
+
+/// Level and content of "# ", "## " and "### " lines.
+fn heading(line: &str) -> Option<(u8, &str)> {
+    let level = line.bytes().take_while(|&b| b == b'#').count();
+    if !(1..=3).contains(&level) {
+        return None;
+    }
+
+    let content = line[level..].strip_prefix(' ')?.trim();
+    (!content.is_empty()).then_some((level as u8, content))
+}
+
+/// Number of ordered items, and the content of "- ", "* " and "1. " lines.
+fn list_item(line: &str) -> Option<(Option<u64>, &str)> {
+    let trimmed = line.trim_start_matches([' ', '\t']);
+
+    let (number, content) = match trimmed
+        .strip_prefix("- ")
+        .or_else(|| trimmed.strip_prefix("* "))
+    {
+        Some(content) => (None, content),
+        None => {
+            let (number, content) = trimmed.split_once(". ")?;
+            if number.is_empty() || number.len() > 9 || !number.bytes().all(|b| b.is_ascii_digit())
+            {
+                return None;
+            }
+            (number.parse().ok(), content)
+        }
+    };
+
+    let content = content.trim();
+    (!content.is_empty()).then_some((number, content))
+}
+
+fn indent(line: &str) -> usize {
+    line.bytes()
+        .take_while(|&b| b == b' ' || b == b'\t')
+        .count()
+}
+
+/// Items at the indentation of the first line, deeper lines are parsed as part of the item above.
+fn parse_list(lines: &[&str]) -> Block {
+    let base_indent = indent(lines[0]);
+    let start = list_item(lines[0]).and_then(|(number, _)| number);
+
+    let mut items = Vec::new();
+    let mut current: Option<(&str, Vec<&str>)> = None;
+
+    for &line in lines {
+        match list_item(line) {
+            Some((_, content)) if indent(line) <= base_indent => {
+                if let Some(item) = current.take() {
+                    items.push(parse_list_item(item));
+                }
+                current = Some((content, Vec::new()));
+            }
+            _ => {
+                if let Some((_, nested)) = current.as_mut() {
+                    nested.push(line);
+                }
+            }
+        }
+    }
+
+    if let Some(item) = current.take() {
+        items.push(parse_list_item(item));
+    }
+
+    Block::List { start, items }
+}
+
+fn parse_list_item((content, nested): (&str, Vec<&str>)) -> Vec<Block> {
+    let mut blocks = vec![Block::Paragraph(parse_inline(content))];
+
+    if !nested.is_empty() {
+        let nested_indent = nested
+            .iter()
+            .filter(|v| !v.trim().is_empty())
+            .map(|v| indent(v))
+            .min()
+            .unwrap_or_default();
+
+        let dedented: Vec<&str> = nested
+            .iter()
+            .map(|v| v.get(nested_indent..).unwrap_or_default())
+            .collect();
+        blocks.extend(parse_blocks(&dedented.join("\n")));
+    }
+
+    blocks
+}
@@ -0,0 +1,329 @@
+use super::ast::Inline;
+use crate::emoji::shortcodes::find_shortcode;
+
+/// Delimiters of styled text, longer delimiters before their prefixes.
+const DELIMITERS: [&str; 6] = ["**", "__", "~~", "||", "*", "_"];
+
+/// Timestamp styles, see https://discord.com/developers/docs/reference#message-formatting-timestamp-styles
+const TIMESTAMP_STYLES: &str = "tTdDfFR";
+
+/// Parses the inline markdown of a paragraph, heading or list item.
+pub fn parse_inline(text: &str) -> Vec<Inline> {
+    let mut nodes = Vec::new();
+    let mut plain = String::new();
+    let mut previous: Option<char> = None;
+    let mut index = 0;
+
+    while let Some(ch) = text[index..].chars().next() {
+        let rest = &text[index..];
+
+        // Escaped markdown characters are kept as text.
+        if ch == '\\' {
+            if let Some(escaped) = rest[1..].chars().next().filter(char::is_ascii_punctuation) {
+                plain.push(escaped);
+                previous = Some(escaped);
+                index += 1 + escaped.len_utf8();
+                continue;
+            }
+        }
+
+        match parse_token(rest, previous) {
+            Some((Inline::Text(text), length)) => {
+                plain.push_str(&text);
+                previous = text.chars().last();
+                index += length;
+            }
+            Some((node, length)) => {
+                if !plain.is_empty() {
+                    nodes.push(Inline::Text(std::mem::take(&mut plain)));
+                }
+                nodes.push(node);
+                previous = rest[..length].chars().last();
+                index += length;
+            }
+            None => {
+                plain.push(ch);
+                previous = Some(ch);
+                index += ch.len_utf8();
+            }
+        }
+    }
+
+    if !plain.is_empty() {
+        nodes.push(Inline::Text(plain));
+    }
+
+    nodes
+}
+
+/// Parses the token at the start of rest, returns it with its length in bytes.
+fn parse_token(rest: &str, previous: Option<char>) -> Option<(Inline, usize)> {
+    match rest.as_bytes().first()? {
+        b'\n' => Some((Inline::LineBreak, 1)),
+        b'`' => parse_code(rest),
+        b'<' => parse_angle_token(rest),
+        b'[' => parse_masked_link(rest),
+        b'h' if !previous.is_some_and(char::is_alphanumeric) => parse_url(rest),
+        b':' if !previous.is_some_and(char::is_alphanumeric) => parse_shortcode(rest),
+        _ => parse_styled(rest, previous),
+    }
+}
+
+/// `code` or ``code``, longer runs of backticks are code blocks and kept as text here.
+fn parse_code(rest: &str) -> Option<(Inline, usize)> {
+    let ticks = rest.bytes().take_while(|&b| b == b'`').count();
+    let fence = &rest[..ticks];
+    if ticks > 2 {
+        return Some((Inline::Text(fence.to_string()), ticks));
+    }
+
+    let end = ticks + rest[ticks..].find(fence)?;
+    let code = rest[ticks..end].trim();
+    if code.is_empty() {
+        return None;
+    }
+
+    Some((Inline::Code(code.to_string()), end + ticks))
+}
+
+/// Mentions, custom emojis, timestamps and <url> links.
+fn parse_angle_token(rest: &str) -> Option<(Inline, usize)> {
+    let end = rest.find('>')?;
+    let inner = &rest[1..end];
+
+    let node = if let Some(id) = inner.strip_prefix("@&") {
+        is_snowflake(id).then(|| Inline::RoleMention(id.to_string()))
+    } else if let Some(id) = inner.strip_prefix("@!").or_else(|| inner.strip_prefix('@')) {
+        is_snowflake(id).then(|| Inline::UserMention(id.to_string()))
+    } else if let Some(id) = inner.strip_prefix('#') {
+        is_snowflake(id).then(|| Inline::ChannelMention(id.to_string()))
+    } else if let Some(emoji) = inner.strip_prefix("a:") {
+        parse_custom_emoji(emoji, true)
+    } else if let Some(emoji) = inner.strip_prefix(':') {
+        parse_custom_emoji(emoji, false)
+    } else if let Some(timestamp) = inner.strip_prefix("t:") {
+        parse_timestamp(timestamp)
+    } else if is_url(inner) {
+        Some(Inline::Link {
+            content: vec![Inline::Text(inner.to_string())],
+            url: inner.to_string(),
+        })
+    } else {
+        None
+    };
+
+    node.map(|node| (node, end + 1))
+}
+
+/// "name:id" of a custom emoji.
+fn parse_custom_emoji(emoji: &str, animated: bool) -> Option<Inline> {
+    let (name, id) = emoji.split_once(':')?;
+    if name.is_empty()
+        || !name.chars().all(|c| c.is_alphanumeric() || c == '_')
+        || !is_snowflake(id)
+    {
+        return None;
+    }
+
+    Some(Inline::CustomEmoji {
+        name: name.to_string(),
+        id: id.to_string(),
+        animated,
+    })
+}
+
+/// "unix" or "unix:style" of a timestamp.
+fn parse_timestamp(timestamp: &str) -> Option<Inline> {
+    let (unix, style) = match timestamp.split_once(':') {
+        Some((unix, style)) => {
+            let mut chars = style.chars();
+            let style = chars.next().filter(|c| TIMESTAMP_STYLES.contains(*c))?;
+            if chars.next().is_some() {
+                return None;
+            }
+            (unix, style)
+        }
+        None => (timestamp, 'f'),
+    };
+
+    Some(Inline::Timestamp {
+        unix: unix.parse().ok()?,
+        style,
+    })
+}
+
+/// :name: of a unicode emoji, like :thumbsup:.
+fn parse_shortcode(rest: &str) -> Option<(Inline, usize)> {
+    let end = 1 + rest[1..].find(':')?;
+    let name = &rest[1..end];
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "_+-".contains(c))
+    {
+        return None;
+    }
+
+    let emoji = find_shortcode(name)?;
+    Some((Inline::Emoji(emoji.to_string()), end + 1))
+}
+
+/// [content](url), the url may be wrapped in <> to suppress its embed.
+fn parse_masked_link(rest: &str) -> Option<(Inline, usize)> {
+    let content_end = rest.find("](")?;
+    let content = &rest[1..content_end];
+    if content.trim().is_empty() || content.contains(['[', ']', '\n']) {
+        return None;
+    }
+
+    let url_start = content_end + 2;
+    let url_end = url_start + rest[url_start..].find(')')?;
+    let url = rest[url_start..url_end].trim();
+    let url = url
+        .strip_prefix('<')
+        .and_then(|v| v.strip_suffix('>'))
+        .unwrap_or(url);
+    if !is_url(url) {
+        return None;
+    }
+
+    Some((
+        Inline::Link {
+            content: parse_inline(content),
+            url: url.to_string(),
+        },
+        url_end + 1,
+    ))
+}
+
+/// A plain http(s) url, without trailing punctuation.
+fn parse_url(rest: &str) -> Option<(Inline, usize)> {
+    if !rest.starts_with("http://") && !rest.starts_with("https://") {
+        return None;
+    }
+
+    let end = rest
+        .find(|c: char| c.is_whitespace() || c == '<')
+        .unwrap_or(rest.len());
+    let mut url = &rest[..end];
+
+    loop {
+        let trimmed = url.trim_end_matches(['.', ',', ':', ';', '!', '?', '"', '\'']);
+        // Keep closing parentheses that belong to the url, like in wikipedia links.
+        let trimmed = match trimmed.strip_suffix(')') {
+            Some(v) if v.matches('(').count() < trimmed.matches(')').count() => v,
+            _ => trimmed,
+        };
+        if trimmed.len() == url.len() {
+            break;
+        }
+        url = trimmed;
+    }
+
+    if !is_url(url) {
+        return None;
+    }
+
+    Some((
+        Inline::Link {
+            content: vec![Inline::Text(url.to_string())],
+            url: url.to_string(),
+        },
+        url.len(),
+    ))
+}
+
+/// Bold, italic, underline, strikethrough and spoilers.
+fn parse_styled(rest: &str, previous: Option<char>) -> Option<(Inline, usize)> {
+    for delimiter in DELIMITERS {
+        if !rest.starts_with(delimiter) {
+            continue;
+        }
+        let Some(close) = find_closing(rest, delimiter, previous) else {
+            continue;
+        };
+
+        let content = parse_inline(&rest[delimiter.len()..close]);
+        let node = match delimiter {
+            "**" => Inline::Bold(content),
+            "__" => Inline::Underline(content),
+            "~~" => Inline::Strikethrough(content),
+            "||" => Inline::Spoiler(content),
+            _ => Inline::Italic(content),
+        };
+
+        return Some((node, close + delimiter.len()));
+    }
+
+    None
+}
+
+/// Byte index of the delimiter closing the one at the start of rest.
+///
+/// Escaped characters and inline code are skipped. In a run of delimiter characters the
+/// last ones close, so ***text*** is bold italic text.
+fn find_closing(rest: &str, delimiter: &str, previous: Option<char>) -> Option<usize> {
+    let marker = delimiter.as_bytes()[0];
+    let start = delimiter.len();
+    let first = rest[start..].chars().next()?;
+
+    // "2 * 3 * 4" and snake_case_names are not italic.
+    if matches!(delimiter, "*" | "**") && first.is_whitespace() {
+        return None;
+    }
+    if delimiter == "_" && previous.is_some_and(char::is_alphanumeric) {
+        return None;
+    }
+
+    let bytes = rest.as_bytes();
+    let mut index = start;
+
+    while index < bytes.len() {
+        match bytes[index] {
+            b'\\' => index += 2,
+            b'`' => {
+                let ticks = bytes[index..].iter().take_while(|&&b| b == b'`').count();
+                let fence = &rest[index..index + ticks];
+                index += ticks;
+                if let Some(end) = rest[index..].find(fence) {
+                    index += end + ticks;
+                }
+            }
+            b if b == marker => {
+                let run = bytes[index..].iter().take_while(|&&b| b == marker).count();
+                // A double delimiter inside single ones is a nested bold or underline.
+                let nested = delimiter.len() == 1 && run == 2;
+
+                if run >= delimiter.len() && !nested {
+                    let close = index + run - delimiter.len();
+                    if close > start && is_valid_closing(rest, close, delimiter) {
+                        return Some(close);
+                    }
+                }
+                index += run;
+            }
+            _ => index += 1,
+        }
+    }
+
+    None
+}
+
+fn is_valid_closing(rest: &str, close: usize, delimiter: &str) -> bool {
+    match delimiter {
+        "*" | "**" => !rest[..close].ends_with(char::is_whitespace),
+        "_" => !rest[close + 1..].starts_with(char::is_alphanumeric),
+        _ => true,
+    }
+}
+
+fn is_snowflake(id: &str) -> bool {
+    !id.is_empty() && id.bytes().all(|b| b.is_ascii_digit())
+}
+
+fn is_url(text: &str) -> bool {
+    text.strip_prefix("https://")
+        .or_else(|| text.strip_prefix("http://"))
+        .is_some_and(|host| !host.is_empty())
+        && !text.contains(char::is_whitespace)
+}
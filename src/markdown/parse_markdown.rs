@@ -0,0 +1,43 @@
+use super::{ast::Block, parse_blocks::parse_blocks};
+
+/// Parses message content in discords markdown dialect.
+///
+/// Code blocks are split off first, so their content is never parsed as markdown.
+pub fn parse_markdown(content: &str) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    let mut rest = content;
+
+    while let Some(start) = rest.find("```") {
+        let code_start = start + 3;
+        let Some(length) = rest[code_start..].find("```") else {
+            break;
+        };
+
+        blocks.extend(parse_blocks(&rest[..start]));
+        blocks.push(parse_code_block(&rest[code_start..code_start + length]));
+
+        rest = &rest[code_start + length + 3..];
+    }
+
+    blocks.extend(parse_blocks(rest));
+
+    blocks
+}
+
+/// The language is the first line, if there are more lines and it is a single word.
+fn parse_code_block(fenced: &str) -> Block {
+    let (language, code) = match fenced.split_once('\n') {
+        Some((first, code)) if is_language(first.trim()) => (first.trim(), code),
+        _ => ("", fenced),
+    };
+
+    Block::Code {
+        language: language.to_string(),
+        code: code.strip_suffix('\n').unwrap_or(code).to_string(),
+    }
+}
+
+fn is_language(text: &str) -> bool {
+    text.chars()
+        .all(|c| c.is_ascii_alphanumeric() || "+-#._".contains(c))
+}
@@ -0,0 +1,242 @@
+use super::{
+    ast::{Block, Inline},
+    format_timestamp::format_timestamp,
+    parse_markdown::parse_markdown,
+};
+
+/// Message content and its expected AST, in the notation written by show_blocks.
+const CORPUS: &[(&str, &str)] = &[
+    // Plain text
+    ("hello world", r#"p["hello world"]"#),
+    ("", ""),
+    ("line one\nline two", r#"p["line one" br "line two"]"#),
+    ("\n\nspaced\n\n", r#"p["spaced"]"#),
+    ("a\n\nb", r#"p["a" br br "b"]"#),
+    // Styles
+    ("**bold**", r#"p[b["bold"]]"#),
+    (
+        "*italic* and _italic_",
+        r#"p[i["italic"] " and " i["italic"]]"#,
+    ),
+    ("__underline__", r#"p[u["underline"]]"#),
+    ("~~strike~~", r#"p[s["strike"]]"#),
+    ("||spoiler||", r#"p[spoiler["spoiler"]]"#),
+    ("***bold italic***", r#"p[b[i["bold italic"]]]"#),
+    ("__*underline italic*__", r#"p[u[i["underline italic"]]]"#),
+    ("*a **b** c*", r#"p[i["a " b["b"] " c"]]"#),
+    ("**multi\nline**", r#"p[b["multi" br "line"]]"#),
+    ("2 * 3 * 4", r#"p["2 * 3 * 4"]"#),
+    ("snake_case_name", r#"p["snake_case_name"]"#),
+    ("**unclosed", r#"p["**unclosed"]"#),
+    ("a ** b", r#"p["a ** b"]"#),
+    (r"\*not italic\*", r#"p["*not italic*"]"#),
+    (r"back\slash", r#"p["back\\slash"]"#),
+    // Inline code
+    ("`code`", r#"p[code"code"]"#),
+    ("``co`de``", r#"p[code"co`de"]"#),
+    ("`**not bold**`", r#"p[code"**not bold**"]"#),
+    ("**a `**` b**", r#"p[b["a " code"**" " b"]]"#),
+    ("`unclosed", r#"p["`unclosed"]"#),
+    // Code blocks
+    ("```rust\nfn main() {}\n```", r#"code(rust)"fn main() {}""#),
+    ("```\nplain\n```", r#"code()"plain""#),
+    ("```inline```", r#"code()"inline""#),
+    ("```two words\nx```", r#"code()"two words\nx""#),
+    (
+        "before\n```js\nlet a = `b`;\n```\nafter",
+        r#"p["before"] code(js)"let a = `b`;" p["after"]"#,
+    ),
+    ("```**not bold**```", r#"code()"**not bold**""#),
+    ("```unclosed", r#"p["```unclosed"]"#),
+    // Quotes
+    ("> quoted", r#"quote{p["quoted"]}"#),
+    (
+        "> one\n> two\nthree",
+        r#"quote{p["one" br "two"]} p["three"]"#,
+    ),
+    (">not a quote", r#"p[">not a quote"]"#),
+    (">>> all\nof\nthis", r#"quote{p["all" br "of" br "this"]}"#),
+    ("before\n>>> rest", r#"p["before"] quote{p["rest"]}"#),
+    ("> **bold** quote", r#"quote{p[b["bold"] " quote"]}"#),
+    // Headings
+    ("# Title", r#"h1["Title"]"#),
+    ("## Sub *title*", r#"h2["Sub " i["title"]]"#),
+    ("### Third", r#"h3["Third"]"#),
+    ("#### Too deep", "p[\"#### Too deep\"]"),
+    ("#NoSpace", r##"p["#NoSpace"]"##),
+    ("text\n# Title\ntext", r#"p["text"] h1["Title"] p["text"]"#),
+    // Lists
+    ("- one\n- two", r#"list{p["one"]; p["two"]}"#),
+    ("* one\n* two", r#"list{p["one"]; p["two"]}"#),
+    ("1. one\n2. two", r#"list(1){p["one"]; p["two"]}"#),
+    ("3. three", r#"list(3){p["three"]}"#),
+    (
+        "- one\n  - nested\n- two",
+        r#"list{p["one"] list{p["nested"]}; p["two"]}"#,
+    ),
+    ("- **bold** item", r#"list{p[b["bold"] " item"]}"#),
+    (
+        "intro\n- item\noutro",
+        r#"p["intro"] list{p["item"]} p["outro"]"#,
+    ),
+    ("-not a list", r#"p["-not a list"]"#),
+    ("1.5 is a number", r#"p["1.5 is a number"]"#),
+    // Links
+    (
+        "[masked](https://example.com)",
+        r#"p[link(https://example.com)["masked"]]"#,
+    ),
+    (
+        "[**bold**](<https://example.com>)",
+        r#"p[link(https://example.com)[b["bold"]]]"#,
+    ),
+    (
+        "[not a link](javascript:alert)",
+        r#"p["[not a link](javascript:alert)"]"#,
+    ),
+    (
+        "see https://example.com/a_b_c.",
+        r#"p["see " link(https://example.com/a_b_c)["https://example.com/a_b_c"] "."]"#,
+    ),
+    (
+        "(https://en.wikipedia.org/wiki/Rust_(language))",
+        r#"p["(" link(https://en.wikipedia.org/wiki/Rust_(language))["https://en.wikipedia.org/wiki/Rust_(language)"] ")"]"#,
+    ),
+    (
+        "<https://example.com>",
+        r#"p[link(https://example.com)["https://example.com"]]"#,
+    ),
+    ("xhttps://example.com", r#"p["xhttps://example.com"]"#),
+    // Mentions
+    ("hi <@123>", r#"p["hi " @user(123)]"#),
+    ("<@!123>", r#"p[@user(123)]"#),
+    ("<#456>", r#"p[#channel(456)]"#),
+    ("<@&789>", r#"p[@role(789)]"#),
+    ("<@abc>", r#"p["<@abc>"]"#),
+    ("a < b > c", r#"p["a < b > c"]"#),
+    ("**<@123>**", r#"p[b[@user(123)]]"#),
+    // Emojis
+    ("<:blob:111>", r#"p[emoji(blob,111)]"#),
+    ("<a:party_blob:222>", r#"p[animated_emoji(party_blob,222)]"#),
+    ("<:bad name:111>", r#"p["<:bad name:111>"]"#),
+    (
+        ":thumbsup: :slight_smile:",
+        r#"p[emoji("👍") " " emoji("🙂")]"#,
+    ),
+    (":not_an_emoji:", r#"p[":not_an_emoji:"]"#),
+    ("12:30:45", r#"p["12:30:45"]"#),
+    // Timestamps
+    ("<t:1700000000>", r#"p[timestamp(1700000000,f)]"#),
+    ("<t:1700000000:R>", r#"p[timestamp(1700000000,R)]"#),
+    ("<t:-1:d>", r#"p[timestamp(-1,d)]"#),
+    (
+        "<t:-9223372036854775808:R>",
+        r#"p[timestamp(-9223372036854775808,R)]"#,
+    ),
+    ("<t:1700000000:X>", r#"p["<t:1700000000:X>"]"#),
+    // Unicode
+    ("**héllo** 🌍", r#"p[b["héllo"] " 🌍"]"#),
+    ("_ünïcode_", r#"p[i["ünïcode"]]"#),
+];
+
+#[test]
+fn parses_corpus() {
+    let mut failures = Vec::new();
+
+    for (content, expected) in CORPUS {
+        let actual = show_blocks(&parse_markdown(content));
+        if actual != *expected {
+            failures.push(format!(
+                "content:  {:?}\nexpected: {}\nactual:   {}",
+                content, expected, actual
+            ));
+        }
+    }
+
+    assert!(failures.is_empty(), "\n{}", failures.join("\n\n"));
+}
+
+#[test]
+fn formats_relative_timestamps() {
+    let now_ms = 1_700_000_000_000;
+
+    assert_eq!(
+        format_timestamp(1_700_000_000, 'R', now_ms),
+        "0 seconds ago"
+    );
+    assert_eq!(format_timestamp(1_700_000_300, 'R', now_ms), "in 5 minutes");
+    assert_eq!(format_timestamp(1_699_996_400, 'R', now_ms), "1 hour ago");
+
+    // Extreme values from message content must not overflow.
+    assert_eq!(
+        format_timestamp(i64::MIN, 'R', now_ms),
+        "292471208677 years ago"
+    );
+    assert_eq!(
+        format_timestamp(i64::MAX, 'R', now_ms),
+        "in 292471208623 years"
+    );
+    assert_eq!(
+        format_timestamp(i64::MAX, 'R', i64::MIN),
+        "in 292471208677 years"
+    );
+    assert_eq!(
+        format_timestamp(i64::MIN, 'd', now_ms),
+        i64::MIN.to_string()
+    );
+}
+
+fn show_blocks(blocks: &[Block]) -> String {
+    blocks.iter().map(show_block).collect::<Vec<_>>().join(" ")
+}
+
+fn show_block(block: &Block) -> String {
+    match block {
+        Block::Paragraph(content) => format!("p[{}]", show_inlines(content)),
+        Block::Heading { level, content } => format!("h{}[{}]", level, show_inlines(content)),
+        Block::Code { language, code } => format!("code({}){:?}", language, code),
+        Block::Quote(blocks) => format!("quote{{{}}}", show_blocks(blocks)),
+        Block::List { start, items } => {
+            let items = items
+                .iter()
+                .map(|v| show_blocks(v))
+                .collect::<Vec<_>>()
+                .join("; ");
+            match start {
+                Some(start) => format!("list({}){{{}}}", start, items),
+                None => format!("list{{{}}}", items),
+            }
+        }
+    }
+}
+
+fn show_inlines(inlines: &[Inline]) -> String {
+    inlines
+        .iter()
+        .map(show_inline)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn show_inline(inline: &Inline) -> String {
+    match inline {
+        Inline::Text(text) => format!("{:?}", text),
+        Inline::LineBreak => "br".to_string(),
+        Inline::Bold(content) => format!("b[{}]", show_inlines(content)),
+        Inline::Italic(content) => format!("i[{}]", show_inlines(content)),
+        Inline::Underline(content) => format!("u[{}]", show_inlines(content)),
+        Inline::Strikethrough(content) => format!("s[{}]", show_inlines(content)),
+        Inline::Spoiler(content) => format!("spoiler[{}]", show_inlines(content)),
+        Inline::Code(code) => format!("code{:?}", code),
+        Inline::Link { content, url } => format!("link({})[{}]", url, show_inlines(content)),
+        Inline::UserMention(id) => format!("@user({})", id),
+        Inline::ChannelMention(id) => format!("#channel({})", id),
+        Inline::RoleMention(id) => format!("@role({})", id),
+        Inline::CustomEmoji { name, id, animated } => {
+            let kind = if *animated { "animated_emoji" } else { "emoji" };
+            format!("{}({},{})", kind, name, id)
+        }
+        Inline::Emoji(emoji) => format!("emoji({:?})", emoji),
+        Inline::Timestamp { unix, style } => format!("timestamp({},{})", unix, style),
+    }
+}
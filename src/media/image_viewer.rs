@@ -0,0 +1,84 @@
+use std::error::Error;
+
+use slint::{Rgba8Pixel, SharedPixelBuffer};
+use tracing::warn;
+
+use crate::state::{AppState, Attachment, ImageViewer, UiUpdate, UpdateSender, HTTP_CLIENT};
+
+/// Longest side the full size image is requested at, larger images are scaled down by discord.
+const MAX_SIZE: u32 = 2048;
+
+/// Opens the image viewer for an image attachment and loads the image in full size.
+pub async fn open_image_viewer(
+    app_state: AppState,
+    update_sender: UpdateSender,
+    attachment: Attachment,
+) {
+    app_state.write().await.image_viewer = Some(ImageViewer {
+        attachment: attachment.clone(),
+        pixels: None,
+    });
+    let _ = update_sender.send(UiUpdate::ImageViewerChanged);
+
+    let pixels = match fetch_image(&attachment).await {
+        Ok(pixels) => pixels,
+        Err(e) => {
+            warn!("Failed to load {}: {}", attachment.filename, e);
+            return;
+        }
+    };
+
+    let mut app_data = app_state.write().await;
+    // The viewer may have been closed, or opened for another image.
+    let Some(viewer) = app_data
+        .image_viewer
+        .as_mut()
+        .filter(|v| v.attachment.id == attachment.id)
+    else {
+        return;
+    };
+    viewer.pixels = Some(pixels);
+    drop(app_data);
+
+    let _ = update_sender.send(UiUpdate::ImageViewerChanged);
+}
+
+async fn fetch_image(
+    attachment: &Attachment,
+) -> Result<SharedPixelBuffer<Rgba8Pixel>, Box<dyn Error + Send + Sync>> {
+    let longest_side = attachment.width.max(attachment.height);
+    let url = if longest_side > MAX_SIZE {
+        let separator = if attachment.proxy_url.contains('?') {
+            '&'
+        } else {
+            '?'
+        };
+        format!(
+            "{}{}width={}&height={}",
+            attachment.proxy_url,
+            separator,
+            attachment.width * MAX_SIZE / longest_side,
+            attachment.height * MAX_SIZE / longest_side
+        )
+    } else {
+        attachment.proxy_url.clone()
+    };
+
+    let bytes = HTTP_CLIENT
+        .get(&url)
+        .send()
+        .await?
+        .error_for_status()?
+        .bytes()
+        .await?;
+
+    // Decoding large images takes a while, keep it off the runtime threads.
+    let image = tokio::task::spawn_blocking(move || image::load_from_memory(&bytes)).await??;
+    let image = image.to_rgba8();
+
+    Ok(SharedPixelBuffer::<Rgba8Pixel>::clone_from_slice(
+        image.as_raw(),
+        image.width(),
+        image.height(),
+    ))
+}
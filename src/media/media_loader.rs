@@ -0,0 +1,53 @@
+use std::{cell::RefCell, collections::HashMap};
+
+use slint::Image;
+use tokio::runtime::Handle;
+
+use super::thumbhash::placeholder_image;
+use crate::{
+    assets::image_loader::ImageLoader,
+    state::{EmbedMedia, Sticker, UiUpdate, UpdateSender},
+};
+
+/// Loads embed images, attachment previews and stickers,
+/// MediaReady is sent with the url of the image or the id of the sticker when it is downloaded.
+pub struct MediaLoader {
+    images: ImageLoader<EmbedMedia>,
+    stickers: ImageLoader<Sticker>,
+    /// Decoded thumbhashes by their base64.
+    placeholders: RefCell<HashMap<String, Image>>,
+}
+
+impl MediaLoader {
+    pub fn new(runtime: Handle, update_sender: UpdateSender) -> Self {
+        Self {
+            images: ImageLoader::new(runtime.clone(), update_sender.clone(), UiUpdate::MediaReady),
+            stickers: ImageLoader::new(runtime, update_sender, UiUpdate::MediaReady),
+            placeholders: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// The blurred thumbhash shown while the image downloads, empty if it has none.
+    pub fn placeholder(&self, media: &EmbedMedia) -> Image {
+        if let Some(image) = self.placeholders.borrow().get(&media.placeholder) {
+            return image.clone();
+        }
+
+        let image =
+            placeholder_image(&media.placeholder, media.placeholder_version).unwrap_or_default();
+        self.placeholders
+            .borrow_mut()
+            .insert(media.placeholder.clone(), image.clone());
+        image
+    }
+
+    /// The image, empty until it is downloaded.
+    pub fn load(&self, media: &EmbedMedia) -> Image {
+        self.images.load(media)
+    }
+
+    /// The still image of a sticker, empty until it is downloaded and for lottie stickers.
+    pub fn load_sticker(&self, sticker: &Sticker) -> Image {
+        self.stickers.load(sticker)
+    }
+}
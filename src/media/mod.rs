@@ -0,0 +1,6 @@
+pub mod image_viewer;
+pub mod media_loader;
+pub mod thumbhash;
+
+#[cfg(test)]
+mod tests;
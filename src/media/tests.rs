@@ -0,0 +1,107 @@
+use base64::{engine::general_purpose::STANDARD_NO_PAD, Engine};
+
+use super::thumbhash::{decode_thumbhash, placeholder_image};
+
+struct Vector {
+    hash: &'static str,
+    width: u32,
+    height: u32,
+    /// Sum of all RGBA bytes.
+    sum: u64,
+    /// RGBA of the top left, center and bottom right pixels.
+    pixels: [[u8; 4]; 3],
+}
+
+/// Expected output of the reference javascript decoder from https://github.com/evanw/thumbhash
+const VECTORS: &[Vector] = &[
+    // Portrait with alpha, the placeholder of an embed image in a MESSAGE_UPDATE.
+    Vector {
+        hash: "+OeBCwIPNGvHCkYqDLGVAxASVHZTVmc=",
+        width: 19,
+        height: 32,
+        sum: 567587,
+        pixels: [
+            [207, 232, 240, 255],
+            [231, 230, 218, 255],
+            [194, 232, 253, 255],
+        ],
+    },
+    // Portrait without alpha.
+    Vector {
+        hash: "1QcSHQRnh493V4dIh4eXh1h4kJUI",
+        width: 23,
+        height: 32,
+        sum: 375116,
+        pixels: [[64, 77, 113, 255], [140, 109, 88, 255], [0, 4, 39, 255]],
+    },
+    // Square with transparent corners.
+    Vector {
+        hash: "YJqGPQw7sFlslqhFafSE+Q6oJ1h2iHB2Rw",
+        width: 32,
+        height: 32,
+        sum: 577328,
+        pixels: [[228, 75, 51, 0], [107, 102, 109, 255], [255, 136, 0, 0]],
+    },
+    // Landscape without alpha.
+    Vector {
+        hash: "3OcRJYB4d3h/iIeHeEh3eIhw+j2w",
+        width: 32,
+        height: 23,
+        sum: 436778,
+        pixels: [
+            [124, 136, 159, 255],
+            [112, 124, 139, 255],
+            [86, 99, 46, 255],
+        ],
+    },
+];
+
+#[test]
+fn decodes_known_vectors() {
+    for vector in VECTORS {
+        let hash = STANDARD_NO_PAD
+            .decode(vector.hash.trim_end_matches('='))
+            .unwrap();
+        let thumbhash = decode_thumbhash(&hash).unwrap();
+
+        let (width, height) = (thumbhash.width, thumbhash.height);
+        assert_eq!(
+            (width, height),
+            (vector.width, vector.height),
+            "{}",
+            vector.hash
+        );
+        assert_eq!(thumbhash.pixels.len(), (width * height * 4) as usize);
+
+        let sum: u64 = thumbhash.pixels.iter().map(|&v| v as u64).sum();
+        assert_eq!(sum, vector.sum, "{}", vector.hash);
+
+        let pixel = |x: u32, y: u32| {
+            let index = ((y * width + x) * 4) as usize;
+            let mut pixel = [0; 4];
+            pixel.copy_from_slice(&thumbhash.pixels[index..index + 4]);
+            pixel
+        };
+        let pixels = [
+            pixel(0, 0),
+            pixel(width / 2, height / 2),
+            pixel(width - 1, height - 1),
+        ];
+        assert_eq!(pixels, vector.pixels, "{}", vector.hash);
+    }
+}
+
+#[test]
+fn rejects_invalid_placeholders() {
+    assert!(decode_thumbhash(&[]).is_none());
+    assert!(decode_thumbhash(&[1, 2, 3, 4]).is_none());
+    assert!(placeholder_image("", 1).is_none());
+    assert!(placeholder_image("not base64!", 1).is_none());
+    assert!(placeholder_image(VECTORS[0].hash, 2).is_none());
+}
+
+#[test]
+fn creates_placeholder_images() {
+    let image = placeholder_image(VECTORS[0].hash, 1).unwrap();
+    assert_eq!((image.size().width, image.size().height), (19, 32));
+}
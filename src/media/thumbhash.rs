@@ -0,0 +1,208 @@
+use std::f64::consts::PI;
+
+use base64::{engine::general_purpose::STANDARD_NO_PAD, Engine};
+use slint::{Image, Rgba8Pixel, SharedPixelBuffer};
+
+/// Decoded placeholders are at most this many pixels on their longer side.
+const MAX_SIZE: f64 = 32.0;
+
+/// Pixels of a decoded thumbhash.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Thumbhash {
+    pub width: u32,
+    pub height: u32,
+    /// RGBA, row by row.
+    pub pixels: Vec<u8>,
+}
+
+/// The blurred placeholder of an embed image or attachment, from its base64 "placeholder".
+///
+/// Only placeholder_version 1 is known, None for other versions and invalid hashes.
+pub fn placeholder_image(placeholder: &str, version: u64) -> Option<Image> {
+    if version != 1 || placeholder.is_empty() {
+        return None;
+    }
+
+    // Discord pads the base64, thumbhashes from elsewhere are often unpadded.
+    let hash = STANDARD_NO_PAD
+        .decode(placeholder.trim_end_matches('='))
+        .ok()?;
+    let thumbhash = decode_thumbhash(&hash)?;
+
+    Some(Image::from_rgba8(
+        SharedPixelBuffer::<Rgba8Pixel>::clone_from_slice(
+            &thumbhash.pixels,
+            thumbhash.width,
+            thumbhash.height,
+        ),
+    ))
+}
+
+/// Decodes a thumbhash, see https://evanw.github.io/thumbhash/
+///
+/// The hash stores the average color and a few DCT coefficients of the image
+/// in the LPQ color space, with an alpha channel if the image has transparency.
+pub fn decode_thumbhash(hash: &[u8]) -> Option<Thumbhash> {
+    if hash.len() < 5 {
+        return None;
+    }
+
+    let header24 = hash[0] as u32 | (hash[1] as u32) << 8 | (hash[2] as u32) << 16;
+    let header16 = hash[3] as u32 | (hash[4] as u32) << 8;
+
+    let l_dc = (header24 & 63) as f64 / 63.0;
+    let p_dc = ((header24 >> 6) & 63) as f64 / 31.5 - 1.0;
+    let q_dc = ((header24 >> 12) & 63) as f64 / 31.5 - 1.0;
+    let l_scale = ((header24 >> 18) & 31) as f64 / 31.0;
+    let has_alpha = header24 >> 23 != 0;
+    let p_scale = ((header16 >> 3) & 63) as f64 / 63.0;
+    let q_scale = ((header16 >> 9) & 63) as f64 / 63.0;
+
+    let (lx, ly) = luminance_size(hash);
+    let (lx, ly) = (lx.max(3), ly.max(3));
+
+    let (a_dc, a_scale) = if has_alpha {
+        let byte = *hash.get(5)?;
+        ((byte & 15) as f64 / 15.0, (byte >> 4) as f64 / 15.0)
+    } else {
+        (1.0, 0.0)
+    };
+
+    // The coefficients are packed as nibbles after the header.
+    let mut reader = NibbleReader {
+        hash,
+        index: if has_alpha { 12 } else { 10 },
+    };
+    let l_ac = reader.read_channel(lx, ly, l_scale);
+    // Saturation is boosted to compensate for the quantization.
+    let p_ac = reader.read_channel(3, 3, p_scale * 1.25);
+    let q_ac = reader.read_channel(3, 3, q_scale * 1.25);
+    let a_ac = if has_alpha {
+        reader.read_channel(5, 5, a_scale)
+    } else {
+        Vec::new()
+    };
+
+    // The aspect ratio is approximated from the coefficient counts, before they are clamped.
+    let (ratio_x, ratio_y) = luminance_size(hash);
+    let ratio = ratio_x as f64 / ratio_y as f64;
+    let width = if ratio > 1.0 {
+        MAX_SIZE
+    } else {
+        (MAX_SIZE * ratio).round()
+    } as u32;
+    let height = if ratio > 1.0 {
+        (MAX_SIZE / ratio).round()
+    } else {
+        MAX_SIZE
+    } as u32;
+    if width == 0 || height == 0 {
+        return None;
+    }
+
+    let cx_stop = lx.max(if has_alpha { 5 } else { 3 });
+    let cy_stop = ly.max(if has_alpha { 5 } else { 3 });
+    let mut fx = vec![0.0; cx_stop];
+    let mut fy = vec![0.0; cy_stop];
+    let mut pixels = Vec::with_capacity((width * height * 4) as usize);
+
+    for y in 0..height {
+        for x in 0..width {
+            let (mut l, mut p, mut q, mut a) = (l_dc, p_dc, q_dc, a_dc);
+
+            for (cx, f) in fx.iter_mut().enumerate() {
+                *f = (PI / width as f64 * (x as f64 + 0.5) * cx as f64).cos();
+            }
+            for (cy, f) in fy.iter_mut().enumerate() {
+                *f = (PI / height as f64 * (y as f64 + 0.5) * cy as f64).cos();
+            }
+
+            let mut j = 0;
+            for (cy, fy) in fy.iter().enumerate().take(ly) {
+                let fy2 = fy * 2.0;
+                let mut cx = if cy > 0 { 0 } else { 1 };
+                while cx * ly < lx * (ly - cy) {
+                    l += l_ac[j] * fx[cx] * fy2;
+                    j += 1;
+                    cx += 1;
+                }
+            }
+
+            let mut j = 0;
+            for (cy, fy) in fy.iter().enumerate().take(3) {
+                let fy2 = fy * 2.0;
+                for fx in &fx[if cy > 0 { 0 } else { 1 }..3 - cy] {
+                    let f = fx * fy2;
+                    p += p_ac[j] * f;
+                    q += q_ac[j] * f;
+                    j += 1;
+                }
+            }
+
+            if has_alpha {
+                let mut j = 0;
+                for (cy, fy) in fy.iter().enumerate().take(5) {
+                    let fy2 = fy * 2.0;
+                    for fx in &fx[if cy > 0 { 0 } else { 1 }..5 - cy] {
+                        a += a_ac[j] * fx * fy2;
+                        j += 1;
+                    }
+                }
+            }
+
+            let b = l - 2.0 / 3.0 * p;
+            let r = (3.0 * l - b + q) / 2.0;
+            let g = r - q;
+
+            for value in [r, g, b, a] {
+                pixels.push((255.0 * value.clamp(0.0, 1.0)) as u8);
+            }
+        }
+    }
+
+    Some(Thumbhash {
+        width,
+        height,
+        pixels,
+    })
+}
+
+/// Number of luminance coefficients in x and y, which also give the aspect ratio.
+fn luminance_size(hash: &[u8]) -> (usize, usize) {
+    let header = hash[3] as usize;
+    let has_alpha = hash[2] & 0x80 != 0;
+    let is_landscape = hash[4] & 0x80 != 0;
+    let long_side = if has_alpha { 5 } else { 7 };
+
+    if is_landscape {
+        (long_side, header & 7)
+    } else {
+        (header & 7, long_side)
+    }
+}
+
+struct NibbleReader<'a> {
+    hash: &'a [u8],
+    /// Index of the next nibble.
+    index: usize,
+}
+
+impl NibbleReader<'_> {
+    /// The AC coefficients of one channel, in the order they are used by decode_thumbhash.
+    fn read_channel(&mut self, nx: usize, ny: usize, scale: f64) -> Vec<f64> {
+        let mut ac = Vec::new();
+
+        for cy in 0..ny {
+            let mut cx = if cy > 0 { 0 } else { 1 };
+            while cx * ny < nx * (ny - cy) {
+                let byte = self.hash.get(self.index / 2).copied().unwrap_or_default();
+                let nibble = (byte >> ((self.index & 1) * 4)) & 15;
+                ac.push((nibble as f64 / 7.5 - 1.0) * scale);
+                self.index += 1;
+                cx += 1;
+            }
+        }
+
+        ac
+    }
+}
@@ -0,0 +1,75 @@
+use std::error::Error;
+
+use serde_json::json;
+use tracing::{instrument, warn};
+
+use crate::{
+    config::load_config::config,
+    state::{AppState, ReadState, UiUpdate, UpdateSender, HTTP_CLIENT},
+};
+
+/// Marks a channel as read up to its last message, used when the channel is viewed.
+///
+/// The read state is updated locally first, then POST /channels/{id}/messages/{id}/ack is sent.
+/// Does nothing if the channel is already read.
+pub async fn ack_channel(app_state: AppState, update_sender: UpdateSender, channel_id: String) {
+    let message_id = {
+        let mut app_data = app_state.write().await;
+
+        let last_loaded_message_id = app_data
+            .messages
+            .get(&channel_id)
+            .and_then(|messages| messages.iter().rev().find_map(|v| v.id.parse::<u64>().ok()))
+            .unwrap_or_default();
+
+        let last_message_id = app_data
+            .last_message_id(&channel_id)
+            .max(last_loaded_message_id);
+        if last_message_id == 0 {
+            return;
+        }
+
+        let read_state = app_data.read_states.entry(channel_id.clone()).or_default();
+        if read_state.last_message_id >= last_message_id && read_state.mention_count == 0 {
+            return;
+        }
+
+        *read_state = ReadState {
+            last_message_id,
+            mention_count: 0,
+        };
+
+        last_message_id
+    };
+    let _ = update_sender.send(UiUpdate::ReadStateChanged(channel_id.clone()));
+
+    let authorization_token = app_state.read().await.token.clone();
+    if let Err(e) = post_ack(&authorization_token, &channel_id, message_id).await {
+        warn!("Failed to acknowledge channel: {}", e);
+    }
+}
+
+/// POST /channels/{id}/messages/{id}/ack
+#[instrument(level = "debug", name = "request", skip_all, fields(endpoint = "ack", %channel_id))]
+async fn post_ack(
+    authorization_token: &str,
+    channel_id: &str,
+    message_id: u64,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let url = format!(
+        "{}/channels/{}/messages/{}/ack",
+        config().endpoints.api,
+        channel_id,
+        message_id
+    );
+
+    HTTP_CLIENT
+        .post(&url)
+        .header("Authorization", authorization_token)
+        .json(&json!({ "token": null }))
+        .send()
+        .await?
+        .error_for_status()?;
+
+    Ok(())
+}
@@ -0,0 +1,73 @@
+use std::path::PathBuf;
+
+use crate::{
+    messages::{nonce::generate_nonce, outgoing_message::UploadFile},
+    state::{AppState, UiUpdate, UpdateSender},
+    utils::format_size::format_size,
+};
+
+/// Discord accepts at most this many files per message.
+pub const MAX_FILES: usize = 10;
+
+/// Attaches files to the composer of a channel, from the file picker or dropped on the window.
+///
+/// Files over the upload limit of the account are rejected before anything is uploaded,
+/// the reason is shown above the composer.
+pub async fn add_composer_files(
+    app_state: AppState,
+    update_sender: UpdateSender,
+    channel_id: String,
+    paths: Vec<PathBuf>,
+) {
+    let mut files = Vec::new();
+    let mut errors = Vec::new();
+    let max_size = app_state.read().await.max_upload_size();
+
+    for path in paths {
+        let filename = path
+            .file_name()
+            .map(|v| v.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        match tokio::fs::metadata(&path).await {
+            Ok(metadata) if !metadata.is_file() => {
+                errors.push(format!("{} is not a file", filename));
+            }
+            Ok(metadata) if metadata.len() > max_size => {
+                errors.push(format!(
+                    "{} is {}, files can be at most {}",
+                    filename,
+                    format_size(metadata.len()),
+                    format_size(max_size)
+                ));
+            }
+            Ok(metadata) => files.push(UploadFile {
+                id: generate_nonce(),
+                path,
+                filename,
+                size: metadata.len(),
+                description: String::new(),
+                spoiler: false,
+            }),
+            Err(e) => errors.push(format!("Could not read {}: {}", path.display(), e)),
+        }
+    }
+
+    {
+        let mut app_data = app_state.write().await;
+        let attached = app_data.composer_files.entry(channel_id).or_default();
+
+        let free = MAX_FILES.saturating_sub(attached.len());
+        if files.len() > free {
+            files.truncate(free);
+            errors.push(format!(
+                "At most {} files can be sent in one message",
+                MAX_FILES
+            ));
+        }
+        attached.extend(files);
+
+        app_data.composer_error = (!errors.is_empty()).then(|| errors.join("\n"));
+    }
+    let _ = update_sender.send(UiUpdate::ComposerFilesChanged);
+}
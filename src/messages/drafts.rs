@@ -0,0 +1,51 @@
+use std::{collections::HashMap, error::Error, fs, path::PathBuf};
+
+use crate::utils::app_dirs::app_dirs;
+
+/// One file per account, None for a session that has not logged in yet.
+fn drafts_path(user_id: &str) -> Option<PathBuf> {
+    if user_id.is_empty() || !user_id.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    Some(
+        app_dirs()
+            .data
+            .join("drafts")
+            .join(format!("{}.json", user_id)),
+    )
+}
+
+/// Loads the unsent composer text per channel id of an account, saved by save_drafts.
+///
+/// The drafts.json of versions with a single account goes to the first account that logs in.
+pub fn load_drafts(user_id: &str) -> HashMap<String, String> {
+    let Some(path) = drafts_path(user_id) else {
+        return HashMap::new();
+    };
+
+    let legacy_path = app_dirs().data.join("drafts.json");
+    if !path.exists() && legacy_path.exists() {
+        if let Some(folder) = path.parent() {
+            let _ = fs::create_dir_all(folder);
+        }
+        let _ = fs::rename(&legacy_path, &path);
+    }
+
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|v| serde_json::from_str(&v).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_drafts(user_id: &str, drafts: &HashMap<String, String>) -> Result<(), Box<dyn Error>> {
+    let Some(path) = drafts_path(user_id) else {
+        return Ok(());
+    };
+    if let Some(folder) = path.parent() {
+        fs::create_dir_all(folder)?;
+    }
+
+    let drafts: HashMap<&String, &String> = drafts.iter().filter(|(_, v)| !v.is_empty()).collect();
+    fs::write(path, serde_json::to_string(&drafts)?)?;
+    Ok(())
+}
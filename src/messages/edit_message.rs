@@ -0,0 +1,59 @@
+use std::error::Error;
+
+use serde_json::{json, Value};
+use tracing::{instrument, warn};
+
+use crate::{
+    config::load_config::config,
+    messages::parse_message::parse_message,
+    state::{AppState, Message, UiUpdate, UpdateSender, HTTP_CLIENT},
+};
+
+/// Edits the content of one of the clients own messages.
+///
+/// The store is updated from the response, MESSAGE_UPDATE will arrive with the same content.
+pub async fn edit_message(
+    app_state: AppState,
+    update_sender: UpdateSender,
+    channel_id: String,
+    message_id: String,
+    content: String,
+) {
+    let authorization_token = app_state.read().await.token.clone();
+    match patch_message(&authorization_token, &channel_id, &message_id, &content).await {
+        Ok(Some(message)) => {
+            let index = app_state.write().await.insert_message(message);
+            let _ = update_sender.send(UiUpdate::MessagesChanged(channel_id, index..index + 1));
+        }
+        Ok(None) => {}
+        Err(e) => warn!("Failed to edit message: {}", e),
+    }
+}
+
+/// PATCH /channels/{id}/messages/{id}
+#[instrument(level = "debug", name = "request", skip_all, fields(endpoint = "edit", %channel_id))]
+async fn patch_message(
+    authorization_token: &str,
+    channel_id: &str,
+    message_id: &str,
+    content: &str,
+) -> Result<Option<Message>, Box<dyn Error + Send + Sync>> {
+    let url = format!(
+        "{}/channels/{}/messages/{}",
+        config().endpoints.api,
+        channel_id,
+        message_id
+    );
+
+    let json = HTTP_CLIENT
+        .patch(&url)
+        .header("Authorization", authorization_token)
+        .json(&json!({ "content": content }))
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<Value>()
+        .await?;
+
+    Ok(parse_message(&json))
+}
@@ -0,0 +1,126 @@
+use std::error::Error;
+
+use serde_json::Value;
+use tracing::{instrument, warn};
+
+use crate::{
+    config::load_config::config,
+    messages::parse_message::parse_message,
+    state::{AppState, Message, UiUpdate, UpdateSender, HTTP_CLIENT},
+};
+
+/// Number of messages requested per page, discords maximum.
+const PAGE_SIZE: usize = 50;
+
+/// Loads the latest messages of a channel into the store.
+pub async fn load_channel_messages(
+    app_state: AppState,
+    update_sender: UpdateSender,
+    channel_id: String,
+) {
+    let authorization_token = app_state.read().await.token.clone();
+    match fetch_messages(&authorization_token, &channel_id, None).await {
+        Ok(messages) => {
+            let mut app_data = app_state.write().await;
+            if messages.len() < PAGE_SIZE {
+                app_data.history_complete.insert(channel_id.clone());
+            }
+            let changed = app_data.set_channel_messages(&channel_id, messages);
+            drop(app_data);
+
+            let _ = update_sender.send(UiUpdate::MessagesChanged(channel_id, changed));
+        }
+        Err(e) => warn!("Failed to fetch messages: {}", e),
+    }
+}
+
+/// Loads the page of messages before the oldest loaded message of a channel.
+///
+/// Does nothing if the whole history is loaded, or a page is already being loaded.
+pub async fn load_older_messages(
+    app_state: AppState,
+    update_sender: UpdateSender,
+    channel_id: String,
+) {
+    let before = {
+        let mut app_data = app_state.write().await;
+
+        if app_data.history_complete.contains(&channel_id)
+            || app_data.history_loading.contains(&channel_id)
+        {
+            return;
+        }
+
+        let Some(before) = app_data
+            .messages
+            .get(&channel_id)
+            .and_then(|messages| messages.iter().find(|v| !v.id.is_empty()))
+            .map(|v| v.id.clone())
+        else {
+            return;
+        };
+
+        app_data.history_loading.insert(channel_id.clone());
+        before
+    };
+
+    let authorization_token = app_state.read().await.token.clone();
+    let result = fetch_messages(&authorization_token, &channel_id, Some(&before))
+        .await
+        .map_err(|e| e.to_string());
+
+    let mut app_data = app_state.write().await;
+    app_data.history_loading.remove(&channel_id);
+
+    match result {
+        Ok(messages) => {
+            if messages.len() < PAGE_SIZE {
+                app_data.history_complete.insert(channel_id.clone());
+            }
+            let changed = app_data.prepend_channel_messages(&channel_id, messages);
+            drop(app_data);
+
+            let _ = update_sender.send(UiUpdate::MessagesPrepended(channel_id, changed.len()));
+        }
+        Err(e) => warn!("Failed to fetch older messages: {}", e),
+    }
+}
+
+/// GET /channels/{id}/messages
+///
+/// Returns the messages oldest first.
+#[instrument(level = "debug", name = "request", skip_all, fields(endpoint = "messages", %channel_id))]
+pub async fn fetch_messages(
+    authorization_token: &str,
+    channel_id: &str,
+    before: Option<&str>,
+) -> Result<Vec<Message>, Box<dyn Error + Send + Sync>> {
+    let mut url = format!(
+        "{}/channels/{}/messages?limit={}",
+        config().endpoints.api,
+        channel_id,
+        PAGE_SIZE
+    );
+    if let Some(before) = before {
+        url.push_str(&format!("&before={}", before));
+    }
+
+    let json = HTTP_CLIENT
+        .get(&url)
+        .header("Authorization", authorization_token)
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<Value>()
+        .await?;
+
+    let mut messages: Vec<Message> = json
+        .as_array()
+        .map(|v| v.iter().filter_map(parse_message).collect())
+        .unwrap_or_default();
+
+    // Discord returns the newest message first.
+    messages.reverse();
+
+    Ok(messages)
+}
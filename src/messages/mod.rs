@@ -0,0 +1,13 @@
+pub mod ack_message;
+pub mod composer_files;
+pub mod drafts;
+pub mod edit_message;
+pub mod fetch_messages;
+pub mod nonce;
+pub mod outgoing_message;
+pub mod parse_attachment;
+pub mod parse_embed;
+pub mod parse_message;
+pub mod reactions;
+pub mod send_message;
+pub mod upload_files;
@@ -0,0 +1,21 @@
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::utils::snowflake::DISCORD_EPOCH_MS;
+
+static INCREMENT: AtomicU64 = AtomicU64::new(0);
+
+/// Generates a nonce in the same format as a snowflake,
+/// so it is unique and sorts by the time it was created.
+pub fn generate_nonce() -> String {
+    let now_ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|v| v.as_millis() as u64)
+        .unwrap_or_default();
+
+    let increment = INCREMENT.fetch_add(1, Ordering::Relaxed) & 0xFFF;
+
+    (((now_ms.saturating_sub(DISCORD_EPOCH_MS)) << 22) | increment).to_string()
+}
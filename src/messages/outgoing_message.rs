@@ -0,0 +1,108 @@
+use std::path::PathBuf;
+
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::state::Attachment;
+
+/// Message flag that suppresses push and desktop notifications (@silent).
+pub const SUPPRESS_NOTIFICATIONS: u64 = 1 << 12;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MessageReference {
+    pub message_id: String,
+    pub channel_id: String,
+}
+
+/// Controls who gets pinged by a message.
+///
+/// parse can contain "users", "roles" and "everyone".
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AllowedMentions {
+    pub parse: Vec<String>,
+    pub users: Vec<String>,
+    pub roles: Vec<String>,
+    /// Whether the author of the replied to message is pinged.
+    pub replied_user: bool,
+}
+
+/// A file attached in the composer, uploaded with the message.
+#[derive(Debug, Clone, Default)]
+pub struct UploadFile {
+    /// Generated locally, identifies the file until it is uploaded.
+    pub id: String,
+    pub path: PathBuf,
+    pub filename: String,
+    /// In bytes, when the file was attached.
+    pub size: u64,
+    /// Alt text.
+    pub description: String,
+    pub spoiler: bool,
+}
+
+impl UploadFile {
+    /// Spoilers are files whose name starts with SPOILER_.
+    pub fn upload_filename(&self) -> String {
+        if self.spoiler {
+            format!("SPOILER_{}", self.filename)
+        } else {
+            self.filename.clone()
+        }
+    }
+
+    /// Shown in the pending message while the file is uploading.
+    pub fn to_attachment(&self) -> Attachment {
+        Attachment {
+            id: self.id.clone(),
+            filename: self.upload_filename(),
+            description: self.description.clone(),
+            size: self.size,
+            ..Default::default()
+        }
+    }
+}
+
+/// A message the client wants to send.
+#[derive(Debug, Clone, Default)]
+pub struct OutgoingMessage {
+    pub content: String,
+    pub reply_to: Option<MessageReference>,
+    /// None uses discords default (everything in content is pinged).
+    pub allowed_mentions: Option<AllowedMentions>,
+    pub silent: bool,
+    pub files: Vec<UploadFile>,
+}
+
+impl OutgoingMessage {
+    pub fn flags(&self) -> u64 {
+        if self.silent {
+            SUPPRESS_NOTIFICATIONS
+        } else {
+            0
+        }
+    }
+
+    /// Body for POST /channels/{id}/messages.
+    pub fn to_json(&self, nonce: &str) -> Value {
+        let mut body = json!({
+            "content": self.content,
+            "nonce": nonce,
+            "enforce_nonce": true,
+            "flags": self.flags(),
+        });
+
+        if let Some(reply_to) = &self.reply_to {
+            body["message_reference"] = json!({
+                "message_id": reply_to.message_id,
+                "channel_id": reply_to.channel_id,
+                "fail_if_not_exists": false,
+            });
+        }
+
+        if let Some(allowed_mentions) = &self.allowed_mentions {
+            body["allowed_mentions"] = json!(allowed_mentions);
+        }
+
+        body
+    }
+}
@@ -0,0 +1,29 @@
+use serde_json::Value;
+
+use crate::state::Attachment;
+
+/// Parses an attachment object from the "attachments" of a message.
+pub fn parse_attachment(json: &Value) -> Attachment {
+    let string = |key: &str| {
+        json.get(key)
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string()
+    };
+
+    let number = |key: &str| json.get(key).and_then(|v| v.as_u64()).unwrap_or_default();
+
+    Attachment {
+        id: string("id"),
+        filename: string("filename"),
+        description: string("description"),
+        content_type: string("content_type"),
+        size: number("size"),
+        url: string("url"),
+        proxy_url: string("proxy_url"),
+        width: number("width") as u32,
+        height: number("height") as u32,
+        placeholder: string("placeholder"),
+        placeholder_version: number("placeholder_version"),
+    }
+}
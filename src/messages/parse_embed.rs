@@ -0,0 +1,84 @@
+use serde_json::Value;
+
+use crate::state::{Embed, EmbedField, EmbedMedia};
+
+/// Parses an embed object from the "embeds" of a message.
+pub fn parse_embed(json: &Value) -> Embed {
+    let string = |pointer: &str| {
+        json.pointer(pointer)
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string()
+    };
+
+    let fields = json
+        .get("fields")
+        .and_then(|v| v.as_array())
+        .map(|fields| fields.iter().map(parse_field).collect())
+        .unwrap_or_default();
+
+    Embed {
+        kind: string("/type"),
+        title: string("/title"),
+        url: string("/url"),
+        description: string("/description"),
+        color: json.get("color").and_then(|v| v.as_u64()).map(|v| v as u32),
+        timestamp: string("/timestamp"),
+        provider_name: string("/provider/name"),
+        author_name: string("/author/name"),
+        author_url: string("/author/url"),
+        footer_text: string("/footer/text"),
+        fields,
+        image: json.get("image").map(parse_media),
+        thumbnail: json.get("thumbnail").map(parse_media),
+        video: json.get("video").map(parse_media),
+    }
+}
+
+fn parse_field(json: &Value) -> EmbedField {
+    let name = json
+        .get("name")
+        .and_then(|v| v.as_str())
+        .unwrap_or_default()
+        .to_string();
+
+    let value = json
+        .get("value")
+        .and_then(|v| v.as_str())
+        .unwrap_or_default()
+        .to_string();
+
+    let inline = json
+        .get("inline")
+        .and_then(|v| v.as_bool())
+        .unwrap_or_default();
+
+    EmbedField {
+        name,
+        value,
+        inline,
+    }
+}
+
+fn parse_media(json: &Value) -> EmbedMedia {
+    let string = |key: &str| {
+        json.get(key)
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string()
+    };
+
+    let number = |key: &str| json.get(key).and_then(|v| v.as_u64()).unwrap_or_default() as u32;
+
+    EmbedMedia {
+        url: string("url"),
+        proxy_url: string("proxy_url"),
+        width: number("width"),
+        height: number("height"),
+        placeholder: string("placeholder"),
+        placeholder_version: json
+            .get("placeholder_version")
+            .and_then(|v| v.as_u64())
+            .unwrap_or_default(),
+    }
+}
@@ -0,0 +1,196 @@
+use serde_json::Value;
+
+use super::{parse_attachment::parse_attachment, parse_embed::parse_embed};
+use crate::state::{CustomEmoji, Message, MessageDelivery, Reaction, Sticker, User};
+
+/// Parses a message object, like the "d" of a MESSAGE_CREATE event,
+/// or the response from POST /channels/{id}/messages.
+pub fn parse_message(json: &Value) -> Option<Message> {
+    let id = json.get("id").and_then(|v| v.as_str())?.to_string();
+
+    let channel_id = json
+        .get("channel_id")
+        .and_then(|v| v.as_str())
+        .unwrap_or_default()
+        .to_string();
+
+    let author = json.get("author").map(parse_user).unwrap_or_default();
+
+    let content = json
+        .get("content")
+        .and_then(|v| v.as_str())
+        .unwrap_or_default()
+        .to_string();
+
+    let timestamp = json
+        .get("timestamp")
+        .and_then(|v| v.as_str())
+        .unwrap_or_default()
+        .to_string();
+
+    let edited_timestamp = json
+        .get("edited_timestamp")
+        .and_then(|v| v.as_str())
+        .unwrap_or_default()
+        .to_string();
+
+    // Nonce can be either a string or an integer.
+    let nonce = match json.get("nonce") {
+        Some(Value::String(nonce)) => nonce.clone(),
+        Some(Value::Number(nonce)) => nonce.to_string(),
+        _ => String::new(),
+    };
+
+    let referenced_message_id = json
+        .pointer("/message_reference/message_id")
+        .and_then(|v| v.as_str())
+        .unwrap_or_default()
+        .to_string();
+
+    let flags = json
+        .get("flags")
+        .and_then(|v| v.as_u64())
+        .unwrap_or_default();
+
+    let mentions = json
+        .get("mentions")
+        .and_then(|v| v.as_array())
+        .map(|mentions| mentions.iter().map(parse_user).collect())
+        .unwrap_or_default();
+
+    let reactions = json
+        .get("reactions")
+        .and_then(|v| v.as_array())
+        .map(|reactions| reactions.iter().map(parse_reaction).collect())
+        .unwrap_or_default();
+
+    let embeds = json
+        .get("embeds")
+        .and_then(|v| v.as_array())
+        .map(|embeds| embeds.iter().map(parse_embed).collect())
+        .unwrap_or_default();
+
+    let attachments = json
+        .get("attachments")
+        .and_then(|v| v.as_array())
+        .map(|attachments| attachments.iter().map(parse_attachment).collect())
+        .unwrap_or_default();
+
+    let stickers = json
+        .get("sticker_items")
+        .and_then(|v| v.as_array())
+        .map(|stickers| stickers.iter().map(parse_sticker).collect())
+        .unwrap_or_default();
+
+    Some(Message {
+        id,
+        channel_id,
+        author,
+        content,
+        timestamp,
+        edited_timestamp,
+        nonce,
+        referenced_message_id,
+        flags,
+        mentions,
+        delivery: MessageDelivery::Sent,
+        reactions,
+        embeds,
+        attachments,
+        stickers,
+    })
+}
+
+/// Parses a sticker item, the partial sticker sent with messages.
+fn parse_sticker(json: &Value) -> Sticker {
+    let id = json
+        .get("id")
+        .and_then(|v| v.as_str())
+        .unwrap_or_default()
+        .to_string();
+
+    let name = json
+        .get("name")
+        .and_then(|v| v.as_str())
+        .unwrap_or_default()
+        .to_string();
+
+    let format_type = json
+        .get("format_type")
+        .and_then(|v| v.as_u64())
+        .unwrap_or_default();
+
+    Sticker {
+        id,
+        name,
+        format_type,
+    }
+}
+
+fn parse_reaction(json: &Value) -> Reaction {
+    let emoji = json.get("emoji").map(parse_emoji).unwrap_or_default();
+
+    let count = json
+        .get("count")
+        .and_then(|v| v.as_u64())
+        .unwrap_or_default();
+
+    let me = json.get("me").and_then(|v| v.as_bool()).unwrap_or_default();
+
+    Reaction { emoji, count, me }
+}
+
+/// Parses a partial emoji object, the id is null for unicode emojis.
+pub fn parse_emoji(json: &Value) -> CustomEmoji {
+    let id = json
+        .get("id")
+        .and_then(|v| v.as_str())
+        .unwrap_or_default()
+        .to_string();
+
+    let name = json
+        .get("name")
+        .and_then(|v| v.as_str())
+        .unwrap_or_default()
+        .to_string();
+
+    let animated = json
+        .get("animated")
+        .and_then(|v| v.as_bool())
+        .unwrap_or_default();
+
+    CustomEmoji { id, name, animated }
+}
+
+pub fn parse_user(json: &Value) -> User {
+    let id = json
+        .get("id")
+        .and_then(|v| v.as_str())
+        .unwrap_or_default()
+        .to_string();
+
+    let username = json
+        .get("username")
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+        .to_string();
+
+    let global_name = json
+        .get("global_name")
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+        .to_string();
+
+    let avatar_hash = json
+        .get("avatar")
+        .and_then(|v| v.as_str())
+        .unwrap_or_default()
+        .to_string();
+
+    User {
+        id,
+        username,
+        global_name,
+        avatar_hash,
+    }
+}
@@ -0,0 +1,247 @@
+use std::error::Error;
+
+use reqwest::Url;
+use serde_json::Value;
+use tracing::{instrument, warn};
+
+use crate::{
+    config::load_config::config,
+    emoji::shortcodes::parse_reaction_emoji,
+    messages::parse_message::parse_user,
+    state::{AppState, CustomEmoji, ReactionUsers, UiUpdate, UpdateSender, User, HTTP_CLIENT},
+};
+
+/// Number of users shown in the reaction popover, discords maximum per request.
+const USERS_LIMIT: usize = 100;
+
+/// Adds the current users reaction with the emoji of a pill, or removes it if it exists.
+pub async fn toggle_reaction(
+    app_state: AppState,
+    update_sender: UpdateSender,
+    channel_id: String,
+    message_id: String,
+    emoji_key: String,
+) {
+    let reaction = app_state
+        .read()
+        .await
+        .messages
+        .get(&channel_id)
+        .and_then(|messages| messages.iter().find(|v| v.id == message_id))
+        .and_then(|message| {
+            message
+                .reactions
+                .iter()
+                .find(|v| v.emoji.reaction_key() == emoji_key)
+        })
+        .cloned();
+    let Some(reaction) = reaction else {
+        return;
+    };
+
+    react(
+        app_state,
+        update_sender,
+        channel_id,
+        message_id,
+        reaction.emoji,
+        !reaction.me,
+    )
+    .await;
+}
+
+/// Adds the current users reaction with a unicode emoji typed as the emoji or its :shortcode:.
+pub async fn add_reaction(
+    app_state: AppState,
+    update_sender: UpdateSender,
+    channel_id: String,
+    message_id: String,
+    text: String,
+) {
+    let Some(emoji) = parse_reaction_emoji(&text) else {
+        return;
+    };
+    let emoji = CustomEmoji {
+        name: emoji.to_string(),
+        ..Default::default()
+    };
+
+    react(
+        app_state,
+        update_sender,
+        channel_id,
+        message_id,
+        emoji,
+        true,
+    )
+    .await;
+}
+
+/// The store is updated before the request is sent and reverted if it fails.
+async fn react(
+    app_state: AppState,
+    update_sender: UpdateSender,
+    channel_id: String,
+    message_id: String,
+    emoji: CustomEmoji,
+    added: bool,
+) {
+    // None if the message is not loaded, or the reaction already is what was asked for.
+    let index =
+        app_state
+            .write()
+            .await
+            .update_reaction(&channel_id, &message_id, &emoji, true, added);
+    let Some(index) = index else {
+        return;
+    };
+    let _ = update_sender.send(UiUpdate::MessagesChanged(
+        channel_id.clone(),
+        index..index + 1,
+    ));
+
+    let authorization_token = app_state.read().await.token.clone();
+    if let Err(e) = send_reaction(
+        &authorization_token,
+        &channel_id,
+        &message_id,
+        &emoji.reaction_key(),
+        added,
+    )
+    .await
+    {
+        warn!("Failed to update reaction: {}", e);
+
+        let index =
+            app_state
+                .write()
+                .await
+                .update_reaction(&channel_id, &message_id, &emoji, true, !added);
+        if let Some(index) = index {
+            let _ = update_sender.send(UiUpdate::MessagesChanged(channel_id, index..index + 1));
+        }
+    }
+}
+
+/// Loads the users that reacted with the emoji, for the reaction popover.
+pub async fn load_reaction_users(
+    app_state: AppState,
+    update_sender: UpdateSender,
+    channel_id: String,
+    message_id: String,
+    emoji_key: String,
+) {
+    app_state.write().await.reaction_users = Some(ReactionUsers {
+        message_id: message_id.clone(),
+        emoji_key: emoji_key.clone(),
+        users: None,
+    });
+    let _ = update_sender.send(UiUpdate::ReactionUsersChanged);
+
+    let authorization_token = app_state.read().await.token.clone();
+    let users = match fetch_reaction_users(
+        &authorization_token,
+        &channel_id,
+        &message_id,
+        &emoji_key,
+    )
+    .await
+    {
+        Ok(users) => users,
+        Err(e) => {
+            warn!("Failed to fetch reactions: {}", e);
+            Vec::new()
+        }
+    };
+
+    let mut app_data = app_state.write().await;
+    // Another popover may have been opened in the meantime.
+    let Some(reaction_users) = app_data
+        .reaction_users
+        .as_mut()
+        .filter(|v| v.message_id == message_id && v.emoji_key == emoji_key)
+    else {
+        return;
+    };
+    reaction_users.users = Some(users);
+    drop(app_data);
+
+    let _ = update_sender.send(UiUpdate::ReactionUsersChanged);
+}
+
+/// PUT or DELETE /channels/{id}/messages/{id}/reactions/{emoji}/@me
+#[instrument(level = "debug", name = "request", skip_all, fields(endpoint = "reaction", %channel_id))]
+async fn send_reaction(
+    authorization_token: &str,
+    channel_id: &str,
+    message_id: &str,
+    emoji_key: &str,
+    added: bool,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let url = reactions_url(channel_id, message_id, emoji_key, &["@me"])?;
+
+    let request = if added {
+        HTTP_CLIENT.put(url)
+    } else {
+        HTTP_CLIENT.delete(url)
+    };
+
+    request
+        .header("Authorization", authorization_token)
+        .send()
+        .await?
+        .error_for_status()?;
+
+    Ok(())
+}
+
+/// GET /channels/{id}/messages/{id}/reactions/{emoji}
+#[instrument(level = "debug", name = "request", skip_all, fields(endpoint = "reaction_users", %channel_id))]
+async fn fetch_reaction_users(
+    authorization_token: &str,
+    channel_id: &str,
+    message_id: &str,
+    emoji_key: &str,
+) -> Result<Vec<User>, Box<dyn Error + Send + Sync>> {
+    let mut url = reactions_url(channel_id, message_id, emoji_key, &[])?;
+    url.query_pairs_mut()
+        .append_pair("limit", &USERS_LIMIT.to_string());
+
+    let json = HTTP_CLIENT
+        .get(url)
+        .header("Authorization", authorization_token)
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<Value>()
+        .await?;
+
+    Ok(json
+        .as_array()
+        .map(|users| users.iter().map(parse_user).collect())
+        .unwrap_or_default())
+}
+
+/// The url of the reactions with an emoji, the emoji is percent-encoded,
+/// so keycap emojis like "#️⃣" are not cut off as a fragment.
+fn reactions_url(
+    channel_id: &str,
+    message_id: &str,
+    emoji_key: &str,
+    rest: &[&str],
+) -> Result<Url, Box<dyn Error + Send + Sync>> {
+    let mut url = Url::parse(&config().endpoints.api)?;
+    url.path_segments_mut()
+        .map_err(|_| "The api endpoint is not a base url")?
+        .pop_if_empty()
+        .extend([
+            "channels",
+            channel_id,
+            "messages",
+            message_id,
+            "reactions",
+            emoji_key,
+        ])
+        .extend(rest);
+    Ok(url)
+}
@@ -0,0 +1,163 @@
+use std::error::Error;
+use tracing::{instrument, warn};
+
+use crate::{
+    config::load_config::config,
+    messages::{
+        nonce::generate_nonce, outgoing_message::OutgoingMessage, parse_message::parse_message,
+        upload_files::post_message_with_files,
+    },
+    state::{AppState, Message, MessageDelivery, UiUpdate, UpdateSender, HTTP_CLIENT},
+};
+
+/// Sends a message to a channel, with optimistic local echo.
+///
+/// 1. Inserts the message into the store as pending, with a generated nonce.
+/// 2. Sends POST /channels/{id}/messages with the same nonce.
+/// 3. The pending message is replaced by the confirmed message,
+///    either from the response or from the MESSAGE_CREATE event, whichever comes first.
+/// 4. On error the message is marked as failed, and can be sent again with retry_message.
+///
+/// Messages with files are uploaded with post_message_with_files, the pending message
+/// shows the files with their upload progress.
+///
+/// Returns the nonce of the message.
+pub async fn send_message(
+    app_state: AppState,
+    update_sender: UpdateSender,
+    channel_id: String,
+    outgoing: OutgoingMessage,
+) -> String {
+    let nonce = generate_nonce();
+
+    let index = {
+        let mut app_data = app_state.write().await;
+
+        let message = Message {
+            id: String::new(),
+            channel_id: channel_id.clone(),
+            author: app_data.current_user.clone().unwrap_or_default(),
+            content: outgoing.content.clone(),
+            timestamp: String::new(),
+            edited_timestamp: String::new(),
+            nonce: nonce.clone(),
+            referenced_message_id: outgoing
+                .reply_to
+                .as_ref()
+                .map(|v| v.message_id.clone())
+                .unwrap_or_default(),
+            flags: outgoing.flags(),
+            mentions: Vec::new(),
+            delivery: MessageDelivery::Pending,
+            reactions: Vec::new(),
+            embeds: Vec::new(),
+            attachments: outgoing.files.iter().map(|v| v.to_attachment()).collect(),
+            stickers: Vec::new(),
+        };
+
+        let messages = app_data.messages.entry(channel_id.clone()).or_default();
+        messages.push(message);
+        let index = messages.len() - 1;
+
+        app_data
+            .pending_messages
+            .insert(nonce.clone(), outgoing.clone());
+
+        index
+    };
+    let _ = update_sender.send(UiUpdate::MessagesChanged(
+        channel_id.clone(),
+        index..index + 1,
+    ));
+
+    deliver_message(app_state, update_sender, &channel_id, &outgoing, &nonce).await;
+
+    nonce
+}
+
+/// Sends a failed message again, with the same nonce.
+pub async fn retry_message(
+    app_state: AppState,
+    update_sender: UpdateSender,
+    channel_id: String,
+    nonce: String,
+) {
+    let (outgoing, index) = {
+        let mut app_data = app_state.write().await;
+
+        let Some(outgoing) = app_data.pending_messages.get(&nonce).cloned() else {
+            return;
+        };
+        let index = app_data.set_message_delivery(&channel_id, &nonce, MessageDelivery::Pending);
+
+        (outgoing, index)
+    };
+    if let Some(index) = index {
+        let _ = update_sender.send(UiUpdate::MessagesChanged(
+            channel_id.clone(),
+            index..index + 1,
+        ));
+    }
+
+    deliver_message(app_state, update_sender, &channel_id, &outgoing, &nonce).await;
+}
+
+async fn deliver_message(
+    app_state: AppState,
+    update_sender: UpdateSender,
+    channel_id: &str,
+    outgoing: &OutgoingMessage,
+    nonce: &str,
+) {
+    let authorization_token = app_state.read().await.token.clone();
+    let result = if outgoing.files.is_empty() {
+        post_message(&authorization_token, channel_id, outgoing, nonce).await
+    } else {
+        post_message_with_files(&app_state, &update_sender, channel_id, outgoing, nonce).await
+    };
+
+    let index = match result {
+        Ok(Some(message)) => Some(app_state.write().await.insert_message(message)),
+        Ok(None) => None,
+        Err(e) => {
+            warn!("Failed to send message: {}", e);
+            app_state
+                .write()
+                .await
+                .set_message_delivery(channel_id, nonce, MessageDelivery::Failed)
+        }
+    };
+
+    if let Some(index) = index {
+        let _ = update_sender.send(UiUpdate::MessagesChanged(
+            channel_id.to_string(),
+            index..index + 1,
+        ));
+    }
+}
+
+#[instrument(level = "debug", name = "request", skip_all, fields(endpoint = "send", %channel_id))]
+async fn post_message(
+    authorization_token: &str,
+    channel_id: &str,
+    outgoing: &OutgoingMessage,
+    nonce: &str,
+) -> Result<Option<Message>, Box<dyn Error + Send + Sync>> {
+    let url = format!(
+        "{}/channels/{}/messages",
+        config().endpoints.api,
+        channel_id
+    );
+
+    let response = HTTP_CLIENT
+        .post(&url)
+        .header("Authorization", authorization_token)
+        .json(&outgoing.to_json(nonce))
+        .send()
+        .await?
+        .error_for_status()?;
+
+    let json = response.json::<serde_json::Value>().await?;
+
+    Ok(parse_message(&json))
+}
@@ -0,0 +1,355 @@
+use std::{error::Error, io, time::Instant};
+
+use futures_util::{
+    stream::{self, BoxStream},
+    StreamExt,
+};
+use reqwest::{
+    header::{CONTENT_LENGTH, CONTENT_TYPE},
+    Body,
+};
+use serde_json::{json, Value};
+use tokio::{fs::File, io::AsyncReadExt};
+use tracing::instrument;
+
+use crate::{
+    config::load_config::config,
+    messages::{
+        outgoing_message::{OutgoingMessage, UploadFile},
+        parse_message::parse_message,
+    },
+    state::{AppState, Message, UiUpdate, UpdateSender, HTTP_CLIENT, PROGRESS_INTERVAL},
+};
+
+/// Messages with more bytes of files than this are uploaded to the cloud storage first,
+/// instead of in one multipart request.
+const CLOUD_UPLOAD_SIZE: u64 = 8 * 1024 * 1024;
+
+/// Files are read and sent in chunks of this size.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+type BodyStream = BoxStream<'static, io::Result<Vec<u8>>>;
+
+/// Sends a message with files, the progress of every file is kept in AppData.upload_progress
+/// while it is uploading.
+///
+/// Small uploads are sent as multipart/form-data with POST /channels/{id}/messages.
+/// Large uploads request upload urls with POST /channels/{id}/attachments, put the files there,
+/// and send the message referring to the uploaded files.
+pub async fn post_message_with_files(
+    app_state: &AppState,
+    update_sender: &UpdateSender,
+    channel_id: &str,
+    outgoing: &OutgoingMessage,
+    nonce: &str,
+) -> Result<Option<Message>, Box<dyn Error + Send + Sync>> {
+    let progress = Progress {
+        app_state: app_state.clone(),
+        update_sender: update_sender.clone(),
+        channel_id: channel_id.to_string(),
+        nonce: nonce.to_string(),
+    };
+    for file in &outgoing.files {
+        progress.set(&file.id, 0).await;
+    }
+
+    let authorization_token = app_state.read().await.token.clone();
+    let total: u64 = outgoing.files.iter().map(|v| v.size).sum();
+    let result = if total > CLOUD_UPLOAD_SIZE {
+        post_cloud_upload(&authorization_token, channel_id, outgoing, nonce, &progress).await
+    } else {
+        post_multipart(&authorization_token, channel_id, outgoing, nonce, &progress).await
+    };
+
+    let mut app_data = app_state.write().await;
+    for file in &outgoing.files {
+        app_data.upload_progress.remove(&file.id);
+    }
+
+    result
+}
+
+#[instrument(level = "debug", name = "request", skip_all, fields(endpoint = "upload", %channel_id))]
+async fn post_multipart(
+    authorization_token: &str,
+    channel_id: &str,
+    outgoing: &OutgoingMessage,
+    nonce: &str,
+    progress: &Progress,
+) -> Result<Option<Message>, Box<dyn Error + Send + Sync>> {
+    let url = format!(
+        "{}/channels/{}/messages",
+        config().endpoints.api,
+        channel_id
+    );
+    let boundary = format!("----DiscordClientBoundary{}", nonce);
+
+    let mut payload = outgoing.to_json(nonce);
+    payload["attachments"] = outgoing
+        .files
+        .iter()
+        .enumerate()
+        .map(|(index, file)| {
+            json!({
+                "id": index.to_string(),
+                "filename": file.upload_filename(),
+                "description": file.description,
+            })
+        })
+        .collect();
+
+    let mut texts = vec![format!(
+        "--{}\r\nContent-Disposition: form-data; name=\"payload_json\"\r\n\
+         Content-Type: application/json\r\n\r\n{}\r\n",
+        boundary, payload
+    )];
+    let mut files = Vec::new();
+
+    for (index, upload) in outgoing.files.iter().enumerate() {
+        let file = File::open(&upload.path).await?;
+        let size = file.metadata().await?.len();
+        files.push((file, size, upload));
+
+        texts.push(format!(
+            "\r\n--{}\r\nContent-Disposition: form-data; name=\"files[{}]\"; filename=\"{}\"\r\n\
+             Content-Type: application/octet-stream\r\n\r\n",
+            boundary,
+            index,
+            escape_filename(&upload.upload_filename())
+        ));
+    }
+    texts.push(format!("\r\n--{}--\r\n", boundary));
+
+    // The files go between the texts, the body length is known up front
+    // so it is not sent chunked.
+    let length = texts.iter().map(|v| v.len() as u64).sum::<u64>()
+        + files.iter().map(|(_, size, _)| size).sum::<u64>();
+
+    let mut texts = texts.into_iter().map(text_part);
+    let mut parts: Vec<BodyStream> = texts.next().into_iter().collect();
+    for (file, size, upload) in files {
+        parts.extend(texts.next());
+        parts.push(file_stream(file, size, upload, progress.clone()));
+    }
+    parts.extend(texts);
+
+    let response = HTTP_CLIENT
+        .post(&url)
+        .header("Authorization", authorization_token)
+        .header(
+            CONTENT_TYPE,
+            format!("multipart/form-data; boundary={}", boundary),
+        )
+        .header(CONTENT_LENGTH, length)
+        .body(Body::wrap_stream(stream::iter(parts).flatten()))
+        .send()
+        .await?
+        .error_for_status()?;
+
+    let json = response.json::<Value>().await?;
+
+    Ok(parse_message(&json))
+}
+
+#[instrument(level = "debug", name = "request", skip_all, fields(endpoint = "cloud_upload", %channel_id))]
+async fn post_cloud_upload(
+    authorization_token: &str,
+    channel_id: &str,
+    outgoing: &OutgoingMessage,
+    nonce: &str,
+    progress: &Progress,
+) -> Result<Option<Message>, Box<dyn Error + Send + Sync>> {
+    // POST /channels/{id}/attachments
+    let url = format!(
+        "{}/channels/{}/attachments",
+        config().endpoints.api,
+        channel_id
+    );
+    let files: Vec<Value> = outgoing
+        .files
+        .iter()
+        .enumerate()
+        .map(|(index, file)| {
+            json!({
+                "id": index.to_string(),
+                "filename": file.upload_filename(),
+                "file_size": file.size,
+            })
+        })
+        .collect();
+
+    let json = HTTP_CLIENT
+        .post(&url)
+        .header("Authorization", authorization_token)
+        .json(&json!({ "files": files }))
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<Value>()
+        .await?;
+
+    let targets = json
+        .get("attachments")
+        .and_then(|v| v.as_array())
+        .cloned()
+        .unwrap_or_default();
+    if targets.len() != outgoing.files.len() {
+        return Err("Discord did not return an upload url for every file".into());
+    }
+
+    let mut attachments = Vec::new();
+
+    for (index, (upload, target)) in outgoing.files.iter().zip(&targets).enumerate() {
+        let upload_url = target
+            .get("upload_url")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default();
+        let uploaded_filename = target
+            .get("upload_filename")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default();
+
+        let file = File::open(&upload.path).await?;
+        let size = file.metadata().await?.len();
+
+        // The upload url is signed, it does not take the token.
+        HTTP_CLIENT
+            .put(upload_url)
+            .header(CONTENT_TYPE, "application/octet-stream")
+            .header(CONTENT_LENGTH, size)
+            .body(Body::wrap_stream(file_stream(
+                file,
+                size,
+                upload,
+                progress.clone(),
+            )))
+            .send()
+            .await?
+            .error_for_status()?;
+
+        attachments.push(json!({
+            "id": index.to_string(),
+            "filename": upload.upload_filename(),
+            "uploaded_filename": uploaded_filename,
+            "description": upload.description,
+        }));
+    }
+
+    // POST /channels/{id}/messages
+    let url = format!(
+        "{}/channels/{}/messages",
+        config().endpoints.api,
+        channel_id
+    );
+    let mut payload = outgoing.to_json(nonce);
+    payload["attachments"] = Value::Array(attachments);
+
+    let json = HTTP_CLIENT
+        .post(&url)
+        .header("Authorization", authorization_token)
+        .json(&payload)
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<Value>()
+        .await?;
+
+    Ok(parse_message(&json))
+}
+
+fn text_part(text: String) -> BodyStream {
+    stream::once(async move { Ok(text.into_bytes()) }).boxed()
+}
+
+/// Reads a file in chunks for a request body, and reports how much of it was sent.
+///
+/// Exactly size bytes are sent, as the Content-Length was set from it.
+/// A file that got smaller since then fails the upload.
+fn file_stream(file: File, size: u64, upload: &UploadFile, progress: Progress) -> BodyStream {
+    let file_id = upload.id.clone();
+    let filename = upload.filename.clone();
+
+    stream::unfold(
+        Some((file.take(size), 0u64, Instant::now())),
+        move |state| {
+            let file_id = file_id.clone();
+            let filename = filename.clone();
+            let progress = progress.clone();
+            async move {
+                let (mut file, sent, last_progress) = state?;
+
+                let mut chunk = vec![0; CHUNK_SIZE];
+                let read = match file.read(&mut chunk).await {
+                    Ok(read) => read,
+                    Err(e) => return Some((Err(e), None)),
+                };
+                if read == 0 {
+                    if sent < size {
+                        let error = io::Error::new(
+                            io::ErrorKind::UnexpectedEof,
+                            format!("{} got smaller while it was uploaded", filename),
+                        );
+                        return Some((Err(error), None));
+                    }
+                    progress.set(&file_id, sent).await;
+                    return None;
+                }
+
+                chunk.truncate(read);
+                let sent = sent + read as u64;
+
+                let last_progress = if last_progress.elapsed() >= PROGRESS_INTERVAL {
+                    progress.set(&file_id, sent).await;
+                    Instant::now()
+                } else {
+                    last_progress
+                };
+
+                Some((Ok(chunk), Some((file, sent, last_progress))))
+            }
+        },
+    )
+    .boxed()
+}
+
+/// Quotes and line breaks would end the filename parameter, they are percent encoded like browsers do.
+fn escape_filename(filename: &str) -> String {
+    filename
+        .replace('"', "%22")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Where the progress of the files of one pending message is reported.
+#[derive(Clone)]
+struct Progress {
+    app_state: AppState,
+    update_sender: UpdateSender,
+    channel_id: String,
+    nonce: String,
+}
+
+impl Progress {
+    async fn set(&self, file_id: &str, sent: u64) {
+        let index = {
+            let mut app_data = self.app_state.write().await;
+            app_data.upload_progress.insert(file_id.to_string(), sent);
+
+            app_data
+                .messages
+                .get(&self.channel_id)
+                .and_then(|messages| {
+                    messages
+                        .iter()
+                        .position(|v| v.nonce == self.nonce && v.id.is_empty())
+                })
+        };
+
+        if let Some(index) = index {
+            let _ = self.update_sender.send(UiUpdate::MessagesChanged(
+                self.channel_id.clone(),
+                index..index + 1,
+            ));
+        }
+    }
+}
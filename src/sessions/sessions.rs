@@ -0,0 +1,216 @@
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc, RwLock,
+};
+
+use crate::{
+    config::load_config::config,
+    state::{create_app_state, create_update_channel, AppState, LoginStep, UiUpdate, UpdateSender},
+    websocket::websocket::spawn_connection,
+};
+
+static NEXT_SESSION_ID: AtomicU64 = AtomicU64::new(0);
+
+/// One account: its token, caches and gateway connection.
+#[derive(Clone)]
+pub struct Session {
+    pub id: u64,
+    pub app_state: AppState,
+    /// Updates of this session, they only reach the window while the session is active.
+    pub update_sender: UpdateSender,
+}
+
+struct Entry {
+    session: Session,
+    /// A message asked for attention while the session was in the background.
+    notified: bool,
+}
+
+struct SessionList {
+    entries: Vec<Entry>,
+    /// Index of the session shown in the window.
+    active: usize,
+}
+
+/// The logged in accounts, each with its own AppState.
+///
+/// Every session has its own update channel, the updates of the active session are forwarded
+/// to the window, background sessions only forward what asks for attention.
+#[derive(Clone)]
+pub struct Sessions {
+    list: Arc<RwLock<SessionList>>,
+    /// The channel of the window.
+    ui_sender: UpdateSender,
+}
+
+impl Sessions {
+    /// Starts with one session, which shows the login screen.
+    pub fn new(ui_sender: UpdateSender) -> Self {
+        let sessions = Self {
+            list: Arc::new(RwLock::new(SessionList {
+                entries: Vec::new(),
+                active: 0,
+            })),
+            ui_sender,
+        };
+        sessions.add();
+        sessions
+    }
+
+    /// Adds a session in the background, it is not logged in yet.
+    pub fn add(&self) -> Session {
+        let (update_sender, mut update_receiver) = create_update_channel();
+        let id = NEXT_SESSION_ID.fetch_add(1, Ordering::Relaxed);
+        let session = Session {
+            id,
+            app_state: create_app_state(id),
+            update_sender,
+        };
+
+        if let Ok(mut list) = self.list.write() {
+            list.entries.push(Entry {
+                session: session.clone(),
+                notified: false,
+            });
+        }
+
+        std::thread::spawn({
+            let sessions = self.clone();
+            let id = session.id;
+            move || {
+                while let Some(update) = update_receiver.blocking_recv() {
+                    sessions.forward(id, update);
+                }
+            }
+        });
+
+        session
+    }
+
+    /// Removes a session that is not logged in, like when adding an account was cancelled.
+    pub fn remove(&self, id: u64) {
+        let Ok(mut list) = self.list.write() else {
+            return;
+        };
+        let Some(index) = list.entries.iter().position(|v| v.session.id == id) else {
+            return;
+        };
+        if list.entries.len() == 1 {
+            return;
+        }
+
+        let was_active = list.active == index;
+        list.entries.remove(index);
+        if list.active >= index && list.active > 0 {
+            list.active -= 1;
+        }
+        let active = list.active;
+        drop(list);
+
+        if was_active {
+            self.switch_to(active);
+        } else {
+            let _ = self.ui_sender.send(UiUpdate::SessionsChanged);
+        }
+    }
+
+    pub fn active(&self) -> Session {
+        let list = self.list.read().unwrap_or_else(|e| e.into_inner());
+        list.entries[list.active].session.clone()
+    }
+
+    /// The session with the id, None once it was removed.
+    pub fn get(&self, id: u64) -> Option<Session> {
+        let list = self.list.read().unwrap_or_else(|e| e.into_inner());
+        list.entries
+            .iter()
+            .find(|v| v.session.id == id)
+            .map(|v| v.session.clone())
+    }
+
+    /// All sessions in the order of the account switcher, with whether they were notified.
+    pub fn all(&self) -> Vec<(Session, bool)> {
+        let list = self.list.read().unwrap_or_else(|e| e.into_inner());
+        list.entries
+            .iter()
+            .map(|v| (v.session.clone(), v.notified))
+            .collect()
+    }
+
+    /// Shows the session at index in the window.
+    ///
+    /// Without accounts.keep_background_connected the previous session disconnects,
+    /// a logged in session connects again when it is shown.
+    pub fn switch_to(&self, index: usize) {
+        let (previous, next) = {
+            let Ok(mut list) = self.list.write() else {
+                return;
+            };
+            if index >= list.entries.len() {
+                return;
+            }
+
+            let previous = list.entries[list.active].session.clone();
+            list.active = index;
+            list.entries[index].notified = false;
+            (previous, list.entries[index].session.clone())
+        };
+
+        if previous.id != next.id && !config().accounts.keep_background_connected {
+            if let Some(disconnect) = previous.app_state.blocking_write().disconnect.take() {
+                let _ = disconnect.send(());
+            }
+        }
+
+        let app_data = next.app_state.blocking_read();
+        let connected = app_data.disconnect.as_ref().is_some_and(|v| !v.is_closed());
+        let logged_in = app_data.login.step == LoginStep::Done && !app_data.token.is_empty();
+        drop(app_data);
+        if logged_in && !connected {
+            spawn_connection(next.app_state.clone(), next.update_sender.clone());
+        }
+
+        let _ = self.ui_sender.send(UiUpdate::SessionSwitched);
+    }
+
+    /// Shows the next account, after the last one the first.
+    pub fn cycle(&self) {
+        let (active, count) = {
+            let list = self.list.read().unwrap_or_else(|e| e.into_inner());
+            (list.active, list.entries.len())
+        };
+        if count > 1 {
+            self.switch_to((active + 1) % count);
+        }
+    }
+
+    fn forward(&self, id: u64, update: UiUpdate) {
+        let Ok(mut list) = self.list.write() else {
+            return;
+        };
+        let Some(index) = list.entries.iter().position(|v| v.session.id == id) else {
+            return;
+        };
+
+        if index == list.active {
+            let _ = self.ui_sender.send(update);
+            return;
+        }
+
+        match update {
+            UiUpdate::Notify(channel_id) => {
+                list.entries[index].notified = true;
+                let _ = self.ui_sender.send(UiUpdate::Notify(channel_id));
+                let _ = self.ui_sender.send(UiUpdate::SessionsChanged);
+            }
+            UiUpdate::CurrentUserChanged => {
+                let _ = self.ui_sender.send(UiUpdate::SessionsChanged);
+            }
+            // Avatars are shared, the switcher shows the avatar of every account.
+            UiUpdate::AvatarReady(user_id) => {
+                let _ = self.ui_sender.send(UiUpdate::AvatarReady(user_id));
+            }
+            _ => {}
+        }
+    }
+}
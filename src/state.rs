@@ -1,23 +1,42 @@
 use once_cell::sync::Lazy;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
-use slint::Image;
-use std::{error::Error, path::PathBuf, sync::Arc};
-use tokio::{
-    fs::File,
-    io::AsyncWriteExt,
-    sync::{mpsc, RwLock},
+use slint::{Image, Rgba8Pixel, SharedPixelBuffer};
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, HashSet},
+    error::Error,
+    hash::Hash,
+    ops::Range,
+    path::PathBuf,
+    sync::{atomic::AtomicBool, Arc},
+    time::Duration,
 };
+use tokio::sync::{mpsc, oneshot, RwLock};
 
-static HTTP_CLIENT: Lazy<Client> = Lazy::new(|| {
+use crate::assets::{
+    animation::Animation,
+    asset::{Asset, GUILD_ICON_SIZE, LIST_IMAGE_SIZE},
+    asset_cache::{Priority, ASSET_CACHE},
+    image_cache,
+};
+use crate::auth::token::Token;
+use crate::messages::drafts::load_drafts;
+use crate::messages::outgoing_message::{OutgoingMessage, UploadFile};
+use crate::utils::snowflake::snowflake_timestamp_ms;
+
+#[cfg(test)]
+mod tests;
+
+pub static HTTP_CLIENT: Lazy<Client> = Lazy::new(|| {
     Client::builder()
-        .pool_idle_timeout(std::time::Duration::from_secs(30))
+        .pool_idle_timeout(Duration::from_secs(30))
         .pool_max_idle_per_host(8)
         .build()
         .expect("Failed to create global HTTP client")
 });
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub struct User {
     pub id: String,
     pub username: String,
@@ -34,61 +53,32 @@ impl User {
         }
     }
 
-    fn local_avatar_path(&self) -> PathBuf {
-        if self.id.is_empty() || self.avatar_hash.is_empty() {
-            return PathBuf::new();
-        }
-
-        let extension = if self.avatar_hash.starts_with("a_") {
-            "gif"
-        } else {
-            "png"
-        };
-
-        PathBuf::from(format!(
-            "./assets/avatars/{}_{}.{}",
-            self.id, self.avatar_hash, extension
-        ))
+    /// The avatar shown size logical pixels large.
+    pub fn avatar_asset(&self, size: u32) -> Option<Asset> {
+        Asset::avatar(&self.id, &self.avatar_hash, size)
     }
 
-    pub fn load_avatar_image(&self) -> Image {
-        let path = self.local_avatar_path();
-        if !path.as_os_str().is_empty() && path.exists() {
-            Image::load_from_path(&path).unwrap_or_default()
-        } else {
-            Image::default()
-        }
+    pub fn load_avatar_image(&self, size: u32) -> Image {
+        self.avatar_asset(size)
+            .map(|asset| image_cache::load_image(&asset))
+            .unwrap_or_default()
     }
 
-    pub async fn get_avatar(&self) -> Result<(), Box<dyn Error>> {
-        if self.id.is_empty() || self.avatar_hash.is_empty() {
-            return Ok(());
-        }
+    /// The frames of an animated avatar, empty for still ones.
+    pub fn load_avatar_animation(&self, size: u32) -> Animation {
+        self.avatar_asset(size)
+            .map(|asset| image_cache::load_animation(&asset))
+            .unwrap_or_default()
+    }
 
-        let path = self.local_avatar_path();
-        if path.exists() {
-            return Ok(());
+    pub async fn get_avatar(
+        &self,
+        size: u32,
+        priority: Priority,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        if let Some(asset) = self.avatar_asset(size) {
+            ASSET_CACHE.fetch(&asset, priority).await?;
         }
-
-        let (extension, format_param) = if self.avatar_hash.starts_with("a_") {
-            ("gif", "gif")
-        } else {
-            ("png", "png")
-        };
-
-        let url = format!(
-            "https://cdn.discordapp.com/avatars/{}/{}.{}?size=64",
-            self.id, self.avatar_hash, format_param
-        );
-
-        let bytes = HTTP_CLIENT.get(&url).send().await?.bytes().await?;
-
-        let folder = "./assets/avatars";
-        tokio::fs::create_dir_all(folder).await?;
-
-        let file_path = format!("{}/{}_{}.{}", folder, self.id, self.avatar_hash, extension);
-        let mut file = File::create(&file_path).await?;
-        file.write_all(&bytes).await?;
         Ok(())
     }
 }
@@ -107,6 +97,8 @@ pub struct PrivateChannel {
     pub recipients: Vec<User>,
     /// sort_id is either a snowflake id of the last message sent, or a snowflake id of the channels creation.
     pub sort_id: u64,
+    /// 0 if no message has been sent in the channel.
+    pub last_message_id: u64,
     pub icon_hash: String,
 }
 
@@ -129,87 +121,772 @@ impl PrivateChannel {
         }
     }
 
-    fn local_icon_path(&self) -> PathBuf {
-        if self.icon_hash.is_empty() {
-            return PathBuf::new();
+    pub fn icon_asset(&self) -> Option<Asset> {
+        Asset::channel_icon(&self.id, &self.icon_hash, LIST_IMAGE_SIZE)
+    }
+
+    /// The image shown for the channel in the channel list,
+    /// the icon for groups and the recipients avatar for private channels.
+    pub fn list_image_asset(&self) -> Option<Asset> {
+        match self.channel_type {
+            ChannelType::Group => self.icon_asset(),
+            ChannelType::Private => self.recipients.first()?.avatar_asset(LIST_IMAGE_SIZE),
         }
+    }
 
-        let extension = if self.icon_hash.starts_with("a_") {
-            "gif"
-        } else {
-            "png"
-        };
+    pub fn load_list_image(&self) -> Image {
+        self.list_image_asset()
+            .map(|asset| image_cache::load_image(&asset))
+            .unwrap_or_default()
+    }
 
-        PathBuf::from(format!(
-            "./assets/channel_icons/{}_{}.{}",
-            self.id, self.icon_hash, extension
-        ))
+    pub fn load_list_animation(&self) -> Animation {
+        self.list_image_asset()
+            .map(|asset| image_cache::load_animation(&asset))
+            .unwrap_or_default()
     }
 
-    pub fn load_icon_image(&self) -> Image {
-        let path = self.local_icon_path();
-        if !path.as_os_str().is_empty() && path.exists() {
-            Image::load_from_path(&path).unwrap_or_default()
+    /// The update sent when the list image was downloaded.
+    pub fn list_image_ready(&self) -> Option<UiUpdate> {
+        match self.channel_type {
+            ChannelType::Group => Some(UiUpdate::ChannelIconReady(self.id.clone())),
+            ChannelType::Private => {
+                Some(UiUpdate::AvatarReady(self.recipients.first()?.id.clone()))
+            }
+        }
+    }
+}
+
+/// A custom emoji from a <:name:id> or <a:name:id> token.
+///
+/// Reactions also use it for unicode emojis, those have no id and the emoji as name.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct CustomEmoji {
+    pub id: String,
+    pub name: String,
+    pub animated: bool,
+}
+
+impl CustomEmoji {
+    /// "name:id", or the unicode emoji, as used in the urls of the reaction endpoints.
+    pub fn reaction_key(&self) -> String {
+        if self.id.is_empty() {
+            self.name.clone()
         } else {
-            Image::default()
+            format!("{}:{}", self.name, self.id)
+        }
+    }
+
+    /// The frames of an animated emoji, empty for still ones and until they are downloaded.
+    pub fn load_animation(&self) -> Animation {
+        if !self.animated {
+            return Animation::default();
         }
+        image_cache::load_animation(&Asset::emoji(&self.id, true))
     }
 
-    pub async fn get_icon(&self) -> Result<(), Box<dyn Error>> {
-        if self.icon_hash.is_empty() || self.id.is_empty() {
+    /// The still image, for animated emojis their first frame.
+    pub fn load_image(&self) -> Image {
+        ASSET_CACHE.load_image(&Asset::emoji(&self.id, false))
+    }
+
+    pub async fn get_image(&self, priority: Priority) -> Result<(), Box<dyn Error + Send + Sync>> {
+        if self.id.is_empty() {
             return Ok(());
         }
 
-        let path = self.local_icon_path();
-        if path.exists() {
+        ASSET_CACHE
+            .fetch(&Asset::emoji(&self.id, false), priority)
+            .await?;
+        if self.animated {
+            ASSET_CACHE
+                .fetch(&Asset::emoji(&self.id, true), priority)
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+/// An image, thumbnail or video of an embed.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct EmbedMedia {
+    pub url: String,
+    /// Discords cached copy of url, which is downloaded instead.
+    pub proxy_url: String,
+    pub width: u32,
+    pub height: u32,
+    /// Base64 thumbhash of the image.
+    pub placeholder: String,
+    pub placeholder_version: u64,
+}
+
+impl EmbedMedia {
+    /// Images are downloaded at most this wide, embeds are not shown larger.
+    const MAX_WIDTH: u32 = 400;
+
+    /// The proxy url, scaled down for large images.
+    fn download_url(&self) -> String {
+        if self.proxy_url.is_empty() {
+            return self.url.clone();
+        }
+        if self.width <= Self::MAX_WIDTH || self.height == 0 {
+            return self.proxy_url.clone();
+        }
+
+        let height = self.height * Self::MAX_WIDTH / self.width;
+        let separator = if self.proxy_url.contains('?') {
+            '&'
+        } else {
+            '?'
+        };
+        format!(
+            "{}{}width={}&height={}",
+            self.proxy_url,
+            separator,
+            Self::MAX_WIDTH,
+            height.max(1)
+        )
+    }
+
+    /// The downloaded image, the format is detected from its content.
+    pub fn load_image(&self) -> Image {
+        ASSET_CACHE.load_image(&Asset::preview(&self.download_url()))
+    }
+
+    pub async fn get_image(&self, priority: Priority) -> Result<(), Box<dyn Error + Send + Sync>> {
+        if self.url.is_empty() && self.proxy_url.is_empty() {
             return Ok(());
         }
 
-        let (extension, format_param) = if self.icon_hash.starts_with("a_") {
-            ("gif", "gif")
+        ASSET_CACHE
+            .fetch(&Asset::preview(&self.download_url()), priority)
+            .await?;
+        Ok(())
+    }
+}
+
+/// A file uploaded with a message.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct Attachment {
+    pub id: String,
+    pub filename: String,
+    /// Alt text of images.
+    pub description: String,
+    /// Mime type, like image/png, empty if discord does not know it.
+    pub content_type: String,
+    /// In bytes.
+    pub size: u64,
+    pub url: String,
+    pub proxy_url: String,
+    /// Only known for images and videos.
+    pub width: u32,
+    pub height: u32,
+    pub placeholder: String,
+    pub placeholder_version: u64,
+}
+
+impl Attachment {
+    pub fn is_image(&self) -> bool {
+        self.content_type.starts_with("image/") && self.width > 0
+    }
+
+    pub fn is_video(&self) -> bool {
+        self.content_type.starts_with("video/") && self.width > 0
+    }
+
+    /// Files named SPOILER_ are hidden until clicked.
+    pub fn is_spoiler(&self) -> bool {
+        self.filename.starts_with("SPOILER_")
+    }
+
+    /// The preview shown in the message, for videos a still frame from the media proxy.
+    pub fn preview(&self) -> EmbedMedia {
+        let proxy_url = if self.is_video() {
+            let separator = if self.proxy_url.contains('?') {
+                '&'
+            } else {
+                '?'
+            };
+            format!("{}{}format=jpeg", self.proxy_url, separator)
         } else {
-            ("png", "png")
+            self.proxy_url.clone()
         };
 
-        let url = format!(
-            "https://cdn.discordapp.com/channel-icons/{}/{}.{}?size=64",
-            self.id, self.icon_hash, format_param
-        );
+        EmbedMedia {
+            url: self.url.clone(),
+            proxy_url,
+            width: self.width,
+            height: self.height,
+            placeholder: self.placeholder.clone(),
+            placeholder_version: self.placeholder_version,
+        }
+    }
+}
+
+/// A sticker sent with a message.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct Sticker {
+    pub id: String,
+    pub name: String,
+    /// 1 PNG, 2 APNG, 3 LOTTIE or 4 GIF.
+    pub format_type: u64,
+}
+
+impl Sticker {
+    /// None for lottie stickers, they are shown by their name.
+    pub fn asset(&self) -> Option<Asset> {
+        Asset::sticker(&self.id, self.format_type)
+    }
 
-        let bytes = HTTP_CLIENT.get(&url).send().await?.bytes().await?;
+    /// The still image, for GIF stickers their first frame.
+    pub fn load_image(&self) -> Image {
+        self.asset()
+            .map(|asset| image_cache::load_image(&asset))
+            .unwrap_or_default()
+    }
 
-        let folder = "./assets/channel_icons";
-        tokio::fs::create_dir_all(folder).await?;
+    pub fn load_animation(&self) -> Animation {
+        self.asset()
+            .map(|asset| image_cache::load_animation(&asset))
+            .unwrap_or_default()
+    }
 
-        let file_path = format!("{}/{}_{}.{}", folder, self.id, self.icon_hash, extension);
-        let mut file = File::create(&file_path).await?;
-        file.write_all(&bytes).await?;
+    pub async fn get_image(&self, priority: Priority) -> Result<(), Box<dyn Error + Send + Sync>> {
+        if let Some(asset) = self.asset() {
+            ASSET_CACHE.fetch(&asset, priority).await?;
+        }
         Ok(())
     }
 }
 
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct EmbedField {
+    pub name: String,
+    pub value: String,
+    /// Shown next to other inline fields.
+    pub inline: bool,
+}
+
+/// A rich embed or link preview of a message.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct Embed {
+    /// "rich", "image", "video", "gifv", "article" or "link".
+    pub kind: String,
+    pub title: String,
+    pub url: String,
+    pub description: String,
+    /// 0xRRGGBB of the bar on the left side.
+    pub color: Option<u32>,
+    /// ISO8601, empty if the embed has no timestamp.
+    pub timestamp: String,
+    /// Website name of link previews, like YouTube.
+    pub provider_name: String,
+    pub author_name: String,
+    pub author_url: String,
+    pub footer_text: String,
+    pub fields: Vec<EmbedField>,
+    pub image: Option<EmbedMedia>,
+    pub thumbnail: Option<EmbedMedia>,
+    pub video: Option<EmbedMedia>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Guild {
+    pub id: String,
     pub name: String,
+    /// Snowflake id of the last message per text channel id.
+    pub channel_last_message_ids: HashMap<String, u64>,
+    /// Name per channel id.
+    pub channel_names: HashMap<String, String>,
+    /// Name per role id.
+    pub role_names: HashMap<String, String>,
+    pub icon_hash: String,
+}
+
+impl Guild {
+    pub fn icon_asset(&self) -> Option<Asset> {
+        Asset::guild_icon(&self.id, &self.icon_hash, GUILD_ICON_SIZE)
+    }
+
+    pub fn load_icon_image(&self) -> Image {
+        self.icon_asset()
+            .map(|asset| image_cache::load_image(&asset))
+            .unwrap_or_default()
+    }
+
+    pub fn load_icon_animation(&self) -> Animation {
+        self.icon_asset()
+            .map(|asset| image_cache::load_animation(&asset))
+            .unwrap_or_default()
+    }
+
+    pub async fn get_icon(&self, priority: Priority) -> Result<(), Box<dyn Error + Send + Sync>> {
+        if let Some(asset) = self.icon_asset() {
+            ASSET_CACHE.fetch(&asset, priority).await?;
+        }
+        Ok(())
+    }
+}
+
+/// How far the client has read a channel.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReadState {
+    pub last_message_id: u64,
+    pub mention_count: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub enum MessageDelivery {
+    Sent,
+    /// Inserted locally, waiting for discord to confirm it.
+    Pending,
+    /// Sending failed, can be retried with the nonce.
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Message {
+    /// Empty until discord has confirmed the message.
+    pub id: String,
+    pub channel_id: String,
+    pub author: User,
+    pub content: String,
+    pub timestamp: String,
+    /// Empty if the message has never been edited.
+    pub edited_timestamp: String,
+    pub nonce: String,
+    /// id of the message this message is a reply to.
+    pub referenced_message_id: String,
+    pub flags: u64,
+    /// Users mentioned in the content.
+    pub mentions: Vec<User>,
+    pub delivery: MessageDelivery,
+    pub reactions: Vec<Reaction>,
+    pub embeds: Vec<Embed>,
+    pub attachments: Vec<Attachment>,
+    pub stickers: Vec<Sticker>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct Reaction {
+    pub emoji: CustomEmoji,
+    pub count: u64,
+    /// The current user reacted with this emoji.
+    pub me: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DownloadState {
+    Downloading,
+    /// Stopped by the user, the partial file is kept so it can be resumed.
+    Cancelled,
+    Done,
+    Failed(String),
+}
+
+/// A file being saved to the download folder.
+#[derive(Debug, Clone)]
+pub struct Download {
+    /// Id of the attachment.
+    pub id: String,
+    pub filename: String,
+    pub url: String,
+    /// Where the finished file is saved, it is written to path.part until then.
+    pub path: PathBuf,
+    /// Bytes written so far.
+    pub received: u64,
+    /// 0 if the size is unknown.
+    pub total: u64,
+    pub state: DownloadState,
+    /// Set to stop the download.
+    pub cancel: Arc<AtomicBool>,
+}
+
+/// The image shown large after clicking an image attachment.
+#[derive(Debug, Clone)]
+pub struct ImageViewer {
+    pub attachment: Attachment,
+    /// None while the full size image is loading.
+    pub pixels: Option<SharedPixelBuffer<Rgba8Pixel>>,
+}
+
+/// Which form the login screen shows.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LoginStep {
+    /// A token is saved, asks for its passphrase.
+    Unlock,
+    #[default]
+    Token,
+    /// Email and password.
+    Password,
+    /// The code of the authenticator app, after the password.
+    Mfa,
+    /// Asks for a passphrase to save the new token with.
+    Save,
+    /// The login screen is closed.
+    Done,
+}
+
+impl LoginStep {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LoginStep::Unlock => "unlock",
+            LoginStep::Token => "token",
+            LoginStep::Password => "password",
+            LoginStep::Mfa => "mfa",
+            LoginStep::Save => "save",
+            LoginStep::Done => "",
+        }
+    }
+}
+
+/// State of the login screen shown until there is a token.
+#[derive(Debug, Clone, Default)]
+pub struct Login {
+    pub step: LoginStep,
+    /// Why the last attempt failed, like a wrong passphrase.
+    pub error: Option<String>,
+    /// A request is running, the form is disabled.
+    pub busy: bool,
+    /// From the password login, sent with the MFA code.
+    pub mfa_ticket: String,
+}
+
+/// The users that reacted with one emoji on a message, shown in a popover.
+#[derive(Debug, Clone, Default)]
+pub struct ReactionUsers {
+    pub message_id: String,
+    /// CustomEmoji::reaction_key of the emoji.
+    pub emoji_key: String,
+    /// None while loading.
+    pub users: Option<Vec<User>>,
+}
+
+impl Message {
+    /// Unix timestamp in milliseconds, pending messages use the time of their nonce.
+    pub fn created_at_ms(&self) -> u64 {
+        snowflake_timestamp_ms(&self.id)
+            .or_else(|| snowflake_timestamp_ms(&self.nonce))
+            .unwrap_or_default()
+    }
 }
 
 #[derive(Debug, Default)]
 pub struct AppData {
+    /// Id of the session, logged with everything the session does.
+    pub session_id: u64,
+    /// Empty until the user has logged in.
+    pub token: Token,
+    pub login: Login,
+    /// Closes the gateway connection, None or closed while not connected.
+    pub disconnect: Option<oneshot::Sender<()>>,
     pub current_user: Option<User>,
     pub private_channels: Vec<PrivateChannel>,
     pub guilds: Vec<Guild>,
+    /// Messages per channel id, oldest first, only for channels whose history was loaded.
+    pub messages: HashMap<String, Vec<Message>>,
+    /// Latest message of private channels whose history is not loaded, for the channel list.
+    pub latest_messages: HashMap<String, Message>,
+    /// Messages that are being sent, or have failed, keyed by nonce.
+    pub pending_messages: HashMap<String, OutgoingMessage>,
+    pub selected_channel_id: Option<String>,
+    /// Channels where the oldest message has been loaded.
+    pub history_complete: HashSet<String>,
+    /// Channels where older messages are being fetched.
+    pub history_loading: HashSet<String>,
+    /// Unsent composer text per channel id.
+    pub drafts: HashMap<String, String>,
+    /// Read state per channel id, channels without one are read.
+    pub read_states: HashMap<String, ReadState>,
+    /// Status ("online", "idle", "dnd", "offline") per user id.
+    pub presences: HashMap<String, String>,
+    /// 0 = none, 1 = Nitro Classic, 2 = Nitro, 3 = Nitro Basic.
+    pub premium_type: u64,
+    /// Users of the reaction whose popover is open.
+    pub reaction_users: Option<ReactionUsers>,
+    /// Downloads of this session, oldest first.
+    pub downloads: Vec<Download>,
+    pub image_viewer: Option<ImageViewer>,
+    /// Files attached in the composer per channel id, sent with the next message.
+    pub composer_files: HashMap<String, Vec<UploadFile>>,
+    /// Why the last attached files were rejected, shown above the composer.
+    pub composer_error: Option<String>,
+    /// Bytes uploaded per UploadFile id, while their message is being sent.
+    pub upload_progress: HashMap<String, u64>,
+}
+
+impl AppData {
+    /// Sets the logged in user, the drafts of the account are loaded when it is a new one.
+    pub fn set_current_user(&mut self, user: User) {
+        if self.current_user.as_ref().map(|v| &v.id) != Some(&user.id) {
+            for (channel_id, draft) in load_drafts(&user.id) {
+                self.drafts.entry(channel_id).or_insert(draft);
+            }
+        }
+        self.current_user = Some(user);
+    }
+
+    /// Sorts the private channels by latest activity, newest first.
+    pub fn sort_private_channels(&mut self) {
+        self.private_channels.sort_by_key(|v| Reverse(v.sort_id));
+    }
+
+    /// Whether the channel has messages after the last acknowledged message.
+    pub fn is_unread(&self, channel_id: &str, last_message_id: u64) -> bool {
+        self.read_states
+            .get(channel_id)
+            .is_some_and(|v| last_message_id > v.last_message_id || v.mention_count > 0)
+    }
+
+    pub fn mention_count(&self, channel_id: &str) -> u64 {
+        self.read_states
+            .get(channel_id)
+            .map(|v| v.mention_count)
+            .unwrap_or_default()
+    }
+
+    /// The newest message of a channel, loaded or only received.
+    pub fn latest_message(&self, channel_id: &str) -> Option<&Message> {
+        self.messages
+            .get(channel_id)
+            .and_then(|messages| messages.last())
+            .or_else(|| self.latest_messages.get(channel_id))
+    }
+
+    /// The id of the last message in a private or guild channel, 0 if unknown.
+    pub fn last_message_id(&self, channel_id: &str) -> u64 {
+        self.private_channels
+            .iter()
+            .find(|v| v.id == channel_id)
+            .map(|v| v.last_message_id)
+            .or_else(|| {
+                self.guilds
+                    .iter()
+                    .find_map(|v| v.channel_last_message_ids.get(channel_id).copied())
+            })
+            .unwrap_or_default()
+    }
+
+    /// Nitro and Nitro Classic allow 4000 characters per message.
+    pub fn max_message_length(&self) -> usize {
+        match self.premium_type {
+            1 | 2 => 4000,
+            _ => 2000,
+        }
+    }
+
+    /// Largest file that can be uploaded, in bytes.
+    pub fn max_upload_size(&self) -> u64 {
+        const MB: u64 = 1024 * 1024;
+
+        match self.premium_type {
+            2 => 500 * MB,
+            1 | 3 => 50 * MB,
+            _ => 10 * MB,
+        }
+    }
+
+    /// Replaces the confirmed messages of a channel with fetched messages,
+    /// local pending and failed messages are kept at the end.
+    ///
+    /// Returns the indices of the changed messages.
+    pub fn set_channel_messages(
+        &mut self,
+        channel_id: &str,
+        fetched: Vec<Message>,
+    ) -> Range<usize> {
+        self.latest_messages.remove(channel_id);
+        let messages = self.messages.entry(channel_id.to_string()).or_default();
+
+        let local: Vec<Message> = messages
+            .drain(..)
+            .filter(|v| v.delivery != MessageDelivery::Sent)
+            .collect();
+
+        *messages = fetched;
+        messages.extend(local);
+
+        0..messages.len()
+    }
+
+    /// Inserts older messages before the messages of a channel.
+    ///
+    /// Returns the indices of the inserted messages.
+    pub fn prepend_channel_messages(
+        &mut self,
+        channel_id: &str,
+        mut older: Vec<Message>,
+    ) -> Range<usize> {
+        let messages = self.messages.entry(channel_id.to_string()).or_default();
+
+        older.retain(|v| !messages.iter().any(|existing| existing.id == v.id));
+        let inserted = 0..older.len();
+        messages.splice(0..0, older);
+
+        inserted
+    }
+
+    /// Inserts a message received from discord.
+    ///
+    /// If a local pending message has the same nonce it is replaced,
+    /// if a message with the same id exists it is updated.
+    ///
+    /// Returns the index of the message in its channel.
+    pub fn insert_message(&mut self, message: Message) -> usize {
+        if !message.nonce.is_empty() {
+            self.pending_messages.remove(&message.nonce);
+        }
+
+        let messages = self.messages.entry(message.channel_id.clone()).or_default();
+
+        let existing = messages.iter().position(|v| {
+            (!message.id.is_empty() && v.id == message.id)
+                || (!message.nonce.is_empty()
+                    && v.nonce == message.nonce
+                    && v.delivery != MessageDelivery::Sent)
+        });
+
+        match existing {
+            Some(index) => {
+                messages[index] = message;
+                index
+            }
+            None => {
+                messages.push(message);
+                messages.len() - 1
+            }
+        }
+    }
+
+    /// Adds or removes the reaction of one user, returns the index of the message if it changed.
+    ///
+    /// Reactions are toggled locally before the request is sent, so when the gateway event for
+    /// the current user arrives it is ignored if it is already applied.
+    pub fn update_reaction(
+        &mut self,
+        channel_id: &str,
+        message_id: &str,
+        emoji: &CustomEmoji,
+        me: bool,
+        added: bool,
+    ) -> Option<usize> {
+        let messages = self.messages.get_mut(channel_id)?;
+        let index = messages.iter().position(|v| v.id == message_id)?;
+        let reactions = &mut messages[index].reactions;
+
+        let key = emoji.reaction_key();
+        let existing = reactions.iter().position(|v| v.emoji.reaction_key() == key);
+
+        match (existing, added) {
+            (Some(position), true) => {
+                let reaction = &mut reactions[position];
+                if me && reaction.me {
+                    return None;
+                }
+                reaction.count += 1;
+                reaction.me |= me;
+            }
+            (None, true) => reactions.push(Reaction {
+                emoji: emoji.clone(),
+                count: 1,
+                me,
+            }),
+            (Some(position), false) => {
+                let reaction = &mut reactions[position];
+                if me && !reaction.me {
+                    return None;
+                }
+                reaction.count = reaction.count.saturating_sub(1);
+                reaction.me &= !me;
+                if reaction.count == 0 {
+                    reactions.remove(position);
+                }
+            }
+            (None, false) => return None,
+        }
+
+        Some(index)
+    }
+
+    /// Returns the index of the message in its channel.
+    pub fn set_message_delivery(
+        &mut self,
+        channel_id: &str,
+        nonce: &str,
+        delivery: MessageDelivery,
+    ) -> Option<usize> {
+        let messages = self.messages.get_mut(channel_id)?;
+        let index = messages
+            .iter()
+            .position(|v| v.nonce == nonce && v.id.is_empty())?;
+
+        messages[index].delivery = delivery;
+        Some(index)
+    }
 }
 
 pub type AppState = Arc<RwLock<AppData>>;
 
-pub fn create_app_state() -> AppState {
-    Arc::new(RwLock::new(AppData::default()))
+pub fn create_app_state(session_id: u64) -> AppState {
+    Arc::new(RwLock::new(AppData {
+        session_id,
+        ..AppData::default()
+    }))
 }
 
-pub type UpdateSender = mpsc::UnboundedSender<()>;
-pub type UpdateReceiver = mpsc::UnboundedReceiver<()>;
+/// What changed in AppData, so the UI only updates what is affected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UiUpdate {
+    CurrentUserChanged,
+    ChannelListChanged,
+    SelectedChannelChanged,
+    /// The avatar of the user with this id is downloaded.
+    AvatarReady(String),
+    /// The icon of the group channel with this id is downloaded.
+    ChannelIconReady(String),
+    /// The icon of the guild with this id is downloaded.
+    GuildIconReady(String),
+    /// The custom emoji with this id is downloaded.
+    EmojiReady(String),
+    /// The embed image with this url, or the sticker with this id, is downloaded.
+    MediaReady(String),
+    /// The status of the user with this id changed.
+    PresenceChanged(String),
+    /// Guilds were loaded.
+    GuildListChanged,
+    /// The unread state or mention count of the channel with this id changed.
+    ReadStateChanged(String),
+    /// Indices of the messages that changed in the channel with this id.
+    MessagesChanged(String, Range<usize>),
+    /// This many older messages were inserted before the messages of the channel with this id.
+    MessagesPrepended(String, usize),
+    /// The reaction popover was opened, or its users were loaded.
+    ReactionUsersChanged,
+    /// A download was added, removed or made progress.
+    DownloadsChanged,
+    /// The image viewer was opened or closed, or its image was loaded.
+    ImageViewerChanged,
+    /// Files were attached or removed in the composer, or were rejected.
+    ComposerFilesChanged,
+    /// The config file was changed.
+    ConfigChanged,
+    /// The login screen changed step, or a login attempt started or failed.
+    LoginChanged,
+    /// A new message in the channel with this id matches the notification rules.
+    Notify(String),
+    /// An account was added or removed, or a background account was notified or logged in.
+    SessionsChanged,
+    /// Another account is shown in the window.
+    SessionSwitched,
+}
+
+pub type UpdateSender = mpsc::UnboundedSender<UiUpdate>;
+pub type UpdateReceiver = mpsc::UnboundedReceiver<UiUpdate>;
+
+/// Download and upload progress is sent to the UI at most this often.
+pub const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
 
-/// Used for sending () as a UI update signal.
+/// Used for sending UiUpdate signals to the UI.
 pub fn create_update_channel() -> (UpdateSender, UpdateReceiver) {
     mpsc::unbounded_channel()
 }
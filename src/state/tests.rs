@@ -0,0 +1,169 @@
+use super::{AppData, Message, MessageDelivery, User};
+use crate::messages::outgoing_message::OutgoingMessage;
+
+const CHANNEL_ID: &str = "10";
+
+fn message(id: &str, nonce: &str, content: &str) -> Message {
+    Message {
+        id: id.to_string(),
+        channel_id: CHANNEL_ID.to_string(),
+        author: User {
+            id: "1".to_string(),
+            username: "alice".to_string(),
+            ..Default::default()
+        },
+        content: content.to_string(),
+        timestamp: String::new(),
+        edited_timestamp: String::new(),
+        nonce: nonce.to_string(),
+        referenced_message_id: String::new(),
+        flags: 0,
+        mentions: Vec::new(),
+        delivery: MessageDelivery::Sent,
+        reactions: Vec::new(),
+        embeds: Vec::new(),
+        attachments: Vec::new(),
+        stickers: Vec::new(),
+    }
+}
+
+/// A message shown locally while it is sent, like send_message inserts it.
+fn pending(app_data: &mut AppData, nonce: &str, content: &str) {
+    let mut message = message("", nonce, content);
+    message.delivery = MessageDelivery::Pending;
+    app_data
+        .messages
+        .entry(CHANNEL_ID.to_string())
+        .or_default()
+        .push(message);
+    app_data.pending_messages.insert(
+        nonce.to_string(),
+        OutgoingMessage {
+            content: content.to_string(),
+            ..Default::default()
+        },
+    );
+}
+
+fn channel(app_data: &AppData) -> Vec<(&str, &str, MessageDelivery)> {
+    app_data.messages[CHANNEL_ID]
+        .iter()
+        .map(|v| (v.id.as_str(), v.nonce.as_str(), v.delivery.clone()))
+        .collect()
+}
+
+#[test]
+fn replaces_a_pending_message_by_its_nonce() {
+    let mut app_data = AppData::default();
+    app_data.set_channel_messages(CHANNEL_ID, vec![message("100", "", "hi")]);
+    pending(&mut app_data, "5001", "first");
+    pending(&mut app_data, "5002", "second");
+
+    // MESSAGE_CREATE of the second message arrives before the first.
+    let index = app_data.insert_message(message("102", "5002", "second"));
+
+    assert_eq!(index, 2);
+    assert_eq!(
+        channel(&app_data),
+        [
+            ("100", "", MessageDelivery::Sent),
+            ("", "5001", MessageDelivery::Pending),
+            ("102", "5002", MessageDelivery::Sent),
+        ]
+    );
+    assert!(!app_data.pending_messages.contains_key("5002"));
+    assert!(app_data.pending_messages.contains_key("5001"));
+}
+
+#[test]
+fn marks_pending_messages_as_failed() {
+    let mut app_data = AppData::default();
+    pending(&mut app_data, "5001", "first");
+
+    assert_eq!(
+        app_data.set_message_delivery(CHANNEL_ID, "5001", MessageDelivery::Failed),
+        Some(0)
+    );
+    assert_eq!(channel(&app_data), [("", "5001", MessageDelivery::Failed)]);
+    // Kept so it can be retried with the same nonce.
+    assert!(app_data.pending_messages.contains_key("5001"));
+
+    // A retry that succeeds replaces the failed message.
+    app_data.set_message_delivery(CHANNEL_ID, "5001", MessageDelivery::Pending);
+    app_data.insert_message(message("101", "5001", "first"));
+    assert_eq!(channel(&app_data), [("101", "5001", MessageDelivery::Sent)]);
+
+    // Sent messages are not failed by a late error, or for an unknown nonce.
+    assert_eq!(
+        app_data.set_message_delivery(CHANNEL_ID, "5001", MessageDelivery::Failed),
+        None
+    );
+    assert_eq!(
+        app_data.set_message_delivery(CHANNEL_ID, "9999", MessageDelivery::Failed),
+        None
+    );
+    assert_eq!(
+        app_data.set_message_delivery("11", "5001", MessageDelivery::Failed),
+        None
+    );
+}
+
+#[test]
+fn deduplicates_the_response_and_message_create() {
+    let mut app_data = AppData::default();
+    pending(&mut app_data, "5001", "first");
+
+    // The gateway is faster than the response to POST /messages.
+    app_data.insert_message(message("101", "5001", "first"));
+    let index = app_data.insert_message(message("101", "5001", "first"));
+    assert_eq!(index, 0);
+    assert_eq!(channel(&app_data), [("101", "5001", MessageDelivery::Sent)]);
+
+    // Messages from other clients have no nonce, they are matched by id.
+    app_data.insert_message(message("102", "", "other"));
+    app_data.insert_message(message("102", "", "other, edited"));
+    assert_eq!(
+        channel(&app_data),
+        [
+            ("101", "5001", MessageDelivery::Sent),
+            ("102", "", MessageDelivery::Sent),
+        ]
+    );
+    assert_eq!(app_data.messages[CHANNEL_ID][1].content, "other, edited");
+
+    // A nonce reused by a later message does not replace a sent one.
+    app_data.insert_message(message("103", "5001", "again"));
+    assert_eq!(app_data.messages[CHANNEL_ID].len(), 3);
+}
+
+#[test]
+fn keeps_local_messages_when_history_is_loaded() {
+    let mut app_data = AppData::default();
+    app_data.set_channel_messages(CHANNEL_ID, vec![message("100", "", "old")]);
+    pending(&mut app_data, "5001", "first");
+    pending(&mut app_data, "5002", "second");
+    app_data.set_message_delivery(CHANNEL_ID, "5002", MessageDelivery::Failed);
+
+    let changed = app_data.set_channel_messages(
+        CHANNEL_ID,
+        vec![message("100", "", "old"), message("101", "", "new")],
+    );
+
+    assert_eq!(changed, 0..4);
+    assert_eq!(
+        channel(&app_data),
+        [
+            ("100", "", MessageDelivery::Sent),
+            ("101", "", MessageDelivery::Sent),
+            ("", "5001", MessageDelivery::Pending),
+            ("", "5002", MessageDelivery::Failed),
+        ]
+    );
+
+    // The pending message is still replaced once it is sent.
+    app_data.insert_message(message("102", "5001", "first"));
+    assert_eq!(
+        channel(&app_data)[2],
+        ("102", "5001", MessageDelivery::Sent)
+    );
+}
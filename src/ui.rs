@@ -1,78 +1,1148 @@
-use slint::{Image, ModelRc, SharedString, VecModel};
+use slint::{
+    platform::Key as SlintKey,
+    winit_030::{
+        winit::{
+            event::{ElementState, WindowEvent},
+            keyboard::{Key, ModifiersState, NamedKey},
+            window::UserAttentionType,
+        },
+        EventResult, WinitWindowAccessor,
+    },
+    ComponentHandle, Image, Model, ModelRc, SharedString, VecModel,
+};
 
-use crate::state::{AppState, ChannelType, UpdateReceiver};
-use std::error::Error;
+mod attachment_row;
+mod channel_list_model;
+mod embed_card;
+mod fit_image;
+mod message_list_model;
+mod rich_text;
+mod update_batch;
+
+#[cfg(test)]
+mod tests;
+
+use crate::assets::{
+    asset::PROFILE_AVATAR_SIZE,
+    asset_cache::{Priority, ASSET_CACHE},
+    image_cache::set_image_cache_budget,
+    scale_factor::set_scale_factor,
+};
+use crate::auth::log_in::{
+    log_in_with_password, log_in_with_token, save_login, show_login_step, skip_saving_login,
+    submit_mfa_code, unlock_saved_token,
+};
+use crate::config::{config::Config, load_config::config};
+use crate::downloads::download_attachment::{
+    cancel_download, download_attachment, resume_download,
+};
+use crate::media::{image_viewer::open_image_viewer, thumbhash::placeholder_image};
+use crate::messages::{
+    ack_message::ack_channel,
+    composer_files::add_composer_files,
+    drafts::save_drafts,
+    edit_message::edit_message,
+    fetch_messages::{load_channel_messages, load_older_messages},
+    outgoing_message::{MessageReference, OutgoingMessage, UploadFile},
+    reactions::{add_reaction, load_reaction_users, toggle_reaction},
+    send_message::{retry_message, send_message},
+};
+use crate::sessions::sessions::{Session, Sessions};
+use crate::state::{
+    AppData, AppState, Attachment, DownloadState, LoginStep, MessageDelivery, UiUpdate,
+    UpdateReceiver, UpdateSender,
+};
+use crate::utils::{format_size::format_size, open_url::open_url, pick_files::pick_files};
+use crate::websocket::load_initial_data::get_guilds::load_guild_icons;
+use channel_list_model::ChannelListModel;
+use message_list_model::{ListChange, MessageListModel};
+use std::{
+    cell::{Cell, RefCell},
+    error::Error,
+    time::Duration,
+};
+use tracing::{error, warn};
+use update_batch::UpdateBatch;
 slint::include_modules!();
 
+/// UI updates arriving within this interval are applied together.
+const FRAME_INTERVAL: Duration = Duration::from_millis(16);
+
+/// update_sender and update_receiver are the channel of the window,
+/// the sessions forward the updates of the active account to it.
 pub fn run_app(
-    app_state: AppState,
+    sessions: Sessions,
+    update_sender: UpdateSender,
     mut update_receiver: UpdateReceiver,
 ) -> Result<(), Box<dyn Error>> {
     let ui = AppWindow::new()?;
 
-    let update_ui = |ui: &AppWindow, app_state: &AppState| {
+    // Runs the REST requests started from the UI.
+    let runtime = tokio::runtime::Runtime::new()?;
+
+    let update_ui = |ui: &AppWindow, sessions: &Sessions, batch: &UpdateBatch| {
+        if batch.config {
+            apply_config(ui, &config());
+        }
+        if batch.notify {
+            request_attention(ui);
+        }
+        if batch.sessions || batch.current_user || !batch.avatars.is_empty() {
+            let accounts = ui.get_accounts();
+            if let Some(model) = accounts.as_any().downcast_ref::<VecModel<AccountRow>>() {
+                sync_account_rows(model, sessions);
+            }
+            ui.set_account_notified(sessions.all().iter().any(|(_, notified)| *notified));
+        }
+
+        let app_state = sessions.active().app_state;
         let guard = app_state.blocking_read();
 
-        ui.set_visible_name(SharedString::from(
-            guard
-                .current_user
-                .as_ref()
-                .map(|user| user.display_name())
-                .unwrap_or("<display_name>"),
-        ));
+        if batch.login {
+            ui.set_login_step(SharedString::from(guard.login.step.as_str()));
+            ui.set_login_error(SharedString::from(
+                guard.login.error.as_deref().unwrap_or_default(),
+            ));
+            ui.set_login_busy(guard.login.busy);
+        }
+
+        if batch.current_user {
+            ui.set_visible_name(SharedString::from(
+                guard
+                    .current_user
+                    .as_ref()
+                    .map(|user| user.display_name())
+                    .unwrap_or("<display_name>"),
+            ));
+
+            if let Some(user) = &guard.current_user {
+                ui.set_avatar_image(user.load_avatar_image(PROFILE_AVATAR_SIZE));
+                ui.set_avatar_animation(user.load_avatar_animation(PROFILE_AVATAR_SIZE).into());
+            }
 
-        if let Some(user) = &guard.current_user {
-            ui.set_avatar_image(user.load_avatar_image());
+            ui.set_max_message_length(guard.max_message_length() as i32);
+        } else if let Some(user) = guard
+            .current_user
+            .as_ref()
+            .filter(|user| batch.avatars.contains(&user.id))
+        {
+            ui.set_avatar_image(user.load_avatar_image(PROFILE_AVATAR_SIZE));
+            ui.set_avatar_animation(user.load_avatar_animation(PROFILE_AVATAR_SIZE).into());
         }
 
-        let private_channel_names: ModelRc<SharedString> = ModelRc::new(VecModel::from(
-            guard
-                .private_channels
-                .iter()
-                .map(|v| SharedString::from(v.display_name()))
-                .collect::<Vec<SharedString>>(),
-        ));
-        ui.set_private_channel_names(private_channel_names);
-
-        let private_channel_avatars: ModelRc<Image> = ModelRc::new(VecModel::from(
-            guard
-                .private_channels
-                .iter()
-                .map(|channel| match channel.channel_type {
-                    ChannelType::Group => {
-                        if !channel.icon_hash.is_empty() {
-                            channel.load_icon_image()
-                        } else {
-                            Image::default()
-                        }
-                    }
-                    ChannelType::Private => channel
-                        .recipients
-                        .first()
-                        .map(|user| user.load_avatar_image())
+        if batch.channel_list
+            || !batch.avatars.is_empty()
+            || !batch.channel_icons.is_empty()
+            || !batch.presences.is_empty()
+            || !batch.messages.is_empty()
+            || !batch.read_states.is_empty()
+        {
+            let private_channels = ui.get_private_channels();
+            if let Some(model) = private_channels.as_any().downcast_ref::<ChannelListModel>() {
+                model.sync(&guard, batch);
+            }
+        }
+
+        if batch.guild_list || !batch.guild_icons.is_empty() || !batch.read_states.is_empty() {
+            let guilds = ui.get_guilds();
+            if let Some(model) = guilds.as_any().downcast_ref::<VecModel<GuildRow>>() {
+                sync_guild_rows(model, &guard);
+            }
+        }
+
+        if batch.channel_list || batch.selected_channel {
+            let selected_channel = guard.selected_channel_id.as_ref().and_then(|id| {
+                guard
+                    .private_channels
+                    .iter()
+                    .find(|channel| &channel.id == id)
+            });
+            ui.set_selected_channel_id(SharedString::from(
+                selected_channel.map(|v| v.id.as_str()).unwrap_or_default(),
+            ));
+            ui.set_selected_channel_name(SharedString::from(
+                selected_channel
+                    .map(|v| v.display_name())
+                    .unwrap_or_default(),
+            ));
+        }
+
+        let changed_messages = guard
+            .selected_channel_id
+            .as_ref()
+            .and_then(|id| batch.messages.get(id))
+            .cloned();
+
+        if batch.selected_channel || changed_messages.is_some() {
+            let messages = ui.get_messages();
+            if let Some(model) = messages.as_any().downcast_ref::<MessageListModel>() {
+                match model.refresh(&guard, changed_messages.unwrap_or_default()) {
+                    ListChange::Reset => ui.set_follow_bottom(true),
+                    ListChange::Prepended => ui.set_history_prepended(true),
+                    ListChange::Changed | ListChange::None => {}
+                }
+            }
+        }
+
+        if batch.reaction_users {
+            let users = guard.reaction_users.as_ref().and_then(|v| v.users.as_ref());
+            ui.set_reaction_users_loading(users.is_none());
+            ui.set_reaction_users(ModelRc::new(VecModel::from(
+                users
+                    .into_iter()
+                    .flatten()
+                    .map(|user| SharedString::from(user.display_name()))
+                    .collect::<Vec<_>>(),
+            )));
+        }
+
+        if batch.downloads {
+            let downloads = ui.get_downloads();
+            if let Some(model) = downloads.as_any().downcast_ref::<VecModel<DownloadRow>>() {
+                sync_download_rows(model, &guard);
+            }
+        }
+
+        if batch.composer_files || batch.selected_channel {
+            let composer_files = ui.get_composer_files();
+            if let Some(model) = composer_files
+                .as_any()
+                .downcast_ref::<VecModel<ComposerFile>>()
+            {
+                let files = guard
+                    .selected_channel_id
+                    .as_ref()
+                    .and_then(|id| guard.composer_files.get(id));
+                set_rows(
+                    model,
+                    files
+                        .into_iter()
+                        .flatten()
+                        .map(|file| ComposerFile {
+                            id: SharedString::from(&file.id),
+                            filename: SharedString::from(&file.filename),
+                            size: SharedString::from(format_size(file.size)),
+                            spoiler: file.spoiler,
+                            description: SharedString::from(&file.description),
+                        })
+                        .collect(),
+                );
+            }
+            ui.set_composer_error(SharedString::from(
+                guard.composer_error.clone().unwrap_or_default(),
+            ));
+        }
+
+        if batch.image_viewer {
+            ui.set_viewer_open(guard.image_viewer.is_some());
+            if let Some(viewer) = &guard.image_viewer {
+                let attachment = &viewer.attachment;
+                ui.set_viewer_filename(SharedString::from(&attachment.filename));
+                ui.set_viewer_loading(viewer.pixels.is_none());
+
+                // The preview shown in the message stands in until the full size image is loaded.
+                let image = match &viewer.pixels {
+                    Some(pixels) => Image::from_rgba8(pixels.clone()),
+                    None => Some(attachment.preview().load_image())
+                        .filter(|v| v.size().width > 0)
+                        .or_else(|| {
+                            placeholder_image(
+                                &attachment.placeholder,
+                                attachment.placeholder_version,
+                            )
+                        })
                         .unwrap_or_default(),
-                })
-                .collect::<Vec<Image>>(),
-        ));
-        ui.set_private_channel_avatars(private_channel_avatars);
+                };
+                let size = image.size();
+                ui.set_viewer_aspect_ratio(if attachment.width > 0 && attachment.height > 0 {
+                    attachment.width as f32 / attachment.height as f32
+                } else if size.width > 0 && size.height > 0 {
+                    size.width as f32 / size.height as f32
+                } else {
+                    1.0
+                });
+                ui.set_viewer_image(image);
+            } else {
+                ui.set_viewer_image(Image::default());
+            }
+        }
+
+        if !batch.emojis.is_empty() || !batch.media.is_empty() {
+            let messages = ui.get_messages();
+            if let Some(model) = messages.as_any().downcast_ref::<MessageListModel>() {
+                model.assets_ready(&guard, &batch.emojis, &batch.media);
+            }
+        }
     };
 
-    update_ui(&ui, &app_state);
+    ui.set_private_channels(ModelRc::new(ChannelListModel::new(
+        runtime.handle().clone(),
+        update_sender.clone(),
+    )));
+    ui.set_guilds(ModelRc::new(VecModel::<GuildRow>::default()));
+    ui.set_downloads(ModelRc::new(VecModel::<DownloadRow>::default()));
+    ui.set_composer_files(ModelRc::new(VecModel::<ComposerFile>::default()));
+    ui.set_accounts(ModelRc::new(VecModel::<AccountRow>::default()));
+    ui.set_messages(ModelRc::new(MessageListModel::new(
+        sessions.clone(),
+        update_sender.clone(),
+        runtime.handle().clone(),
+    )));
+
+    set_scale_factor(ui.window().scale_factor());
+    apply_config(&ui, &config());
+
+    update_ui(&ui, &sessions, &UpdateBatch::everything());
+
+    ui.on_unlock_saved_token({
+        let sessions = sessions.clone();
+        let runtime = runtime.handle().clone();
+        move |passphrase| {
+            let Session {
+                app_state,
+                update_sender,
+                ..
+            } = sessions.active();
+            runtime.spawn(unlock_saved_token(
+                sessions.clone(),
+                app_state.clone(),
+                update_sender.clone(),
+                passphrase.to_string(),
+            ));
+        }
+    });
+
+    ui.on_log_in_with_token({
+        let sessions = sessions.clone();
+        let runtime = runtime.handle().clone();
+        move |token| {
+            let Session {
+                app_state,
+                update_sender,
+                ..
+            } = sessions.active();
+            runtime.spawn(log_in_with_token(
+                app_state.clone(),
+                update_sender.clone(),
+                token.to_string(),
+            ));
+        }
+    });
+
+    ui.on_log_in_with_password({
+        let sessions = sessions.clone();
+        let runtime = runtime.handle().clone();
+        move |email, password| {
+            let Session {
+                app_state,
+                update_sender,
+                ..
+            } = sessions.active();
+            runtime.spawn(log_in_with_password(
+                app_state.clone(),
+                update_sender.clone(),
+                email.to_string(),
+                password.to_string(),
+            ));
+        }
+    });
+
+    ui.on_submit_mfa_code({
+        let sessions = sessions.clone();
+        let runtime = runtime.handle().clone();
+        move |code| {
+            let Session {
+                app_state,
+                update_sender,
+                ..
+            } = sessions.active();
+            runtime.spawn(submit_mfa_code(
+                app_state.clone(),
+                update_sender.clone(),
+                code.to_string(),
+            ));
+        }
+    });
+
+    ui.on_save_login({
+        let sessions = sessions.clone();
+        let runtime = runtime.handle().clone();
+        move |passphrase, repeated| {
+            let Session {
+                app_state,
+                update_sender,
+                ..
+            } = sessions.active();
+            runtime.spawn(save_login(
+                app_state.clone(),
+                update_sender.clone(),
+                passphrase.to_string(),
+                repeated.to_string(),
+            ));
+        }
+    });
+
+    ui.on_skip_saving_login({
+        let sessions = sessions.clone();
+        let runtime = runtime.handle().clone();
+        move || {
+            let Session {
+                app_state,
+                update_sender,
+                ..
+            } = sessions.active();
+            runtime.spawn(skip_saving_login(app_state.clone(), update_sender.clone()));
+        }
+    });
+
+    ui.on_show_login_step({
+        let sessions = sessions.clone();
+        move |step| {
+            let Session {
+                app_state,
+                update_sender,
+                ..
+            } = sessions.active();
+            let step = match step.as_str() {
+                "unlock" => LoginStep::Unlock,
+                "password" => LoginStep::Password,
+                _ => LoginStep::Token,
+            };
+            show_login_step(&app_state, &update_sender, step);
+        }
+    });
+
+    ui.on_switch_account({
+        let weak_ui = ui.as_weak();
+        let sessions = sessions.clone();
+        move |index| {
+            if let Some(ui) = weak_ui.upgrade() {
+                switch_session(&ui, &sessions, |sessions| {
+                    sessions.switch_to(index.max(0) as usize)
+                });
+            }
+        }
+    });
+
+    ui.on_add_account({
+        let weak_ui = ui.as_weak();
+        let sessions = sessions.clone();
+        move || {
+            let Some(ui) = weak_ui.upgrade() else {
+                return;
+            };
+
+            let session = sessions.add();
+            let index = sessions
+                .all()
+                .iter()
+                .position(|(v, _)| v.id == session.id)
+                .unwrap_or_default();
+            switch_session(&ui, &sessions, |sessions| sessions.switch_to(index));
+        }
+    });
+
+    ui.on_cancel_login({
+        let weak_ui = ui.as_weak();
+        let sessions = sessions.clone();
+        move || {
+            let Some(ui) = weak_ui.upgrade() else {
+                return;
+            };
+
+            let session = sessions.active();
+            if session.app_state.blocking_read().login.step != LoginStep::Done {
+                switch_session(&ui, &sessions, |sessions| sessions.remove(session.id));
+            }
+        }
+    });
+
+    ui.on_channel_selected({
+        let weak_ui = ui.as_weak();
+        let sessions = sessions.clone();
+        let runtime = runtime.handle().clone();
+        move |channel_id| {
+            let Session {
+                app_state,
+                update_sender,
+                ..
+            } = sessions.active();
+            let Some(ui) = weak_ui.upgrade() else {
+                return;
+            };
+
+            let channel_id = {
+                let mut guard = app_state.blocking_write();
+                let Some(channel_id) = guard
+                    .private_channels
+                    .iter()
+                    .find(|channel| channel.id == channel_id.as_str())
+                    .map(|channel| channel.id.clone())
+                else {
+                    return;
+                };
+
+                // Keep the draft of the channel we are leaving.
+                if let Some(previous) = guard.selected_channel_id.take() {
+                    guard
+                        .drafts
+                        .insert(previous, ui.get_composer_text().to_string());
+                }
+
+                ui.set_composer_text(SharedString::from(
+                    guard.drafts.get(&channel_id).cloned().unwrap_or_default(),
+                ));
+                guard.selected_channel_id = Some(channel_id.clone());
+                guard.composer_error = None;
+
+                channel_id
+            };
+
+            ui.set_reply_to_id(SharedString::new());
+            ui.set_reply_to_author(SharedString::new());
+            ui.set_editing_message_id(SharedString::new());
+
+            let _ = update_sender.send(UiUpdate::SelectedChannelChanged);
+
+            runtime.spawn(async move {
+                load_channel_messages(app_state.clone(), update_sender.clone(), channel_id.clone())
+                    .await;
+                ack_channel(app_state, update_sender, channel_id).await;
+            });
+        }
+    });
+
+    ui.on_channel_list_scrolled({
+        let weak_ui = ui.as_weak();
+        let sessions = sessions.clone();
+        move |first, last| {
+            let app_state = sessions.active().app_state;
+            let Some(ui) = weak_ui.upgrade() else {
+                return;
+            };
+
+            let private_channels = ui.get_private_channels();
+            if let Some(model) = private_channels.as_any().downcast_ref::<ChannelListModel>() {
+                let first = first.max(0) as usize;
+                model.set_visible_rows(
+                    &app_state.blocking_read(),
+                    first..(last.max(0) as usize).max(first),
+                );
+            }
+        }
+    });
+
+    ui.on_send_message({
+        let weak_ui = ui.as_weak();
+        let sessions = sessions.clone();
+        let runtime = runtime.handle().clone();
+        move |text| {
+            let Session {
+                app_state,
+                update_sender,
+                ..
+            } = sessions.active();
+            let Some(ui) = weak_ui.upgrade() else {
+                return;
+            };
+
+            let Some(channel_id) = app_state.blocking_read().selected_channel_id.clone() else {
+                return;
+            };
+
+            let editing_message_id = ui.get_editing_message_id().to_string();
+
+            if !editing_message_id.is_empty() {
+                runtime.spawn(edit_message(
+                    app_state.clone(),
+                    update_sender.clone(),
+                    channel_id.clone(),
+                    editing_message_id,
+                    text.to_string(),
+                ));
+            } else {
+                // "@silent " at the start sends the message without notifications.
+                let (content, silent) = match text.strip_prefix("@silent ") {
+                    Some(content) => (content.to_string(), true),
+                    None => (text.to_string(), false),
+                };
+
+                let files = {
+                    let mut guard = app_state.blocking_write();
+                    guard.composer_error = None;
+                    guard.composer_files.remove(&channel_id).unwrap_or_default()
+                };
+                let _ = update_sender.send(UiUpdate::ComposerFilesChanged);
+
+                let reply_to_id = ui.get_reply_to_id();
+                let reply_to = (!reply_to_id.is_empty()).then(|| MessageReference {
+                    message_id: reply_to_id.to_string(),
+                    channel_id: channel_id.clone(),
+                });
+
+                runtime.spawn(send_message(
+                    app_state.clone(),
+                    update_sender.clone(),
+                    channel_id.clone(),
+                    OutgoingMessage {
+                        content,
+                        reply_to,
+                        allowed_mentions: None,
+                        silent,
+                        files,
+                    },
+                ));
+            }
+
+            app_state.blocking_write().drafts.remove(&channel_id);
+
+            ui.set_composer_text(SharedString::new());
+            ui.set_reply_to_id(SharedString::new());
+            ui.set_reply_to_author(SharedString::new());
+            ui.set_editing_message_id(SharedString::new());
+        }
+    });
+
+    ui.on_edit_last_message({
+        let weak_ui = ui.as_weak();
+        let sessions = sessions.clone();
+        move || {
+            let app_state = sessions.active().app_state;
+            let Some(ui) = weak_ui.upgrade() else {
+                return;
+            };
+
+            let guard = app_state.blocking_read();
+            let (Some(channel_id), Some(current_user)) =
+                (&guard.selected_channel_id, &guard.current_user)
+            else {
+                return;
+            };
+
+            let last_own_message = guard.messages.get(channel_id).and_then(|messages| {
+                messages
+                    .iter()
+                    .rev()
+                    .find(|v| v.author.id == current_user.id && v.delivery == MessageDelivery::Sent)
+            });
+
+            if let Some(message) = last_own_message {
+                ui.set_editing_message_id(SharedString::from(&message.id));
+                ui.set_composer_text(SharedString::from(&message.content));
+            }
+        }
+    });
+
+    ui.on_reply_to({
+        let weak_ui = ui.as_weak();
+        let sessions = sessions.clone();
+        move |message_id| {
+            let app_state = sessions.active().app_state;
+            let Some(ui) = weak_ui.upgrade() else {
+                return;
+            };
+
+            let guard = app_state.blocking_read();
+            let message = guard
+                .selected_channel_id
+                .as_ref()
+                .and_then(|id| guard.messages.get(id))
+                .and_then(|messages| messages.iter().find(|v| v.id == message_id.as_str()));
+
+            if let Some(message) = message {
+                ui.set_editing_message_id(SharedString::new());
+                ui.set_reply_to_id(SharedString::from(&message.id));
+                ui.set_reply_to_author(SharedString::from(message.author.display_name()));
+            }
+        }
+    });
+
+    ui.on_load_older_messages({
+        let sessions = sessions.clone();
+        let runtime = runtime.handle().clone();
+        move || {
+            let Session {
+                app_state,
+                update_sender,
+                ..
+            } = sessions.active();
+            let Some(channel_id) = app_state.blocking_read().selected_channel_id.clone() else {
+                return;
+            };
+
+            runtime.spawn(load_older_messages(
+                app_state.clone(),
+                update_sender.clone(),
+                channel_id,
+            ));
+        }
+    });
+
+    ui.on_retry_message({
+        let sessions = sessions.clone();
+        let runtime = runtime.handle().clone();
+        move |nonce| {
+            let Session {
+                app_state,
+                update_sender,
+                ..
+            } = sessions.active();
+            let Some(channel_id) = app_state.blocking_read().selected_channel_id.clone() else {
+                return;
+            };
+
+            runtime.spawn(retry_message(
+                app_state.clone(),
+                update_sender.clone(),
+                channel_id,
+                nonce.to_string(),
+            ));
+        }
+    });
+
+    ui.on_copy_to_clipboard({
+        // Kept alive, on linux the copied text is served by the clipboard owner.
+        let clipboard = RefCell::new(None::<arboard::Clipboard>);
+        move |text| {
+            let mut clipboard = clipboard.borrow_mut();
+            if clipboard.is_none() {
+                match arboard::Clipboard::new() {
+                    Ok(v) => *clipboard = Some(v),
+                    Err(e) => {
+                        warn!("Failed to open the clipboard: {}", e);
+                        return;
+                    }
+                }
+            }
+
+            if let Some(Err(e)) = clipboard.as_mut().map(|v| v.set_text(text.as_str())) {
+                warn!("Failed to copy to the clipboard: {}", e);
+            }
+        }
+    });
+
+    ui.on_toggle_reaction({
+        let sessions = sessions.clone();
+        let runtime = runtime.handle().clone();
+        move |message_id, emoji_key| {
+            let Session {
+                app_state,
+                update_sender,
+                ..
+            } = sessions.active();
+            let Some(channel_id) = app_state.blocking_read().selected_channel_id.clone() else {
+                return;
+            };
+
+            runtime.spawn(toggle_reaction(
+                app_state.clone(),
+                update_sender.clone(),
+                channel_id,
+                message_id.to_string(),
+                emoji_key.to_string(),
+            ));
+        }
+    });
+
+    ui.on_add_reaction({
+        let sessions = sessions.clone();
+        let runtime = runtime.handle().clone();
+        move |message_id, text| {
+            let Session {
+                app_state,
+                update_sender,
+                ..
+            } = sessions.active();
+            let Some(channel_id) = app_state.blocking_read().selected_channel_id.clone() else {
+                return;
+            };
+
+            runtime.spawn(add_reaction(
+                app_state.clone(),
+                update_sender.clone(),
+                channel_id,
+                message_id.to_string(),
+                text.to_string(),
+            ));
+        }
+    });
+
+    ui.on_show_reaction_users({
+        let sessions = sessions.clone();
+        let runtime = runtime.handle().clone();
+        move |message_id, emoji_key| {
+            let Session {
+                app_state,
+                update_sender,
+                ..
+            } = sessions.active();
+            let Some(channel_id) = app_state.blocking_read().selected_channel_id.clone() else {
+                return;
+            };
+
+            runtime.spawn(load_reaction_users(
+                app_state.clone(),
+                update_sender.clone(),
+                channel_id,
+                message_id.to_string(),
+                emoji_key.to_string(),
+            ));
+        }
+    });
+
+    ui.on_attach_files({
+        let sessions = sessions.clone();
+        let runtime = runtime.handle().clone();
+        move || {
+            let Session {
+                app_state,
+                update_sender,
+                ..
+            } = sessions.active();
+            let Some(channel_id) = app_state.blocking_read().selected_channel_id.clone() else {
+                return;
+            };
+
+            runtime.spawn(async move {
+                let paths = match tokio::task::spawn_blocking(pick_files).await {
+                    Ok(Ok(paths)) => paths,
+                    Ok(Err(e)) => {
+                        warn!("Failed to open the file picker: {}", e);
+                        return;
+                    }
+                    Err(_) => return,
+                };
+                if !paths.is_empty() {
+                    add_composer_files(app_state, update_sender, channel_id, paths).await;
+                }
+            });
+        }
+    });
+
+    ui.on_remove_composer_file({
+        let sessions = sessions.clone();
+        move |file_id| {
+            let Session {
+                app_state,
+                update_sender,
+                ..
+            } = sessions.active();
+            let mut guard = app_state.blocking_write();
+            let Some(channel_id) = guard.selected_channel_id.clone() else {
+                return;
+            };
+            if let Some(files) = guard.composer_files.get_mut(&channel_id) {
+                files.retain(|v| v.id != file_id.as_str());
+            }
+            guard.composer_error = None;
+            let _ = update_sender.send(UiUpdate::ComposerFilesChanged);
+        }
+    });
+
+    ui.on_set_composer_file_spoiler({
+        let sessions = sessions.clone();
+        move |file_id, spoiler| {
+            let Session {
+                app_state,
+                update_sender,
+                ..
+            } = sessions.active();
+            update_composer_file(&app_state, &file_id, |file| file.spoiler = spoiler);
+            let _ = update_sender.send(UiUpdate::ComposerFilesChanged);
+        }
+    });
+
+    ui.on_set_composer_file_description({
+        let sessions = sessions.clone();
+        move |file_id, description| {
+            let Session {
+                app_state,
+                update_sender,
+                ..
+            } = sessions.active();
+            update_composer_file(&app_state, &file_id, |file| {
+                file.description = description.to_string()
+            });
+            let _ = update_sender.send(UiUpdate::ComposerFilesChanged);
+        }
+    });
+
+    // Files dropped on the window are attached to the composer of the selected channel.
+    ui.window().on_winit_window_event({
+        let weak_ui = ui.as_weak();
+        let sessions = sessions.clone();
+        let runtime = runtime.handle().clone();
+        // Kept from ModifiersChanged, KeyboardInput does not carry them.
+        let modifiers = Cell::new(ModifiersState::empty());
+        move |_, event| {
+            let Some(ui) = weak_ui.upgrade() else {
+                return EventResult::Propagate;
+            };
+
+            match event {
+                WindowEvent::ModifiersChanged(changed) => modifiers.set(changed.state()),
+                // Handled here so it works wherever the focus is.
+                WindowEvent::KeyboardInput { event, .. }
+                    if event.state == ElementState::Pressed
+                        && matches_key_binding(
+                            &config().keybindings.switch_account,
+                            &event.logical_key,
+                            modifiers.get(),
+                        ) =>
+                {
+                    if !event.repeat {
+                        switch_session(&ui, &sessions, Sessions::cycle);
+                    }
+                    return EventResult::PreventDefault;
+                }
+                WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                    let Session {
+                        app_state,
+                        update_sender,
+                        ..
+                    } = sessions.active();
+                    set_scale_factor(*scale_factor as f32);
+
+                    // Avatars and icons are downloaded again at the size for the new scale.
+                    let _runtime = runtime.enter();
+                    load_guild_icons(app_state.clone(), update_sender.clone());
+                    if let Some(user) = app_state.blocking_read().current_user.clone() {
+                        let update_sender = update_sender.clone();
+                        runtime.spawn(async move {
+                            if user
+                                .get_avatar(PROFILE_AVATAR_SIZE, Priority::Visible)
+                                .await
+                                .is_ok()
+                            {
+                                let _ = update_sender.send(UiUpdate::AvatarReady(user.id));
+                            }
+                        });
+                    }
+                    let _ = update_sender.send(UiUpdate::ChannelListChanged);
+                }
+                WindowEvent::Focused(focused) => {
+                    ui.global::<ImageAnimation>().set_window_active(*focused)
+                }
+                WindowEvent::HoveredFile(_) => ui.set_dropping_files(true),
+                WindowEvent::HoveredFileCancelled => ui.set_dropping_files(false),
+                WindowEvent::DroppedFile(path) => {
+                    ui.set_dropping_files(false);
+
+                    let Session {
+                        app_state,
+                        update_sender,
+                        ..
+                    } = sessions.active();
+                    let selected_channel_id = app_state.blocking_read().selected_channel_id.clone();
+                    if let Some(channel_id) = selected_channel_id {
+                        runtime.spawn(add_composer_files(
+                            app_state.clone(),
+                            update_sender.clone(),
+                            channel_id,
+                            vec![path.clone()],
+                        ));
+                    }
+                }
+                _ => {}
+            }
+
+            EventResult::Propagate
+        }
+    });
+
+    ui.on_open_image({
+        let sessions = sessions.clone();
+        let runtime = runtime.handle().clone();
+        move |attachment_id| {
+            let Session {
+                app_state,
+                update_sender,
+                ..
+            } = sessions.active();
+            let Some(attachment) = find_attachment(&app_state.blocking_read(), &attachment_id)
+            else {
+                return;
+            };
+
+            runtime.spawn(open_image_viewer(
+                app_state.clone(),
+                update_sender.clone(),
+                attachment,
+            ));
+        }
+    });
+
+    ui.on_close_image_viewer({
+        let sessions = sessions.clone();
+        move || {
+            let Session {
+                app_state,
+                update_sender,
+                ..
+            } = sessions.active();
+            app_state.blocking_write().image_viewer = None;
+            let _ = update_sender.send(UiUpdate::ImageViewerChanged);
+        }
+    });
+
+    ui.on_open_image_original({
+        let sessions = sessions.clone();
+        move || {
+            let app_state = sessions.active().app_state;
+            let Some(url) = app_state
+                .blocking_read()
+                .image_viewer
+                .as_ref()
+                .map(|v| v.attachment.url.clone())
+            else {
+                return;
+            };
+
+            if let Err(e) = open_url(&url) {
+                warn!("Failed to open {}: {}", url, e);
+            }
+        }
+    });
+
+    ui.on_download_viewed_image({
+        let sessions = sessions.clone();
+        let runtime = runtime.handle().clone();
+        move || {
+            let Session {
+                app_state,
+                update_sender,
+                ..
+            } = sessions.active();
+            let Some(attachment) = app_state
+                .blocking_read()
+                .image_viewer
+                .as_ref()
+                .map(|v| v.attachment.clone())
+            else {
+                return;
+            };
+
+            runtime.spawn(download_attachment(
+                app_state.clone(),
+                update_sender.clone(),
+                attachment,
+            ));
+        }
+    });
+
+    ui.on_download_attachment({
+        let sessions = sessions.clone();
+        let runtime = runtime.handle().clone();
+        move |attachment_id| {
+            let Session {
+                app_state,
+                update_sender,
+                ..
+            } = sessions.active();
+            let Some(attachment) = find_attachment(&app_state.blocking_read(), &attachment_id)
+            else {
+                return;
+            };
+
+            runtime.spawn(download_attachment(
+                app_state.clone(),
+                update_sender.clone(),
+                attachment,
+            ));
+        }
+    });
+
+    ui.on_cancel_download({
+        let sessions = sessions.clone();
+        let runtime = runtime.handle().clone();
+        move |id| {
+            let app_state = sessions.active().app_state;
+            runtime.spawn(cancel_download(app_state.clone(), id.to_string()));
+        }
+    });
+
+    ui.on_resume_download({
+        let sessions = sessions.clone();
+        let runtime = runtime.handle().clone();
+        move |id| {
+            let Session {
+                app_state,
+                update_sender,
+                ..
+            } = sessions.active();
+            runtime.spawn(resume_download(
+                app_state.clone(),
+                update_sender.clone(),
+                id.to_string(),
+            ));
+        }
+    });
+
+    ui.on_open_download({
+        let sessions = sessions.clone();
+        move |id| {
+            let app_state = sessions.active().app_state;
+            let Some(path) = app_state
+                .blocking_read()
+                .downloads
+                .iter()
+                .find(|v| v.id == id.as_str())
+                .map(|v| v.path.clone())
+            else {
+                return;
+            };
+
+            if let Err(e) = open_url(&path.to_string_lossy()) {
+                warn!("Failed to open {}: {}", path.display(), e);
+            }
+        }
+    });
+
+    ui.on_dismiss_download({
+        let sessions = sessions.clone();
+        move |id| {
+            let Session {
+                app_state,
+                update_sender,
+                ..
+            } = sessions.active();
+            app_state
+                .blocking_write()
+                .downloads
+                .retain(|v| v.id != id.as_str() || v.state == DownloadState::Downloading);
+            let _ = update_sender.send(UiUpdate::DownloadsChanged);
+        }
+    });
+
+    ui.on_open_url(|url| {
+        if let Err(e) = open_url(&url) {
+            warn!("Failed to open {}: {}", url, e);
+        }
+    });
 
     let weak_ui = ui.as_weak();
 
-    let app_state_clone = app_state.clone();
+    let sessions_clone = sessions.clone();
 
     std::thread::spawn(move || {
         let rt = tokio::runtime::Runtime::new().unwrap();
         rt.block_on(async {
-            while let Some(()) = update_receiver.recv().await {
+            while let Some(update) = update_receiver.recv().await {
+                let mut batch = UpdateBatch::default();
+                batch.add(update);
+
+                // Coalesce everything that arrives within the same frame into one UI update.
+                tokio::time::sleep(FRAME_INTERVAL).await;
+                while let Ok(update) = update_receiver.try_recv() {
+                    batch.add(update);
+                }
+
                 let weak_ui = weak_ui.clone();
-                let app_state = app_state_clone.clone();
+                let sessions = sessions_clone.clone();
 
                 slint::invoke_from_event_loop(move || {
                     if let Some(ui) = weak_ui.upgrade() {
-                        update_ui(&ui, &app_state);
+                        update_ui(&ui, &sessions, &batch);
                     }
                 })
                 .unwrap();
@@ -81,5 +1151,305 @@ pub fn run_app(
     });
 
     ui.run()?;
+
+    keep_composer_draft(&ui, &sessions.active().app_state);
+    for (session, _) in sessions.all() {
+        save_session_drafts(&session.app_state);
+    }
+    if let Err(e) = ASSET_CACHE.save() {
+        error!("Failed to save the asset index: {}", e);
+    }
+
     Ok(())
 }
+
+/// Applies the settings of the config file that do not need a new connection.
+fn apply_config(ui: &AppWindow, config: &Config) {
+    let color = |color: crate::config::config::Color| {
+        slint::Color::from_rgb_u8(color.red, color.green, color.blue)
+    };
+
+    let theme = ui.global::<Theme>();
+    theme.set_primary(color(config.theme.primary));
+    theme.set_background(color(config.theme.background));
+    theme.set_card(color(config.theme.card));
+    theme.set_text(color(config.theme.text));
+    theme.set_font_family(SharedString::from(&config.fonts.family));
+    theme.set_message_font_size(config.fonts.message_size as f32);
+    theme.set_composer_font_size(config.fonts.composer_size as f32);
+    theme.set_channel_list_font_size(config.fonts.channel_list_size as f32);
+
+    let keybindings = ui.global::<Keybindings>();
+    keybindings.set_send_message((&config.keybindings.send_message).into());
+    keybindings.set_edit_last_message((&config.keybindings.edit_last_message).into());
+    keybindings.set_cancel((&config.keybindings.cancel).into());
+    keybindings.set_close_image_viewer((&config.keybindings.close_image_viewer).into());
+
+    // The profile card cycles through the modes until the next change of the file.
+    ui.global::<ImageAnimation>()
+        .set_mode(SharedString::from(config.theme.animate_images.as_str()));
+
+    ASSET_CACHE.set_budget(config.cache.assets_mb * 1024 * 1024);
+    set_image_cache_budget(config.cache.images_mb as usize * 1024 * 1024);
+}
+
+/// Shows another account, the composer text is kept as a draft of the account it was typed in.
+fn switch_session(ui: &AppWindow, sessions: &Sessions, switch: impl FnOnce(&Sessions)) {
+    let previous = sessions.active().app_state;
+    keep_composer_draft(ui, &previous);
+    save_session_drafts(&previous);
+
+    switch(sessions);
+
+    let app_state = sessions.active().app_state;
+    let guard = app_state.blocking_read();
+    ui.set_composer_text(SharedString::from(
+        guard
+            .selected_channel_id
+            .as_ref()
+            .and_then(|id| guard.drafts.get(id))
+            .cloned()
+            .unwrap_or_default(),
+    ));
+    ui.set_reply_to_id(SharedString::new());
+    ui.set_reply_to_author(SharedString::new());
+    ui.set_editing_message_id(SharedString::new());
+}
+
+/// Keeps the composer text as the draft of the selected channel.
+fn keep_composer_draft(ui: &AppWindow, app_state: &AppState) {
+    let mut guard = app_state.blocking_write();
+    if let Some(channel_id) = guard.selected_channel_id.clone() {
+        guard
+            .drafts
+            .insert(channel_id, ui.get_composer_text().to_string());
+    }
+}
+
+/// Writes the drafts of a session to the file of its account.
+fn save_session_drafts(app_state: &AppState) {
+    let guard = app_state.blocking_read();
+    let Some(user) = &guard.current_user else {
+        return;
+    };
+    if let Err(e) = save_drafts(&user.id, &guard.drafts) {
+        error!("Failed to save drafts: {}", e);
+    }
+}
+
+/// Whether a key pressed in the window is the shortcut, compared like Keybindings.matches.
+fn matches_key_binding(
+    binding: &crate::config::key_binding::KeyBinding,
+    key: &Key,
+    modifiers: ModifiersState,
+) -> bool {
+    let text = match key {
+        Key::Character(text) => text.to_lowercase(),
+        Key::Named(named) => {
+            let key = match named {
+                NamedKey::Enter => SlintKey::Return,
+                NamedKey::Escape => SlintKey::Escape,
+                NamedKey::Tab => SlintKey::Tab,
+                NamedKey::Space => SlintKey::Space,
+                NamedKey::Backspace => SlintKey::Backspace,
+                NamedKey::Delete => SlintKey::Delete,
+                NamedKey::ArrowUp => SlintKey::UpArrow,
+                NamedKey::ArrowDown => SlintKey::DownArrow,
+                NamedKey::ArrowLeft => SlintKey::LeftArrow,
+                NamedKey::ArrowRight => SlintKey::RightArrow,
+                NamedKey::Home => SlintKey::Home,
+                NamedKey::End => SlintKey::End,
+                NamedKey::PageUp => SlintKey::PageUp,
+                NamedKey::PageDown => SlintKey::PageDown,
+                NamedKey::F1 => SlintKey::F1,
+                NamedKey::F2 => SlintKey::F2,
+                NamedKey::F3 => SlintKey::F3,
+                NamedKey::F4 => SlintKey::F4,
+                NamedKey::F5 => SlintKey::F5,
+                NamedKey::F6 => SlintKey::F6,
+                NamedKey::F7 => SlintKey::F7,
+                NamedKey::F8 => SlintKey::F8,
+                NamedKey::F9 => SlintKey::F9,
+                NamedKey::F10 => SlintKey::F10,
+                NamedKey::F11 => SlintKey::F11,
+                NamedKey::F12 => SlintKey::F12,
+                _ => return false,
+            };
+            char::from(key).to_string()
+        }
+        _ => return false,
+    };
+
+    text == binding.key
+        && modifiers.control_key() == binding.control
+        && modifiers.shift_key() == binding.shift
+        && modifiers.alt_key() == binding.alt
+        && modifiers.super_key() == binding.meta
+}
+
+fn sync_account_rows(model: &VecModel<AccountRow>, sessions: &Sessions) {
+    let active = sessions.active().id;
+    let rows = sessions
+        .all()
+        .into_iter()
+        .map(|(session, notified)| {
+            let app_data = session.app_state.blocking_read();
+            let user = app_data.current_user.as_ref();
+            AccountRow {
+                name: SharedString::from(user.map(|v| v.display_name()).unwrap_or("Not logged in")),
+                avatar: user
+                    .map(|v| v.load_avatar_image(PROFILE_AVATAR_SIZE))
+                    .unwrap_or_default(),
+                active: session.id == active,
+                notified,
+            }
+        })
+        .collect();
+
+    set_rows(model, rows);
+}
+
+/// Flashes the taskbar entry, unless the window is already in front.
+fn request_attention(ui: &AppWindow) {
+    ui.window().with_winit_window(|window| {
+        if !window.has_focus() {
+            window.request_user_attention(Some(UserAttentionType::Informational));
+        }
+    });
+}
+
+fn sync_guild_rows(model: &VecModel<GuildRow>, app_data: &AppData) {
+    let rows: Vec<GuildRow> = app_data
+        .guilds
+        .iter()
+        .map(|guild| GuildRow {
+            id: SharedString::from(&guild.id),
+            name: SharedString::from(&guild.name),
+            icon: guild.load_icon_image(),
+            icon_animation: guild.load_icon_animation().into(),
+            initials: SharedString::from(
+                guild
+                    .name
+                    .split_whitespace()
+                    .filter_map(|word| word.chars().next())
+                    .take(3)
+                    .collect::<String>(),
+            ),
+            unread: guild
+                .channel_last_message_ids
+                .iter()
+                .any(|(channel_id, &last_message_id)| {
+                    app_data.is_unread(channel_id, last_message_id)
+                }),
+            mention_count: guild
+                .channel_last_message_ids
+                .keys()
+                .map(|channel_id| app_data.mention_count(channel_id))
+                .sum::<u64>() as i32,
+        })
+        .collect();
+
+    set_rows(model, rows);
+}
+
+/// Progress changes often while downloading, so the rows are updated in place.
+fn sync_download_rows(model: &VecModel<DownloadRow>, app_data: &AppData) {
+    let rows: Vec<DownloadRow> = app_data
+        .downloads
+        .iter()
+        .map(|download| {
+            let (state, status) = match &download.state {
+                DownloadState::Downloading if download.total > 0 => (
+                    "downloading",
+                    format!(
+                        "{} / {}",
+                        format_size(download.received),
+                        format_size(download.total)
+                    ),
+                ),
+                DownloadState::Downloading => ("downloading", format_size(download.received)),
+                DownloadState::Cancelled => ("cancelled", "Cancelled".to_string()),
+                DownloadState::Done => ("done", format_size(download.received)),
+                DownloadState::Failed(error) => ("failed", error.clone()),
+            };
+
+            DownloadRow {
+                id: SharedString::from(&download.id),
+                filename: SharedString::from(&download.filename),
+                state: SharedString::from(state),
+                progress: if download.total > 0 {
+                    download.received as f32 / download.total as f32
+                } else {
+                    -1.0
+                },
+                status: SharedString::from(status),
+            }
+        })
+        .collect();
+
+    set_rows(model, rows);
+}
+
+/// An attachment of a message in the selected channel.
+fn find_attachment(app_data: &AppData, attachment_id: &str) -> Option<Attachment> {
+    let channel_id = app_data.selected_channel_id.as_ref()?;
+    app_data
+        .messages
+        .get(channel_id)?
+        .iter()
+        .flat_map(|message| &message.attachments)
+        .find(|v| v.id == attachment_id)
+        .cloned()
+}
+
+/// Replaces the rows of a model, in place if the number of rows is the same,
+/// so only changed rows are relayouted.
+fn set_rows<T: Clone + PartialEq + 'static>(model: &VecModel<T>, rows: Vec<T>) {
+    if rows.len() != model.row_count() {
+        model.set_vec(rows);
+        return;
+    }
+
+    for (index, row) in rows.into_iter().enumerate() {
+        if model.row_data(index).as_ref() != Some(&row) {
+            model.set_row_data(index, row);
+        }
+    }
+}
+
+/// Changes a file attached in the composer of the selected channel.
+fn update_composer_file(app_state: &AppState, file_id: &str, update: impl FnOnce(&mut UploadFile)) {
+    let mut guard = app_state.blocking_write();
+    let Some(channel_id) = guard.selected_channel_id.clone() else {
+        return;
+    };
+
+    if let Some(file) = guard
+        .composer_files
+        .get_mut(&channel_id)
+        .and_then(|files| files.iter_mut().find(|v| v.id == file_id))
+    {
+        update(file);
+    }
+}
+
+impl From<crate::assets::animation::Animation> for Animation {
+    fn from(animation: crate::assets::animation::Animation) -> Self {
+        Self {
+            frames: animation.frames,
+            frame_ms: animation.frame_ms,
+        }
+    }
+}
+
+impl From<&crate::config::key_binding::KeyBinding> for KeyBinding {
+    fn from(binding: &crate::config::key_binding::KeyBinding) -> Self {
+        Self {
+            key: SharedString::from(&binding.key),
+            control: binding.control,
+            shift: binding.shift,
+            alt: binding.alt,
+            meta: binding.meta,
+        }
+    }
+}
@@ -0,0 +1,75 @@
+use slint::SharedString;
+
+use super::{fit_image::fit_image, AttachmentRow};
+use crate::{media::media_loader::MediaLoader, state::Attachment, utils::format_size::format_size};
+
+/// Converts an attachment into what is shown below the message content,
+/// images and videos as previews and other files as cards.
+///
+/// upload_progress is set for the files of a pending message, from 0 to 1.
+pub fn render_attachment(
+    attachment: &Attachment,
+    media_loader: &MediaLoader,
+    upload_progress: Option<f32>,
+) -> AttachmentRow {
+    let kind = if attachment.is_image() {
+        "image"
+    } else if attachment.is_video() {
+        "video"
+    } else {
+        "file"
+    };
+
+    let (width, height) = fit_image(attachment.width, attachment.height);
+    let preview = (kind != "file").then(|| attachment.preview());
+
+    AttachmentRow {
+        id: SharedString::from(&attachment.id),
+        filename: SharedString::from(&attachment.filename),
+        url: SharedString::from(&attachment.url),
+        size: SharedString::from(format_size(attachment.size)),
+        kind: SharedString::from(kind),
+        icon: SharedString::from(file_icon(attachment)),
+        image: preview
+            .as_ref()
+            .map(|v| media_loader.load(v))
+            .unwrap_or_default(),
+        placeholder: preview
+            .as_ref()
+            .map(|v| media_loader.placeholder(v))
+            .unwrap_or_default(),
+        width,
+        height,
+        spoiler: attachment.is_spoiler(),
+        description: SharedString::from(&attachment.description),
+        upload_progress: upload_progress.unwrap_or(-1.0),
+    }
+}
+
+fn file_icon(attachment: &Attachment) -> &'static str {
+    let extension = attachment
+        .filename
+        .rsplit_once('.')
+        .map(|(_, v)| v.to_ascii_lowercase())
+        .unwrap_or_default();
+    let content_type = attachment.content_type.as_str();
+
+    if content_type.starts_with("image/") {
+        "🖼"
+    } else if content_type.starts_with("video/") {
+        "🎞"
+    } else if content_type.starts_with("audio/") {
+        "🎵"
+    } else if ["zip", "rar", "7z", "tar", "gz", "xz", "zst"].contains(&extension.as_str()) {
+        "📦"
+    } else if content_type.starts_with("text/")
+        || ["txt", "md", "log", "json", "toml", "yaml", "rs", "py", "js"]
+            .contains(&extension.as_str())
+    {
+        "📝"
+    } else if extension == "pdf" {
+        "📕"
+    } else {
+        "📄"
+    }
+}
@@ -0,0 +1,290 @@
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    ops::Range,
+};
+
+use slint::{Model, ModelTracker, SharedString, VecModel};
+use tokio::{runtime::Handle, task::AbortHandle};
+use tracing::warn;
+
+use super::{update_batch::UpdateBatch, ChannelRow};
+use crate::{
+    assets::{
+        asset::Asset,
+        asset_cache::{Priority, ASSET_CACHE},
+    },
+    state::{AppData, ChannelType, PrivateChannel, UiUpdate, UpdateSender},
+};
+
+/// Rows assumed to be visible until the list reports its scroll position.
+const INITIAL_VISIBLE_ROWS: usize = 20;
+
+/// Long lived model for the private channel list.
+///
+/// sync moves, inserts, removes and updates rows in place,
+/// so slint keeps the scroll position and only relayouts the rows that changed.
+/// Rows only move when the order of the channels changed,
+/// and only the rows of the channels named in a batch are built again.
+///
+/// Avatars and icons are only downloaded for the rows in view,
+/// downloads of rows that are scrolled away before they finish are cancelled.
+pub struct ChannelListModel {
+    rows: VecModel<ChannelRow>,
+    /// Row index per channel id.
+    indices: RefCell<HashMap<String, usize>>,
+    /// The asset each rows image was loaded from, per channel id,
+    /// so images are only loaded from disk again when they changed.
+    image_keys: RefCell<HashMap<String, String>>,
+    runtime: Handle,
+    update_sender: UpdateSender,
+    visible_rows: RefCell<Range<usize>>,
+    /// Downloads of the images of visible rows, by asset key.
+    downloads: RefCell<HashMap<String, AbortHandle>>,
+}
+
+impl ChannelListModel {
+    pub fn new(runtime: Handle, update_sender: UpdateSender) -> Self {
+        Self {
+            rows: VecModel::default(),
+            indices: RefCell::new(HashMap::new()),
+            image_keys: RefCell::new(HashMap::new()),
+            runtime,
+            update_sender,
+            visible_rows: RefCell::new(0..INITIAL_VISIBLE_ROWS),
+            downloads: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// Called when the list is scrolled or resized.
+    pub fn set_visible_rows(&self, app_data: &AppData, rows: Range<usize>) {
+        if *self.visible_rows.borrow() == rows {
+            return;
+        }
+        *self.visible_rows.borrow_mut() = rows;
+        self.load_visible_images(app_data);
+    }
+
+    /// Makes the rows match the private channels in AppData.
+    pub fn sync(&self, app_data: &AppData, batch: &UpdateBatch) {
+        let channels = &app_data.private_channels;
+
+        let in_order = {
+            let indices = self.indices.borrow();
+            channels.len() == indices.len()
+                && channels
+                    .iter()
+                    .enumerate()
+                    .all(|(index, channel)| indices.get(&channel.id) == Some(&index))
+        };
+        if !in_order {
+            self.reorder(app_data, batch);
+        }
+
+        for (index, channel) in channels.iter().enumerate() {
+            if !batch.channel_list && !is_named(channel, batch) {
+                continue;
+            }
+
+            let previous = self.rows.row_data(index);
+            let row = self.build_row(channel, app_data, previous.as_ref(), batch);
+            if previous.as_ref() != Some(&row) {
+                self.rows.set_row_data(index, row);
+            }
+        }
+
+        self.load_visible_images(app_data);
+    }
+
+    /// Removes the rows of closed channels, moves rows to the position of their channel
+    /// and inserts rows for new channels.
+    fn reorder(&self, app_data: &AppData, batch: &UpdateBatch) {
+        let channels = &app_data.private_channels;
+        let channel_ids: HashSet<&str> = channels.iter().map(|v| v.id.as_str()).collect();
+
+        let mut indices = self.indices.borrow_mut();
+        let mut ids = vec![String::new(); indices.len()];
+        for (id, index) in indices.drain() {
+            ids[index] = id;
+        }
+
+        // Remove rows of channels that were closed.
+        for index in (0..ids.len()).rev() {
+            if !channel_ids.contains(ids[index].as_str()) {
+                self.rows.remove(index);
+                self.image_keys.borrow_mut().remove(&ids.remove(index));
+            }
+        }
+        indices.extend(ids.iter().cloned().enumerate().map(|(i, id)| (id, i)));
+
+        // Rows before target are in place, so an existing row is at target or below it.
+        for (target, channel) in channels.iter().enumerate() {
+            let moved = match indices.get(&channel.id).copied() {
+                Some(index) if index == target => continue,
+                Some(index) => {
+                    let row = self.rows.remove(index);
+                    self.rows.insert(target, row);
+                    let id = ids.remove(index);
+                    ids.insert(target, id);
+                    target..index + 1
+                }
+                None => {
+                    let row = self.build_row(channel, app_data, None, batch);
+                    self.rows.insert(target, row);
+                    ids.insert(target, channel.id.clone());
+                    target..ids.len()
+                }
+            };
+            for index in moved {
+                indices.insert(ids[index].clone(), index);
+            }
+        }
+    }
+
+    /// Downloads the missing images of the visible rows,
+    /// and cancels the downloads of rows that are no longer visible.
+    ///
+    /// Failed downloads are kept, so they are only retried when their row comes into view again.
+    fn load_visible_images(&self, app_data: &AppData) {
+        let visible = self.visible_rows.borrow().clone();
+        let mut wanted: HashMap<String, (Asset, UiUpdate)> = app_data
+            .private_channels
+            .iter()
+            .skip(visible.start)
+            .take(visible.len())
+            .filter_map(|channel| Some((channel.list_image_asset()?, channel.list_image_ready()?)))
+            .filter(|(asset, _)| !ASSET_CACHE.contains(asset))
+            .map(|(asset, update)| (asset.key(), (asset, update)))
+            .collect();
+
+        let mut downloads = self.downloads.borrow_mut();
+        downloads.retain(|key, download| {
+            let keep = wanted.contains_key(key);
+            if !keep {
+                download.abort();
+            }
+            keep
+        });
+        wanted.retain(|key, _| !downloads.contains_key(key));
+
+        for (key, (asset, update)) in wanted {
+            let update_sender = self.update_sender.clone();
+            let download = self.runtime.spawn(async move {
+                match ASSET_CACHE.fetch(&asset, Priority::Visible).await {
+                    Ok(_) => {
+                        let _ = update_sender.send(update);
+                    }
+                    Err(e) => warn!("Failed to download {}: {}", asset.url, e),
+                }
+            });
+            downloads.insert(key, download.abort_handle());
+        }
+    }
+
+    fn build_row(
+        &self,
+        channel: &PrivateChannel,
+        app_data: &AppData,
+        previous: Option<&ChannelRow>,
+        batch: &UpdateBatch,
+    ) -> ChannelRow {
+        let recipient = channel.recipients.first();
+
+        // Changes with the avatar or icon, and with the size it is downloaded at.
+        let image_key = channel
+            .list_image_asset()
+            .map(|asset| asset.key())
+            .unwrap_or_default();
+
+        let image_downloaded = match channel.channel_type {
+            ChannelType::Group => batch.channel_icons.contains(&channel.id),
+            ChannelType::Private => recipient.is_some_and(|user| batch.avatars.contains(&user.id)),
+        };
+
+        let (avatar, avatar_animation) = match previous {
+            Some(previous)
+                if !image_downloaded
+                    && self.image_keys.borrow().get(&channel.id) == Some(&image_key) =>
+            {
+                (previous.avatar.clone(), previous.avatar_animation.clone())
+            }
+            _ => {
+                self.image_keys
+                    .borrow_mut()
+                    .insert(channel.id.clone(), image_key);
+                (
+                    channel.load_list_image(),
+                    channel.load_list_animation().into(),
+                )
+            }
+        };
+
+        let presence = match channel.channel_type {
+            ChannelType::Group => String::new(),
+            ChannelType::Private => recipient
+                .and_then(|user| app_data.presences.get(&user.id))
+                .filter(|status| status.as_str() != "offline")
+                .cloned()
+                .unwrap_or_default(),
+        };
+
+        let last_message_preview = app_data
+            .latest_message(&channel.id)
+            .map(|message| {
+                let content = message.content.lines().next().unwrap_or_default();
+                let is_own = app_data
+                    .current_user
+                    .as_ref()
+                    .is_some_and(|user| user.id == message.author.id);
+
+                if is_own {
+                    format!("You: {}", content)
+                } else {
+                    content.to_string()
+                }
+            })
+            .unwrap_or_default();
+
+        ChannelRow {
+            id: SharedString::from(&channel.id),
+            name: SharedString::from(channel.display_name()),
+            avatar,
+            avatar_animation,
+            unread: app_data.is_unread(&channel.id, channel.last_message_id),
+            mention_count: app_data.mention_count(&channel.id) as i32,
+            presence: SharedString::from(presence),
+            last_message_preview: SharedString::from(last_message_preview),
+        }
+    }
+}
+
+/// Whether the batch changed something shown in the row of the channel.
+fn is_named(channel: &PrivateChannel, batch: &UpdateBatch) -> bool {
+    batch.messages.contains_key(&channel.id)
+        || batch.read_states.contains(&channel.id)
+        || batch.channel_icons.contains(&channel.id)
+        || channel
+            .recipients
+            .iter()
+            .any(|user| batch.avatars.contains(&user.id) || batch.presences.contains(&user.id))
+}
+
+impl Model for ChannelListModel {
+    type Data = ChannelRow;
+
+    fn row_count(&self) -> usize {
+        self.rows.row_count()
+    }
+
+    fn row_data(&self, row: usize) -> Option<Self::Data> {
+        self.rows.row_data(row)
+    }
+
+    fn model_tracker(&self) -> &dyn ModelTracker {
+        self.rows.model_tracker()
+    }
+
+    fn as_any(&self) -> &dyn core::any::Any {
+        self
+    }
+}
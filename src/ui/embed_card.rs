@@ -0,0 +1,125 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use chrono::DateTime;
+use slint::{Color, ModelRc, SharedString, VecModel};
+
+use super::{
+    fit_image::fit_image, rich_text::MarkdownRenderer, EmbedCard, EmbedField, EmbedFieldRow,
+};
+use crate::{
+    markdown::{format_timestamp::format_timestamp, parse_markdown::parse_markdown},
+    media::media_loader::MediaLoader,
+    state::Embed,
+};
+
+/// Inline fields per row.
+const MAX_INLINE_FIELDS: usize = 3;
+
+/// Bar color of embeds without a color.
+const DEFAULT_COLOR: Color = Color::from_rgb_u8(0x20, 0x22, 0x25);
+
+/// Converts an embed into the card shown below the message content.
+///
+/// Images are empty until the media loader has downloaded them, their size is known
+/// from the embed so the layout does not change when they arrive.
+pub fn render_embed(
+    embed: &Embed,
+    renderer: &mut MarkdownRenderer,
+    media_loader: &MediaLoader,
+) -> EmbedCard {
+    let is_preview = matches!(embed.kind.as_str(), "image" | "gifv");
+    let is_video = embed.kind == "video" || embed.video.is_some();
+
+    // Previews of images and videos only have a thumbnail, which is shown large.
+    let (image, thumbnail) = match (&embed.image, &embed.thumbnail) {
+        (None, Some(thumbnail)) if is_preview || is_video => (Some(thumbnail), None),
+        (image, thumbnail) => (image.as_ref(), thumbnail.as_ref()),
+    };
+
+    let (image_width, image_height) = image
+        .map(|v| fit_image(v.width, v.height))
+        .unwrap_or_default();
+
+    let mut footer = embed.footer_text.clone();
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(&embed.timestamp) {
+        let now_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|v| v.as_millis() as i64)
+            .unwrap_or_default();
+        let time = format_timestamp(timestamp.timestamp(), 'f', now_ms);
+
+        footer = if footer.is_empty() {
+            time
+        } else {
+            format!("{} • {}", footer, time)
+        };
+    }
+
+    // Image previews show nothing but the image.
+    let description = if is_preview {
+        Vec::new()
+    } else {
+        renderer.render(&parse_markdown(&embed.description))
+    };
+
+    EmbedCard {
+        color: embed
+            .color
+            .map(|v| Color::from_rgb_u8((v >> 16) as u8, (v >> 8) as u8, v as u8))
+            .unwrap_or(DEFAULT_COLOR),
+        provider: SharedString::from(&embed.provider_name),
+        author: SharedString::from(&embed.author_name),
+        author_url: SharedString::from(&embed.author_url),
+        title: if is_preview {
+            SharedString::new()
+        } else {
+            SharedString::from(&embed.title)
+        },
+        url: SharedString::from(&embed.url),
+        description: ModelRc::new(VecModel::from(description)),
+        field_rows: ModelRc::new(VecModel::from(render_fields(embed, renderer))),
+        has_thumbnail: thumbnail.is_some(),
+        thumbnail: thumbnail.map(|v| media_loader.load(v)).unwrap_or_default(),
+        thumbnail_placeholder: thumbnail
+            .map(|v| media_loader.placeholder(v))
+            .unwrap_or_default(),
+        has_image: image.is_some(),
+        image: image.map(|v| media_loader.load(v)).unwrap_or_default(),
+        image_placeholder: image
+            .map(|v| media_loader.placeholder(v))
+            .unwrap_or_default(),
+        image_width,
+        image_height,
+        video: is_video,
+        footer: SharedString::from(footer),
+    }
+}
+
+/// Groups consecutive inline fields into rows, other fields get a row of their own.
+fn render_fields(embed: &Embed, renderer: &mut MarkdownRenderer) -> Vec<EmbedFieldRow> {
+    let mut rows: Vec<Vec<EmbedField>> = Vec::new();
+    let mut previous_inline = false;
+
+    for field in &embed.fields {
+        let rendered = EmbedField {
+            name: SharedString::from(&field.name),
+            value: ModelRc::new(VecModel::from(
+                renderer.render(&parse_markdown(&field.value)),
+            )),
+        };
+
+        match rows.last_mut() {
+            Some(row) if field.inline && previous_inline && row.len() < MAX_INLINE_FIELDS => {
+                row.push(rendered)
+            }
+            _ => rows.push(vec![rendered]),
+        }
+        previous_inline = field.inline;
+    }
+
+    rows.into_iter()
+        .map(|fields| EmbedFieldRow {
+            fields: ModelRc::new(VecModel::from(fields)),
+        })
+        .collect()
+}
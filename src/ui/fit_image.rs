@@ -0,0 +1,16 @@
+/// Largest size of images in messages, in logical pixels.
+const MAX_WIDTH: f32 = 400.0;
+const MAX_HEIGHT: f32 = 300.0;
+
+/// The size an image of width x height pixels is shown at in a message,
+/// scaled down to fit and keeping its aspect ratio. Unknown sizes are shown as 16:9.
+pub fn fit_image(width: u32, height: u32) -> (f32, f32) {
+    if width == 0 || height == 0 {
+        return (MAX_WIDTH, MAX_WIDTH * 9.0 / 16.0);
+    }
+
+    let (width, height) = (width as f32, height as f32);
+    let scale = (MAX_WIDTH / width).min(MAX_HEIGHT / height).min(1.0);
+
+    (width * scale, height * scale)
+}
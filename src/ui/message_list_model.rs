@@ -0,0 +1,464 @@
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    ops::Range,
+};
+
+use chrono::{Local, TimeZone};
+use slint::{Model, ModelNotify, ModelRc, ModelTracker, SharedString, VecModel};
+use tokio::runtime::Handle;
+
+use super::{
+    attachment_row::render_attachment, embed_card::render_embed, rich_text::MarkdownRenderer,
+    MessageRow, ReactionPill, StickerRow,
+};
+use crate::{
+    assets::image_loader::ImageLoader,
+    highlight::highlighter::Highlighter,
+    markdown::parse_markdown::parse_markdown,
+    media::media_loader::MediaLoader,
+    sessions::sessions::Sessions,
+    state::{AppData, CustomEmoji, Message, MessageDelivery, UiUpdate, UpdateSender},
+};
+
+/// Messages from the same author within this window are shown under one header.
+const GROUP_WINDOW_MS: u64 = 7 * 60 * 1000;
+
+/// Identifies what a row shows, so unchanged rows are not reported to slint.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum RowKey {
+    DaySeparator(SharedString),
+    Message {
+        nonce_or_id: String,
+        id: String,
+        edited_timestamp: String,
+        delivery: MessageDelivery,
+        grouped: bool,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub(super) struct ListRow {
+    key: RowKey,
+    /// Index into the channels messages in the store.
+    message_index: usize,
+}
+
+impl ListRow {
+    /// The nonce or id of the message, None for day separators.
+    fn message_key(&self) -> Option<&str> {
+        match &self.key {
+            RowKey::Message { nonce_or_id, .. } => Some(nonce_or_id),
+            RowKey::DaySeparator(_) => None,
+        }
+    }
+}
+
+/// The rows that differ between two layouts, found by matching their keys from both ends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct RowDiff {
+    /// Rows at the start that are the same.
+    pub prefix: usize,
+    /// Rows at the end that are the same.
+    pub suffix: usize,
+    /// Old rows between them.
+    pub removed: usize,
+    /// New rows between them.
+    pub added: usize,
+}
+
+impl RowDiff {
+    pub fn new(old_rows: &[ListRow], new_rows: &[ListRow]) -> Self {
+        let prefix = old_rows
+            .iter()
+            .zip(new_rows.iter())
+            .take_while(|(old, new)| old.key == new.key)
+            .count();
+
+        let max_suffix = old_rows.len().min(new_rows.len()) - prefix;
+        let suffix = old_rows
+            .iter()
+            .rev()
+            .zip(new_rows.iter().rev())
+            .take(max_suffix)
+            .take_while(|(old, new)| old.key == new.key)
+            .count();
+
+        Self {
+            prefix,
+            suffix,
+            removed: old_rows.len() - prefix - suffix,
+            added: new_rows.len() - prefix - suffix,
+        }
+    }
+
+    /// How the list changed, old_len is the number of rows before.
+    pub fn change(&self, old_len: usize) -> ListChange {
+        // Everything except the first day separator and message was kept,
+        // so older history was added on top.
+        if self.removed == 0 && self.added == 0 {
+            ListChange::None
+        } else if self.prefix <= 1 && self.added > self.removed && self.suffix + 2 >= old_len {
+            ListChange::Prepended
+        } else {
+            ListChange::Changed
+        }
+    }
+}
+
+/// Slint model over the messages of the selected channel.
+///
+/// Only the layout (day separators and grouping) is computed for every message,
+/// a MessageRow is only built when slint asks for it, which the ListView only does for visible rows.
+/// Built rows are kept until their message changes, so scrolling back does not lock the store.
+pub struct MessageListModel {
+    /// Rows are built from the active session.
+    sessions: Sessions,
+    channel_id: RefCell<Option<String>>,
+    rows: RefCell<Vec<ListRow>>,
+    /// Index of every sent message of the channel by id, to find the author of replies.
+    message_indices: RefCell<HashMap<String, usize>>,
+    /// Built rows by the nonce or id of their message.
+    built: RefCell<HashMap<String, MessageRow>>,
+    notify: ModelNotify,
+    highlighter: Highlighter,
+    emoji_loader: ImageLoader<CustomEmoji>,
+    media_loader: MediaLoader,
+}
+
+/// How the rows changed in the last refresh.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListChange {
+    None,
+    /// A different channel, or the rows could not be matched.
+    Reset,
+    /// Rows were inserted before all existing rows.
+    Prepended,
+    Changed,
+}
+
+impl MessageListModel {
+    pub fn new(sessions: Sessions, update_sender: UpdateSender, runtime: Handle) -> Self {
+        Self {
+            highlighter: Highlighter::new(sessions.clone()),
+            emoji_loader: ImageLoader::new(
+                runtime.clone(),
+                update_sender.clone(),
+                UiUpdate::EmojiReady,
+            ),
+            media_loader: MediaLoader::new(runtime, update_sender),
+            sessions,
+            channel_id: RefCell::new(None),
+            rows: RefCell::new(Vec::new()),
+            message_indices: RefCell::new(HashMap::new()),
+            built: RefCell::new(HashMap::new()),
+            notify: ModelNotify::default(),
+        }
+    }
+
+    /// Recomputes the layout from the store, and notifies slint about the rows that changed.
+    ///
+    /// changed_messages are indices of messages that changed without changing the layout,
+    /// like new embeds, their rows are reported as changed too.
+    pub fn refresh(&self, app_data: &AppData, changed_messages: Range<usize>) -> ListChange {
+        let channel_id = app_data.selected_channel_id.clone();
+        let messages = channel_id
+            .as_ref()
+            .and_then(|id| app_data.messages.get(id))
+            .map(|messages| messages.as_slice())
+            .unwrap_or_default();
+
+        let new_rows = layout_rows(messages);
+        *self.message_indices.borrow_mut() = messages
+            .iter()
+            .enumerate()
+            .filter(|(_, message)| !message.id.is_empty())
+            .map(|(index, message)| (message.id.clone(), index))
+            .collect();
+
+        if *self.channel_id.borrow() != channel_id || self.rows.borrow().is_empty() {
+            *self.channel_id.borrow_mut() = channel_id;
+            *self.rows.borrow_mut() = new_rows;
+            self.built.borrow_mut().clear();
+            self.notify.reset();
+            return ListChange::Reset;
+        }
+
+        let old_rows = self.rows.replace(new_rows);
+        let new_rows = self.rows.borrow();
+        let diff = RowDiff::new(&old_rows, &new_rows);
+
+        {
+            let mut built = self.built.borrow_mut();
+            let kept: HashSet<&str> = new_rows.iter().filter_map(ListRow::message_key).collect();
+            built.retain(|key, _| kept.contains(key.as_str()));
+
+            for (row, list_row) in new_rows.iter().enumerate() {
+                let Some(key) = list_row.message_key() else {
+                    continue;
+                };
+                let in_changed_region = row >= diff.prefix && row < new_rows.len() - diff.suffix;
+                // Replies to older messages name their author once those are loaded.
+                let is_reply = messages
+                    .get(list_row.message_index)
+                    .is_some_and(|v| !v.referenced_message_id.is_empty());
+
+                if in_changed_region
+                    || changed_messages.contains(&list_row.message_index)
+                    || (diff.added > 0 && is_reply)
+                {
+                    built.remove(key);
+                }
+                if !in_changed_region && changed_messages.contains(&list_row.message_index) {
+                    self.notify.row_changed(row);
+                }
+            }
+        }
+
+        let changed = diff.removed.min(diff.added);
+        for row in diff.prefix..diff.prefix + changed {
+            self.notify.row_changed(row);
+        }
+        if diff.removed > changed {
+            self.notify
+                .row_removed(diff.prefix + changed, diff.removed - changed);
+        }
+        if diff.added > changed {
+            self.notify
+                .row_added(diff.prefix + changed, diff.added - changed);
+        }
+
+        diff.change(old_rows.len())
+    }
+
+    /// Rebuilds the rows of messages that use one of the downloaded custom emojis,
+    /// embed images, attachment previews or stickers.
+    pub fn assets_ready(
+        &self,
+        app_data: &AppData,
+        emoji_ids: &HashSet<String>,
+        media_urls: &HashSet<String>,
+    ) {
+        let Some(messages) = self
+            .channel_id
+            .borrow()
+            .as_ref()
+            .and_then(|id| app_data.messages.get(id))
+        else {
+            return;
+        };
+
+        for (row, list_row) in self.rows.borrow().iter().enumerate() {
+            if !matches!(list_row.key, RowKey::Message { .. }) {
+                continue;
+            }
+            let uses_asset = messages.get(list_row.message_index).is_some_and(|message| {
+                let uses_emoji = emoji_ids.iter().any(|id| {
+                    message.content.contains(&format!(":{}>", id))
+                        || message.reactions.iter().any(|v| &v.emoji.id == id)
+                });
+                let uses_media = message.embeds.iter().any(|embed| {
+                    [&embed.image, &embed.thumbnail]
+                        .into_iter()
+                        .flatten()
+                        .any(|v| media_urls.contains(&v.url))
+                }) || message
+                    .attachments
+                    .iter()
+                    .any(|v| media_urls.contains(&v.url))
+                    || message.stickers.iter().any(|v| media_urls.contains(&v.id));
+                uses_emoji || uses_media
+            });
+            if uses_asset {
+                if let Some(key) = list_row.message_key() {
+                    self.built.borrow_mut().remove(key);
+                }
+                self.notify.row_changed(row);
+            }
+        }
+    }
+}
+
+impl Model for MessageListModel {
+    type Data = MessageRow;
+
+    fn row_count(&self) -> usize {
+        self.rows.borrow().len()
+    }
+
+    fn row_data(&self, row: usize) -> Option<Self::Data> {
+        let list_row = self.rows.borrow().get(row)?.clone();
+
+        if let RowKey::DaySeparator(label) = list_row.key {
+            return Some(MessageRow {
+                day_separator: label,
+                ..Default::default()
+            });
+        }
+
+        let key = list_row.message_key()?;
+        if let Some(built) = self.built.borrow().get(key) {
+            return Some(built.clone());
+        }
+
+        let channel_id = self.channel_id.borrow().clone()?;
+        let app_state = self.sessions.active().app_state;
+        let app_data = app_state.blocking_read();
+        let messages = app_data.messages.get(&channel_id)?;
+        let message = messages.get(list_row.message_index)?;
+
+        let reply_to_author = if message.referenced_message_id.is_empty() {
+            SharedString::new()
+        } else {
+            self.message_indices
+                .borrow()
+                .get(&message.referenced_message_id)
+                .and_then(|&index| messages.get(index))
+                .map(|v| SharedString::from(v.author.display_name()))
+                .unwrap_or_else(|| SharedString::from("unknown message"))
+        };
+
+        let mut renderer =
+            MarkdownRenderer::new(message, &app_data, &self.highlighter, &self.emoji_loader);
+        let blocks = renderer.render_content(&parse_markdown(&message.content));
+        let embeds: Vec<_> = message
+            .embeds
+            .iter()
+            .map(|embed| render_embed(embed, &mut renderer, &self.media_loader))
+            .collect();
+        let attachments: Vec<_> = message
+            .attachments
+            .iter()
+            .map(|attachment| {
+                let upload_progress = app_data
+                    .upload_progress
+                    .get(&attachment.id)
+                    .map(|&sent| sent as f32 / attachment.size.max(1) as f32);
+                render_attachment(attachment, &self.media_loader, upload_progress)
+            })
+            .collect();
+        let stickers: Vec<_> = message
+            .stickers
+            .iter()
+            .map(|sticker| StickerRow {
+                name: SharedString::from(&sticker.name),
+                image: self.media_loader.load_sticker(sticker),
+                animation: sticker.load_animation().into(),
+            })
+            .collect();
+
+        let message_row = MessageRow {
+            id: SharedString::from(&message.id),
+            nonce: SharedString::from(&message.nonce),
+            author: SharedString::from(message.author.display_name()),
+            time: SharedString::from(format_time(message.created_at_ms())),
+            blocks: ModelRc::new(VecModel::from(blocks)),
+            embeds: ModelRc::new(VecModel::from(embeds)),
+            attachments: ModelRc::new(VecModel::from(attachments)),
+            stickers: ModelRc::new(VecModel::from(stickers)),
+            reply_to_author,
+            day_separator: SharedString::new(),
+            grouped: matches!(list_row.key, RowKey::Message { grouped: true, .. }),
+            edited: !message.edited_timestamp.is_empty(),
+            pending: message.delivery == MessageDelivery::Pending,
+            failed: message.delivery == MessageDelivery::Failed,
+            reactions: ModelRc::new(VecModel::from(
+                message
+                    .reactions
+                    .iter()
+                    .map(|reaction| ReactionPill {
+                        text: if reaction.emoji.id.is_empty() {
+                            SharedString::from(&reaction.emoji.name)
+                        } else {
+                            SharedString::from(format!(":{}:", reaction.emoji.name))
+                        },
+                        emoji: if reaction.emoji.id.is_empty() {
+                            Default::default()
+                        } else {
+                            self.emoji_loader.load(&reaction.emoji)
+                        },
+                        emoji_animation: reaction.emoji.load_animation().into(),
+                        key: SharedString::from(reaction.emoji.reaction_key()),
+                        count: reaction.count as i32,
+                        me: reaction.me,
+                    })
+                    .collect::<Vec<_>>(),
+            )),
+        };
+
+        self.built
+            .borrow_mut()
+            .insert(key.to_string(), message_row.clone());
+        Some(message_row)
+    }
+
+    fn model_tracker(&self) -> &dyn ModelTracker {
+        &self.notify
+    }
+
+    fn as_any(&self) -> &dyn core::any::Any {
+        self
+    }
+}
+
+/// Inserts a day separator before the first message of every day,
+/// and groups consecutive messages from the same author.
+pub(super) fn layout_rows(messages: &[Message]) -> Vec<ListRow> {
+    let mut rows = Vec::with_capacity(messages.len() + 1);
+    let mut previous: Option<&Message> = None;
+
+    for (index, message) in messages.iter().enumerate() {
+        let created_at = message.created_at_ms();
+        let day = format_day(created_at);
+
+        let new_day = previous.is_none_or(|v| format_day(v.created_at_ms()) != day);
+        if new_day {
+            rows.push(ListRow {
+                key: RowKey::DaySeparator(SharedString::from(day)),
+                message_index: index,
+            });
+        }
+
+        let grouped = !new_day
+            && message.referenced_message_id.is_empty()
+            && previous.is_some_and(|v| {
+                v.author.id == message.author.id
+                    && created_at.saturating_sub(v.created_at_ms()) < GROUP_WINDOW_MS
+            });
+
+        rows.push(ListRow {
+            key: RowKey::Message {
+                nonce_or_id: if message.nonce.is_empty() {
+                    message.id.clone()
+                } else {
+                    message.nonce.clone()
+                },
+                id: message.id.clone(),
+                edited_timestamp: message.edited_timestamp.clone(),
+                delivery: message.delivery.clone(),
+                grouped,
+            },
+            message_index: index,
+        });
+
+        previous = Some(message);
+    }
+
+    rows
+}
+
+fn format_day(timestamp_ms: u64) -> String {
+    Local
+        .timestamp_millis_opt(timestamp_ms as i64)
+        .single()
+        .map(|v| v.format("%B %-d, %Y").to_string())
+        .unwrap_or_default()
+}
+
+fn format_time(timestamp_ms: u64) -> String {
+    Local
+        .timestamp_millis_opt(timestamp_ms as i64)
+        .single()
+        .map(|v| v.format("%H:%M").to_string())
+        .unwrap_or_default()
+}
@@ -0,0 +1,436 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use slint::{Image, ModelRc, SharedString, VecModel};
+
+use super::{CodeLine, CodeSpan, RichBlock, RichSpan};
+use crate::{
+    assets::image_loader::ImageLoader,
+    emoji::shortcodes::count_emojis,
+    highlight::highlighter::Highlighter,
+    markdown::{
+        ast::{Block, Inline},
+        format_timestamp::format_timestamp,
+    },
+    state::{AppData, CustomEmoji, Message},
+};
+
+/// Messages with up to this many emojis and nothing else show them large.
+const MAX_JUMBO_EMOJIS: usize = 27;
+
+/// Style inherited by the spans of nested inlines.
+#[derive(Debug, Clone, Default)]
+struct SpanStyle {
+    bold: bool,
+    italic: bool,
+    underline: bool,
+    strikethrough: bool,
+    spoiler: bool,
+    url: SharedString,
+}
+
+/// Flattens parsed markdown of a message into the lines of styled spans shown by RichText.
+///
+/// Mentions are resolved to names from the message and the app state.
+/// Code blocks are shown without colors until the highlighter is done with them,
+/// custom emojis are empty until they are downloaded.
+pub struct MarkdownRenderer<'a> {
+    message: &'a Message,
+    app_data: &'a AppData,
+    highlighter: &'a Highlighter,
+    emoji_loader: &'a ImageLoader<CustomEmoji>,
+    /// The message only contains emojis, which are shown large.
+    jumbo: bool,
+    /// Number of code blocks rendered so far.
+    code_blocks: usize,
+    now_ms: i64,
+    rows: Vec<RichBlock>,
+}
+
+impl<'a> MarkdownRenderer<'a> {
+    pub fn new(
+        message: &'a Message,
+        app_data: &'a AppData,
+        highlighter: &'a Highlighter,
+        emoji_loader: &'a ImageLoader<CustomEmoji>,
+    ) -> Self {
+        Self {
+            message,
+            app_data,
+            highlighter,
+            emoji_loader,
+            jumbo: false,
+            code_blocks: 0,
+            now_ms: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|v| v.as_millis() as i64)
+                .unwrap_or_default(),
+            rows: Vec::new(),
+        }
+    }
+
+    /// The content of the message, shown large if it only contains emojis.
+    pub fn render_content(&mut self, blocks: &[Block]) -> Vec<RichBlock> {
+        self.jumbo = is_emoji_only(blocks);
+        let rows = self.render(blocks);
+        self.jumbo = false;
+        rows
+    }
+
+    /// Markdown in embeds, code blocks are counted on from the content
+    /// so the highlighter keeps them apart.
+    pub fn render(&mut self, blocks: &[Block]) -> Vec<RichBlock> {
+        self.render_blocks(blocks, false, 0);
+        std::mem::take(&mut self.rows)
+    }
+
+    fn render_blocks(&mut self, blocks: &[Block], quoted: bool, indent: i32) {
+        for block in blocks {
+            match block {
+                Block::Paragraph(content) => {
+                    self.render_lines(content, "text", 0, quoted, indent, String::new())
+                }
+                Block::Heading { level, content } => self.render_lines(
+                    content,
+                    "heading",
+                    *level as i32,
+                    quoted,
+                    indent,
+                    String::new(),
+                ),
+                Block::Code { language, code } => {
+                    let code_lines = self.render_code(language, code);
+                    self.rows.push(RichBlock {
+                        kind: SharedString::from("code"),
+                        quoted,
+                        indent,
+                        language: SharedString::from(language),
+                        code: SharedString::from(code),
+                        code_lines: ModelRc::new(VecModel::from(code_lines)),
+                        ..Default::default()
+                    })
+                }
+                Block::Quote(blocks) => self.render_blocks(blocks, true, indent),
+                Block::List { start, items } => {
+                    for (index, item) in items.iter().enumerate() {
+                        let marker = match start {
+                            Some(start) => format!("{}.", start + index as u64),
+                            None => "•".to_string(),
+                        };
+
+                        // The first paragraph of an item is shown next to the marker.
+                        match item.split_first() {
+                            Some((Block::Paragraph(content), rest)) => {
+                                self.render_lines(content, "text", 0, quoted, indent + 1, marker);
+                                self.render_blocks(rest, quoted, indent + 1);
+                            }
+                            _ => self.render_blocks(item, quoted, indent + 1),
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn render_code(&mut self, language: &str, code: &str) -> Vec<CodeLine> {
+        let message_key = if self.message.nonce.is_empty() {
+            &self.message.id
+        } else {
+            &self.message.nonce
+        };
+
+        let highlighted = self.highlighter.get(
+            self.app_data.session_id,
+            &self.message.channel_id,
+            message_key,
+            self.code_blocks,
+            language,
+            code,
+        );
+        self.code_blocks += 1;
+
+        let code_line = |spans: Vec<CodeSpan>| CodeLine {
+            spans: ModelRc::new(VecModel::from(spans)),
+        };
+
+        match highlighted {
+            Some(lines) => lines
+                .iter()
+                .map(|tokens| {
+                    code_line(
+                        tokens
+                            .iter()
+                            .map(|token| CodeSpan {
+                                text: SharedString::from(&token.text),
+                                kind: SharedString::from(token.kind.name()),
+                            })
+                            .collect(),
+                    )
+                })
+                .collect(),
+            None => code
+                .split('\n')
+                .map(|line| {
+                    code_line(vec![CodeSpan {
+                        text: SharedString::from(line),
+                        kind: SharedString::from("plain"),
+                    }])
+                })
+                .collect(),
+        }
+    }
+
+    /// Adds a row per line of the inlines, only the first line gets the list marker.
+    fn render_lines(
+        &mut self,
+        content: &[Inline],
+        kind: &str,
+        level: i32,
+        quoted: bool,
+        indent: i32,
+        marker: String,
+    ) {
+        let mut lines = vec![Vec::new()];
+        self.render_inlines(content, &SpanStyle::default(), &mut lines);
+
+        for (index, spans) in lines.into_iter().enumerate() {
+            self.rows.push(RichBlock {
+                kind: SharedString::from(kind),
+                level,
+                quoted,
+                indent,
+                jumbo: self.jumbo,
+                marker: if index == 0 {
+                    SharedString::from(&marker)
+                } else {
+                    SharedString::new()
+                },
+                spans: ModelRc::new(VecModel::from(spans)),
+                ..Default::default()
+            });
+        }
+    }
+
+    fn render_inlines(
+        &self,
+        inlines: &[Inline],
+        style: &SpanStyle,
+        lines: &mut Vec<Vec<RichSpan>>,
+    ) {
+        for inline in inlines {
+            match inline {
+                Inline::Text(text) => push_span(lines, style, text, false, false),
+                Inline::LineBreak => lines.push(Vec::new()),
+                Inline::Bold(content) => {
+                    let style = SpanStyle {
+                        bold: true,
+                        ..style.clone()
+                    };
+                    self.render_inlines(content, &style, lines);
+                }
+                Inline::Italic(content) => {
+                    let style = SpanStyle {
+                        italic: true,
+                        ..style.clone()
+                    };
+                    self.render_inlines(content, &style, lines);
+                }
+                Inline::Underline(content) => {
+                    let style = SpanStyle {
+                        underline: true,
+                        ..style.clone()
+                    };
+                    self.render_inlines(content, &style, lines);
+                }
+                Inline::Strikethrough(content) => {
+                    let style = SpanStyle {
+                        strikethrough: true,
+                        ..style.clone()
+                    };
+                    self.render_inlines(content, &style, lines);
+                }
+                Inline::Spoiler(content) => {
+                    let style = SpanStyle {
+                        spoiler: true,
+                        ..style.clone()
+                    };
+                    self.render_inlines(content, &style, lines);
+                }
+                Inline::Link { content, url } => {
+                    let style = SpanStyle {
+                        url: SharedString::from(url),
+                        ..style.clone()
+                    };
+                    self.render_inlines(content, &style, lines);
+                }
+                Inline::Code(code) => push_span(lines, style, code, true, false),
+                Inline::UserMention(id) => push_span(
+                    lines,
+                    style,
+                    &format!("@{}", self.user_name(id)),
+                    false,
+                    true,
+                ),
+                Inline::ChannelMention(id) => push_span(
+                    lines,
+                    style,
+                    &format!("#{}", self.channel_name(id)),
+                    false,
+                    true,
+                ),
+                Inline::RoleMention(id) => push_span(
+                    lines,
+                    style,
+                    &format!("@{}", self.role_name(id)),
+                    false,
+                    true,
+                ),
+                Inline::CustomEmoji { name, id, animated } => {
+                    let emoji = CustomEmoji {
+                        id: id.clone(),
+                        name: name.clone(),
+                        animated: *animated,
+                    };
+                    push_emoji(lines, style, &emoji, self.emoji_loader.load(&emoji));
+                }
+                Inline::Emoji(emoji) => push_span(lines, style, emoji, false, false),
+                Inline::Timestamp {
+                    unix,
+                    style: format,
+                } => push_span(
+                    lines,
+                    style,
+                    &format_timestamp(*unix, *format, self.now_ms),
+                    true,
+                    false,
+                ),
+            }
+        }
+    }
+
+    fn user_name(&self, id: &str) -> String {
+        let app_data = self.app_data;
+
+        self.message
+            .mentions
+            .iter()
+            .chain(&app_data.current_user)
+            .chain(app_data.private_channels.iter().flat_map(|v| &v.recipients))
+            .find(|user| user.id == id)
+            .map(|user| user.display_name().to_string())
+            .unwrap_or_else(|| "unknown-user".to_string())
+    }
+
+    fn channel_name(&self, id: &str) -> String {
+        let app_data = self.app_data;
+
+        app_data
+            .private_channels
+            .iter()
+            .find(|v| v.id == id)
+            .map(|v| v.display_name().to_string())
+            .or_else(|| {
+                app_data
+                    .guilds
+                    .iter()
+                    .find_map(|v| v.channel_names.get(id).cloned())
+            })
+            .unwrap_or_else(|| "unknown-channel".to_string())
+    }
+
+    fn role_name(&self, id: &str) -> String {
+        self.app_data
+            .guilds
+            .iter()
+            .find_map(|v| v.role_names.get(id).cloned())
+            .unwrap_or_else(|| "unknown-role".to_string())
+    }
+}
+
+/// Adds text to the current line, merged into the previous span if it has the same style.
+fn push_span(
+    lines: &mut [Vec<RichSpan>],
+    style: &SpanStyle,
+    text: &str,
+    code: bool,
+    mention: bool,
+) {
+    let Some(spans) = lines.last_mut() else {
+        return;
+    };
+
+    let span = RichSpan {
+        text: SharedString::from(text),
+        bold: style.bold,
+        italic: style.italic,
+        underline: style.underline,
+        strikethrough: style.strikethrough,
+        spoiler: style.spoiler,
+        url: style.url.clone(),
+        code,
+        mention,
+        ..Default::default()
+    };
+
+    match spans.last_mut() {
+        // Code and mentions keep their own background.
+        Some(last) if !code && !mention && has_same_style(last, &span) => {
+            last.text = SharedString::from(format!("{}{}", last.text, text));
+        }
+        _ => spans.push(span),
+    }
+}
+
+/// Adds a custom emoji to the current line, its name is shown until the image is downloaded.
+fn push_emoji(lines: &mut [Vec<RichSpan>], style: &SpanStyle, emoji: &CustomEmoji, image: Image) {
+    let Some(spans) = lines.last_mut() else {
+        return;
+    };
+
+    spans.push(RichSpan {
+        text: SharedString::from(format!(":{}:", emoji.name)),
+        spoiler: style.spoiler,
+        url: style.url.clone(),
+        emoji: image,
+        emoji_id: SharedString::from(&emoji.id),
+        emoji_animation: emoji.load_animation().into(),
+        ..Default::default()
+    });
+}
+
+/// Whether the content is only emojis and whitespace, and few enough to be shown large.
+fn is_emoji_only(blocks: &[Block]) -> bool {
+    let mut count = 0;
+
+    for block in blocks {
+        let Block::Paragraph(content) = block else {
+            return false;
+        };
+
+        for inline in content {
+            match inline {
+                Inline::CustomEmoji { .. } | Inline::Emoji(_) => count += 1,
+                Inline::LineBreak => {}
+                Inline::Text(text) => match count_emojis(text) {
+                    Some(emojis) => count += emojis,
+                    None => return false,
+                },
+                _ => return false,
+            }
+        }
+    }
+
+    (1..=MAX_JUMBO_EMOJIS).contains(&count)
+}
+
+fn has_same_style(a: &RichSpan, b: &RichSpan) -> bool {
+    a.emoji_id.is_empty()
+        && b.emoji_id.is_empty()
+        && a.bold == b.bold
+        && a.italic == b.italic
+        && a.underline == b.underline
+        && a.strikethrough == b.strikethrough
+        && a.spoiler == b.spoiler
+        && a.code == b.code
+        && a.mention == b.mention
+        && a.url == b.url
+}
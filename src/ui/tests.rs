@@ -0,0 +1,141 @@
+use super::{
+    message_list_model::{layout_rows, ListChange, RowDiff},
+    update_batch::UpdateBatch,
+};
+use crate::{
+    state::{Message, MessageDelivery, UiUpdate, User},
+    utils::snowflake::DISCORD_EPOCH_MS,
+};
+
+fn add_all(updates: Vec<UiUpdate>) -> UpdateBatch {
+    let mut batch = UpdateBatch::default();
+    for update in updates {
+        batch.add(update);
+    }
+    batch
+}
+
+#[test]
+fn merges_changes_after_a_prepend() {
+    // The changed indices are counted after the prepended messages.
+    let batch = add_all(vec![
+        UiUpdate::MessagesPrepended("1".to_string(), 5),
+        UiUpdate::MessagesChanged("1".to_string(), 7..8),
+    ]);
+    assert_eq!(batch.messages.get("1"), Some(&(0..8)));
+}
+
+#[test]
+fn moves_changes_before_a_prepend() {
+    // Message 2 is message 7 once 5 messages are prepended.
+    let batch = add_all(vec![
+        UiUpdate::MessagesChanged("1".to_string(), 2..3),
+        UiUpdate::MessagesPrepended("1".to_string(), 5),
+    ]);
+    assert_eq!(batch.messages.get("1"), Some(&(0..8)));
+
+    let batch = add_all(vec![
+        UiUpdate::MessagesChanged("1".to_string(), 2..3),
+        UiUpdate::MessagesPrepended("2".to_string(), 5),
+    ]);
+    assert_eq!(batch.messages.get("1"), Some(&(2..3)));
+    assert_eq!(batch.messages.get("2"), Some(&(0..5)));
+}
+
+#[test]
+fn merges_duplicate_media() {
+    let batch = add_all(vec![
+        UiUpdate::MediaReady("https://media.example/a.png".to_string()),
+        UiUpdate::MediaReady("https://media.example/a.png".to_string()),
+        UiUpdate::MediaReady("https://media.example/b.png".to_string()),
+    ]);
+    assert_eq!(batch.media.len(), 2);
+    assert!(batch.media.contains("https://media.example/a.png"));
+    assert!(batch.media.contains("https://media.example/b.png"));
+}
+
+/// 2025-01-10 12:00 UTC, far enough from midnight that every time zone shows one day.
+const NOON_MS: u64 = 1_736_510_400_000;
+const DAY: u64 = 24 * 60;
+
+/// A sent message, the minutes are counted from noon of the first day.
+fn message(minutes: u64, author: &str) -> Message {
+    let id = (NOON_MS + minutes * 60 * 1000 - DISCORD_EPOCH_MS) << 22;
+    Message {
+        id: id.to_string(),
+        channel_id: "1".to_string(),
+        author: User {
+            id: author.to_string(),
+            username: author.to_string(),
+            ..Default::default()
+        },
+        content: String::new(),
+        timestamp: String::new(),
+        edited_timestamp: String::new(),
+        nonce: String::new(),
+        referenced_message_id: String::new(),
+        flags: 0,
+        mentions: Vec::new(),
+        delivery: MessageDelivery::Sent,
+        reactions: Vec::new(),
+        embeds: Vec::new(),
+        attachments: Vec::new(),
+        stickers: Vec::new(),
+    }
+}
+
+fn list_change(old: &[Message], new: &[Message]) -> ListChange {
+    let old_rows = layout_rows(old);
+    RowDiff::new(&old_rows, &layout_rows(new)).change(old_rows.len())
+}
+
+#[test]
+fn detects_history_prepended_on_an_earlier_day() {
+    let old = vec![message(DAY, "alice"), message(DAY + 1, "bob")];
+    let new = [vec![message(0, "carol"), message(1, "dave")], old.clone()].concat();
+
+    assert_eq!(list_change(&old, &new), ListChange::Prepended);
+}
+
+#[test]
+fn detects_history_prepended_on_the_same_day() {
+    let old = vec![message(10, "alice"), message(11, "bob")];
+
+    let new = [vec![message(5, "carol")], old.clone()].concat();
+    assert_eq!(list_change(&old, &new), ListChange::Prepended);
+
+    // The first old message is grouped under the prepended one, so its row changes too.
+    let new = [vec![message(5, "alice")], old.clone()].concat();
+    assert_eq!(list_change(&old, &new), ListChange::Prepended);
+}
+
+#[test]
+fn detects_history_prepended_before_a_single_message() {
+    let old = vec![message(DAY, "alice")];
+    let new = [vec![message(0, "bob")], old.clone()].concat();
+
+    assert_eq!(list_change(&old, &new), ListChange::Prepended);
+}
+
+#[test]
+fn appended_edited_and_deleted_messages_are_changes() {
+    let old = vec![message(0, "alice"), message(1, "bob")];
+
+    assert_eq!(list_change(&old, &old), ListChange::None);
+
+    let appended = [old.clone(), vec![message(2, "carol")]].concat();
+    assert_eq!(list_change(&old, &appended), ListChange::Changed);
+
+    let mut edited = old.clone();
+    edited[0].edited_timestamp = "2025-01-10T12:05:00+00:00".to_string();
+    assert_eq!(list_change(&old, &edited), ListChange::Changed);
+
+    assert_eq!(list_change(&old, &old[1..]), ListChange::Changed);
+
+    // A single message is replaced, nothing was added on top.
+    let single = vec![message(0, "alice")];
+    assert_eq!(
+        list_change(&single, &[message(1, "bob")]),
+        ListChange::Changed
+    );
+}
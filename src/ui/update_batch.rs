@@ -0,0 +1,120 @@
+use std::{
+    collections::{HashMap, HashSet},
+    ops::Range,
+};
+
+use crate::state::UiUpdate;
+
+/// UiUpdates received within one frame, merged so each part of the UI is updated at most once.
+#[derive(Debug, Default)]
+pub struct UpdateBatch {
+    pub current_user: bool,
+    pub channel_list: bool,
+    pub guild_list: bool,
+    pub selected_channel: bool,
+    pub reaction_users: bool,
+    pub downloads: bool,
+    pub image_viewer: bool,
+    pub composer_files: bool,
+    pub config: bool,
+    pub login: bool,
+    /// The account switcher.
+    pub sessions: bool,
+    /// A new message should ask for attention.
+    pub notify: bool,
+    /// User ids with a downloaded avatar.
+    pub avatars: HashSet<String>,
+    /// Channel ids with a downloaded icon.
+    pub channel_icons: HashSet<String>,
+    /// Guild ids with a downloaded icon.
+    pub guild_icons: HashSet<String>,
+    /// Ids of downloaded custom emojis.
+    pub emojis: HashSet<String>,
+    /// Urls of downloaded embed images.
+    pub media: HashSet<String>,
+    /// Channel ids with a changed read state.
+    pub read_states: HashSet<String>,
+    /// User ids with a changed status.
+    pub presences: HashSet<String>,
+    /// Changed message indices per channel id, merged into one range.
+    /// Prepended messages are included, and move the indices of the changes before them.
+    pub messages: HashMap<String, Range<usize>>,
+}
+
+impl UpdateBatch {
+    /// Updates everything, used when the window is created and when another account is shown.
+    pub fn everything() -> Self {
+        Self {
+            current_user: true,
+            channel_list: true,
+            guild_list: true,
+            selected_channel: true,
+            reaction_users: true,
+            downloads: true,
+            image_viewer: true,
+            composer_files: true,
+            login: true,
+            sessions: true,
+            ..Default::default()
+        }
+    }
+
+    pub fn add(&mut self, update: UiUpdate) {
+        match update {
+            UiUpdate::CurrentUserChanged => self.current_user = true,
+            UiUpdate::ChannelListChanged => self.channel_list = true,
+            UiUpdate::GuildListChanged => self.guild_list = true,
+            UiUpdate::SelectedChannelChanged => self.selected_channel = true,
+            UiUpdate::ReactionUsersChanged => self.reaction_users = true,
+            UiUpdate::DownloadsChanged => self.downloads = true,
+            UiUpdate::ImageViewerChanged => self.image_viewer = true,
+            UiUpdate::ComposerFilesChanged => self.composer_files = true,
+            UiUpdate::ConfigChanged => self.config = true,
+            UiUpdate::LoginChanged => self.login = true,
+            UiUpdate::Notify(_) => self.notify = true,
+            UiUpdate::SessionsChanged => self.sessions = true,
+            UiUpdate::SessionSwitched => {
+                *self = Self {
+                    config: self.config,
+                    notify: self.notify,
+                    ..Self::everything()
+                }
+            }
+            UiUpdate::ReadStateChanged(channel_id) => {
+                self.read_states.insert(channel_id);
+            }
+            UiUpdate::AvatarReady(user_id) => {
+                self.avatars.insert(user_id);
+            }
+            UiUpdate::ChannelIconReady(channel_id) => {
+                self.channel_icons.insert(channel_id);
+            }
+            UiUpdate::GuildIconReady(guild_id) => {
+                self.guild_icons.insert(guild_id);
+            }
+            UiUpdate::EmojiReady(emoji_id) => {
+                self.emojis.insert(emoji_id);
+            }
+            UiUpdate::MediaReady(url) => {
+                self.media.insert(url);
+            }
+            UiUpdate::PresenceChanged(user_id) => {
+                self.presences.insert(user_id);
+            }
+            UiUpdate::MessagesChanged(channel_id, changed) => {
+                self.messages
+                    .entry(channel_id)
+                    .and_modify(|range| {
+                        *range = range.start.min(changed.start)..range.end.max(changed.end)
+                    })
+                    .or_insert(changed);
+            }
+            UiUpdate::MessagesPrepended(channel_id, count) => {
+                self.messages
+                    .entry(channel_id)
+                    .and_modify(|range| *range = 0..range.end + count)
+                    .or_insert(0..count);
+            }
+        }
+    }
+}
@@ -0,0 +1,171 @@
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+};
+
+use once_cell::sync::OnceCell;
+use tracing::{info, warn};
+
+/// Name of the folder in each of the base directories.
+const APP_NAME: &str = "blazingly-fast-discord-client";
+
+static APP_DIRS: OnceCell<AppDirs> = OnceCell::new();
+
+/// Where the client keeps its files, following the XDG base directory specification.
+///
+/// With --profile-dir all three are folders in that directory instead,
+/// so several profiles can be kept apart.
+#[derive(Debug, Clone)]
+pub struct AppDirs {
+    /// Settings written by the user, $XDG_CONFIG_HOME/<app>.
+    pub config: PathBuf,
+    /// Files that can be downloaded again, $XDG_CACHE_HOME/<app>.
+    pub cache: PathBuf,
+    /// Files that should survive, like drafts, $XDG_DATA_HOME/<app>.
+    pub data: PathBuf,
+}
+
+impl AppDirs {
+    fn new(profile_dir: Option<PathBuf>) -> Self {
+        match profile_dir {
+            Some(profile_dir) => Self {
+                config: profile_dir.join("config"),
+                cache: profile_dir.join("cache"),
+                data: profile_dir.join("data"),
+            },
+            None => Self {
+                config: base_dir("XDG_CONFIG_HOME", ".config").join(APP_NAME),
+                cache: base_dir("XDG_CACHE_HOME", ".cache").join(APP_NAME),
+                data: base_dir("XDG_DATA_HOME", ".local/share").join(APP_NAME),
+            },
+        }
+    }
+
+    pub fn assets(&self) -> PathBuf {
+        self.cache.join("assets")
+    }
+}
+
+/// Chooses the directories from --profile-dir or the environment.
+///
+/// Called once at startup, before anything reads or writes files.
+pub fn init_app_dirs(args: impl Iterator<Item = String>) {
+    APP_DIRS.get_or_init(|| AppDirs::new(profile_dir_arg(args)));
+}
+
+/// Moves files that older versions wrote to the working directory into the directories.
+///
+/// Called after logging is set up, before the asset cache is opened and drafts are loaded.
+pub fn migrate_old_files() {
+    let dirs = app_dirs();
+
+    // Only the asset cache, not some other assets folder where the client was started.
+    // Its index moves along, so the asset cache keeps the files.
+    if Path::new("assets/index.json").exists() {
+        migrate(Path::new("assets"), &dirs.assets());
+    }
+    migrate(
+        Path::new("data/drafts.json"),
+        &dirs.data.join("drafts.json"),
+    );
+}
+
+/// The directories chosen by init_app_dirs.
+pub fn app_dirs() -> &'static AppDirs {
+    APP_DIRS.get_or_init(|| AppDirs::new(None))
+}
+
+/// --profile-dir <path> or --profile-dir=<path>.
+fn profile_dir_arg(mut args: impl Iterator<Item = String>) -> Option<PathBuf> {
+    while let Some(arg) = args.next() {
+        if arg == "--profile-dir" {
+            return args.next().map(PathBuf::from);
+        }
+        if let Some(path) = arg.strip_prefix("--profile-dir=") {
+            return Some(PathBuf::from(path));
+        }
+    }
+    None
+}
+
+/// The XDG variable if it is set to an absolute path, otherwise the platforms default.
+fn base_dir(variable: &str, home_default: &str) -> PathBuf {
+    if let Some(dir) = env::var_os(variable).map(PathBuf::from) {
+        if dir.is_absolute() {
+            return dir;
+        }
+    }
+
+    if cfg!(windows) {
+        let variable = if variable == "XDG_CACHE_HOME" {
+            "LOCALAPPDATA"
+        } else {
+            "APPDATA"
+        };
+        if let Some(dir) = env::var_os(variable) {
+            return PathBuf::from(dir);
+        }
+    }
+
+    let Some(home) = env::var_os("HOME").map(PathBuf::from) else {
+        return PathBuf::from(".");
+    };
+
+    if cfg!(target_os = "macos") {
+        return if variable == "XDG_CACHE_HOME" {
+            home.join("Library/Caches")
+        } else {
+            home.join("Library/Application Support")
+        };
+    }
+    home.join(home_default)
+}
+
+/// Moves a file or folder from an older version to its new place, unless that already exists.
+fn migrate(old: &Path, new: &Path) {
+    if !old.exists() || new.exists() {
+        return;
+    }
+
+    let result = new
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|_| move_path(old, new));
+
+    match result {
+        Ok(()) => info!("Moved {} to {}", old.display(), new.display()),
+        Err(e) => warn!(
+            "Failed to move {} to {}: {}",
+            old.display(),
+            new.display(),
+            e
+        ),
+    }
+}
+
+/// Renames, or copies and deletes when the new place is on another file system.
+fn move_path(old: &Path, new: &Path) -> io::Result<()> {
+    if fs::rename(old, new).is_ok() {
+        return Ok(());
+    }
+
+    copy_recursive(old, new)?;
+    if old.is_dir() {
+        fs::remove_dir_all(old)
+    } else {
+        fs::remove_file(old)
+    }
+}
+
+fn copy_recursive(from: &Path, to: &Path) -> io::Result<()> {
+    if !from.is_dir() {
+        return fs::copy(from, to).map(|_| ());
+    }
+
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        copy_recursive(&entry.path(), &to.join(entry.file_name()))?;
+    }
+    Ok(())
+}
@@ -1 +1,6 @@
-pub mod save_pretty_json;
\ No newline at end of file
+pub mod app_dirs;
+pub mod format_size;
+pub mod open_url;
+pub mod pick_files;
+pub mod save_pretty_json;
+pub mod snowflake;
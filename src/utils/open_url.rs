@@ -0,0 +1,22 @@
+use std::process::Command;
+
+/// Opens a url in the default browser.
+pub fn open_url(url: &str) -> std::io::Result<()> {
+    #[cfg(target_os = "windows")]
+    let mut command = {
+        let mut command = Command::new("cmd");
+        command.args(["/C", "start", ""]);
+        command
+    };
+    #[cfg(target_os = "macos")]
+    let mut command = Command::new("open");
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let mut command = Command::new("xdg-open");
+
+    let mut child = command.arg(url).spawn()?;
+
+    // Reap the opener once it exits.
+    std::thread::spawn(move || child.wait());
+
+    Ok(())
+}
@@ -0,0 +1,55 @@
+use std::{io, path::PathBuf, process::Command};
+
+/// Asks the user to choose files with the file dialog of the desktop.
+///
+/// Blocks until the dialog is closed, returns no files if it was cancelled.
+pub fn pick_files() -> io::Result<Vec<PathBuf>> {
+    #[cfg(target_os = "windows")]
+    let output = Command::new("powershell")
+        .args([
+            "-NoProfile",
+            "-Command",
+            "Add-Type -AssemblyName System.Windows.Forms; \
+             $dialog = New-Object System.Windows.Forms.OpenFileDialog; \
+             $dialog.Multiselect = $true; \
+             if ($dialog.ShowDialog() -eq 'OK') { $dialog.FileNames -join \"`n\" }",
+        ])
+        .output()?;
+    #[cfg(target_os = "macos")]
+    let output = Command::new("osascript")
+        .args([
+            "-e",
+            "set paths to \"\"",
+            "-e",
+            "repeat with f in (choose file with multiple selections allowed)",
+            "-e",
+            "set paths to paths & POSIX path of f & linefeed",
+            "-e",
+            "end repeat",
+            "-e",
+            "return paths",
+        ])
+        .output()?;
+    // zenity on GNOME and most other desktops, kdialog on KDE.
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let output = match Command::new("zenity")
+        .args(["--file-selection", "--multiple", "--separator=\n"])
+        .output()
+    {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Command::new("kdialog")
+            .args(["--getopenfilename", ".", "--multiple", "--separate-output"])
+            .output()?,
+        output => output?,
+    };
+
+    if !output.status.success() {
+        return Ok(Vec::new());
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|v| v.trim_end_matches('\r'))
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .collect())
+}
@@ -0,0 +1,8 @@
+/// First second of 2015, the epoch discord snowflakes are counted from.
+pub const DISCORD_EPOCH_MS: u64 = 1_420_070_400_000;
+
+/// Unix timestamp in milliseconds of when a snowflake was created.
+pub fn snowflake_timestamp_ms(snowflake: &str) -> Option<u64> {
+    let snowflake = snowflake.parse::<u64>().ok()?;
+    Some((snowflake >> 22) + DISCORD_EPOCH_MS)
+}
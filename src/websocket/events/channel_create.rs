@@ -0,0 +1,38 @@
+use serde_json::Value;
+
+use crate::{
+    state::{AppState, UiUpdate, UpdateSender},
+    websocket::load_initial_data::get_private_channels::get_private_channel,
+};
+
+/// Handles CHANNEL_CREATE (opcode 0).
+///
+/// New private channels (type 1) and groups (type 3) are added to the channel list.
+pub async fn channel_create(json: &Value, app_state: AppState, update_sender: UpdateSender) {
+    if !is_private_channel(json) {
+        return;
+    }
+
+    let Some(channel) = json.get("d").and_then(get_private_channel) else {
+        return;
+    };
+
+    let mut app_data = app_state.write().await;
+    if app_data.private_channels.iter().any(|v| v.id == channel.id) {
+        return;
+    }
+
+    app_data.private_channels.push(channel);
+    app_data.sort_private_channels();
+    drop(app_data);
+
+    let _ = update_sender.send(UiUpdate::ChannelListChanged);
+}
+
+/// Whether the "d" of a channel event is a private channel or a group.
+pub fn is_private_channel(json: &Value) -> bool {
+    matches!(
+        json.pointer("/d/type").and_then(|v| v.as_u64()),
+        Some(1 | 3)
+    )
+}
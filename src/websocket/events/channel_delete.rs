@@ -0,0 +1,33 @@
+use serde_json::Value;
+
+use crate::{
+    state::{AppState, UiUpdate, UpdateSender},
+    websocket::events::channel_create::is_private_channel,
+};
+
+/// Handles CHANNEL_DELETE (opcode 0), sent when a private channel is closed or a group is left.
+pub async fn channel_delete(json: &Value, app_state: AppState, update_sender: UpdateSender) {
+    if !is_private_channel(json) {
+        return;
+    }
+
+    let Some(channel_id) = json.pointer("/d/id").and_then(|v| v.as_str()) else {
+        return;
+    };
+
+    let mut app_data = app_state.write().await;
+    app_data.private_channels.retain(|v| v.id != channel_id);
+    app_data.messages.remove(channel_id);
+    app_data.latest_messages.remove(channel_id);
+
+    let was_selected = app_data.selected_channel_id.as_deref() == Some(channel_id);
+    if was_selected {
+        app_data.selected_channel_id = None;
+    }
+    drop(app_data);
+
+    let _ = update_sender.send(UiUpdate::ChannelListChanged);
+    if was_selected {
+        let _ = update_sender.send(UiUpdate::SelectedChannelChanged);
+    }
+}
@@ -0,0 +1,61 @@
+use serde_json::Value;
+
+use crate::{
+    messages::parse_message::parse_user,
+    state::{AppState, UiUpdate, UpdateSender},
+};
+
+/// Handles CHANNEL_RECIPIENT_ADD (opcode 0), a user was added to a group.
+pub async fn channel_recipient_add(json: &Value, app_state: AppState, update_sender: UpdateSender) {
+    let (Some(channel_id), Some(user)) = (
+        json.pointer("/d/channel_id").and_then(|v| v.as_str()),
+        json.pointer("/d/user").map(parse_user),
+    ) else {
+        return;
+    };
+
+    let mut app_data = app_state.write().await;
+    let Some(channel) = app_data
+        .private_channels
+        .iter_mut()
+        .find(|v| v.id == channel_id)
+    else {
+        return;
+    };
+
+    if channel.recipients.iter().any(|v| v.id == user.id) {
+        return;
+    }
+    channel.recipients.push(user);
+    drop(app_data);
+
+    let _ = update_sender.send(UiUpdate::ChannelListChanged);
+}
+
+/// Handles CHANNEL_RECIPIENT_REMOVE (opcode 0), a user left or was removed from a group.
+pub async fn channel_recipient_remove(
+    json: &Value,
+    app_state: AppState,
+    update_sender: UpdateSender,
+) {
+    let (Some(channel_id), Some(user_id)) = (
+        json.pointer("/d/channel_id").and_then(|v| v.as_str()),
+        json.pointer("/d/user/id").and_then(|v| v.as_str()),
+    ) else {
+        return;
+    };
+
+    let mut app_data = app_state.write().await;
+    let Some(channel) = app_data
+        .private_channels
+        .iter_mut()
+        .find(|v| v.id == channel_id)
+    else {
+        return;
+    };
+
+    channel.recipients.retain(|v| v.id != user_id);
+    drop(app_data);
+
+    let _ = update_sender.send(UiUpdate::ChannelListChanged);
+}
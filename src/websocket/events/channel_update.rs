@@ -0,0 +1,40 @@
+use serde_json::Value;
+
+use crate::{
+    state::{AppState, UiUpdate, UpdateSender},
+    websocket::{
+        events::channel_create::is_private_channel,
+        load_initial_data::get_private_channels::get_private_channel,
+    },
+};
+
+/// Handles CHANNEL_UPDATE (opcode 0), e.g. a group was renamed or got a new icon.
+pub async fn channel_update(json: &Value, app_state: AppState, update_sender: UpdateSender) {
+    if !is_private_channel(json) {
+        return;
+    }
+
+    let Some(updated) = json.get("d").and_then(get_private_channel) else {
+        return;
+    };
+
+    let mut app_data = app_state.write().await;
+    let Some(channel) = app_data
+        .private_channels
+        .iter_mut()
+        .find(|v| v.id == updated.id)
+    else {
+        return;
+    };
+
+    channel.name = updated.name;
+    channel.icon_hash = updated.icon_hash;
+    channel.recipients = updated.recipients;
+    channel.sort_id = channel.sort_id.max(updated.sort_id);
+    channel.last_message_id = channel.last_message_id.max(updated.last_message_id);
+
+    app_data.sort_private_channels();
+    drop(app_data);
+
+    let _ = update_sender.send(UiUpdate::ChannelListChanged);
+}
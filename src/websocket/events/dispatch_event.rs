@@ -0,0 +1,85 @@
+use serde_json::Value;
+
+use crate::{
+    state::{AppState, UiUpdate, UpdateSender},
+    websocket::{
+        events::{
+            channel_create::channel_create,
+            channel_delete::channel_delete,
+            channel_recipient::{channel_recipient_add, channel_recipient_remove},
+            channel_update::channel_update,
+            message_ack::message_ack,
+            message_create::message_create,
+            message_reaction::{
+                message_reaction_add, message_reaction_remove, message_reaction_remove_all,
+                message_reaction_remove_emoji,
+            },
+            message_update::message_update,
+            presence_update::{presence_update, ready_supplemental},
+        },
+        load_initial_data::{get_guilds::load_guild_icons, load_initial_data::load_initial_data},
+    },
+};
+
+/// Handles a dispatch event (opcode 0) by its event type "t".
+pub async fn dispatch_event(
+    event_type: &str,
+    json: &Value,
+    app_state: AppState,
+    update_sender: UpdateSender,
+) {
+    match event_type {
+        "READY" => {
+            load_initial_data(json, app_state.clone()).await;
+
+            let _ = update_sender.send(UiUpdate::CurrentUserChanged);
+            let _ = update_sender.send(UiUpdate::ChannelListChanged);
+            let _ = update_sender.send(UiUpdate::GuildListChanged);
+
+            load_guild_icons(app_state.clone(), update_sender.clone());
+        }
+        "MESSAGE_CREATE" => {
+            message_create(json, app_state.clone(), update_sender.clone()).await;
+        }
+        "MESSAGE_ACK" => {
+            message_ack(json, app_state.clone(), update_sender.clone()).await;
+        }
+        "MESSAGE_UPDATE" => {
+            message_update(json, app_state.clone(), update_sender.clone()).await;
+        }
+        "MESSAGE_REACTION_ADD" => {
+            message_reaction_add(json, app_state.clone(), update_sender.clone()).await;
+        }
+        "MESSAGE_REACTION_REMOVE" => {
+            message_reaction_remove(json, app_state.clone(), update_sender.clone()).await;
+        }
+        "MESSAGE_REACTION_REMOVE_ALL" => {
+            message_reaction_remove_all(json, app_state.clone(), update_sender.clone()).await;
+        }
+        "MESSAGE_REACTION_REMOVE_EMOJI" => {
+            message_reaction_remove_emoji(json, app_state.clone(), update_sender.clone()).await;
+        }
+        "READY_SUPPLEMENTAL" => {
+            ready_supplemental(json, app_state.clone(), update_sender.clone()).await;
+        }
+        "PRESENCE_UPDATE" => {
+            presence_update(json, app_state.clone(), update_sender.clone()).await;
+        }
+        "CHANNEL_CREATE" => {
+            channel_create(json, app_state.clone(), update_sender.clone()).await;
+        }
+        "CHANNEL_UPDATE" => {
+            channel_update(json, app_state.clone(), update_sender.clone()).await;
+        }
+        "CHANNEL_DELETE" => {
+            channel_delete(json, app_state.clone(), update_sender.clone()).await;
+        }
+        "CHANNEL_RECIPIENT_ADD" => {
+            channel_recipient_add(json, app_state.clone(), update_sender.clone()).await;
+        }
+        "CHANNEL_RECIPIENT_REMOVE" => {
+            channel_recipient_remove(json, app_state.clone(), update_sender.clone()).await;
+        }
+        _ => {}
+    }
+}
@@ -0,0 +1,30 @@
+use serde_json::Value;
+
+use crate::state::{AppState, ReadState, UiUpdate, UpdateSender};
+
+/// Handles MESSAGE_ACK (opcode 0), sent when a channel was read, also from other devices.
+pub async fn message_ack(json: &Value, app_state: AppState, update_sender: UpdateSender) {
+    let (Some(channel_id), Some(message_id)) = (
+        json.pointer("/d/channel_id").and_then(|v| v.as_str()),
+        json.pointer("/d/message_id")
+            .and_then(|v| v.as_str())
+            .and_then(|v| v.parse::<u64>().ok()),
+    ) else {
+        return;
+    };
+
+    let mention_count = json
+        .pointer("/d/mention_count")
+        .and_then(|v| v.as_u64())
+        .unwrap_or_default();
+
+    app_state.write().await.read_states.insert(
+        channel_id.to_string(),
+        ReadState {
+            last_message_id: message_id,
+            mention_count,
+        },
+    );
+
+    let _ = update_sender.send(UiUpdate::ReadStateChanged(channel_id.to_string()));
+}
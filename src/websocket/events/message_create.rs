@@ -0,0 +1,132 @@
+use serde_json::Value;
+use tracing::{debug, Instrument};
+
+use crate::{
+    config::load_config::config,
+    logging::redact::redact_content,
+    messages::{ack_message::ack_channel, parse_message::parse_message},
+    state::{AppState, ReadState, UiUpdate, UpdateSender},
+};
+
+/// Handles MESSAGE_CREATE (opcode 0).
+///
+/// If the message has the nonce of a pending local message, the pending message is replaced.
+/// Also updates the channels last message, position in the channel list, and read state.
+///
+/// Messages are only kept for channels whose history is loaded, of other private channels
+/// only the latest message is kept for the preview, of other guild channels none.
+pub async fn message_create(json: &Value, app_state: AppState, update_sender: UpdateSender) {
+    let Some(message) = json.get("d").and_then(parse_message) else {
+        return;
+    };
+
+    let channel_id = message.channel_id.clone();
+    let message_id = message.id.parse::<u64>().unwrap_or_default();
+    let author_id = message.author.id.clone();
+    let notifications = &config().notifications;
+    let keyword = notifications.matches_keyword(&message.content);
+    debug!(
+        %channel_id,
+        message_id,
+        content = %redact_content(&message.content),
+        "Message created"
+    );
+
+    let mut app_data = app_state.write().await;
+    let previous_last_message_id = app_data.last_message_id(&channel_id);
+    let is_private = app_data.private_channels.iter().any(|v| v.id == channel_id);
+    let index = if app_data.messages.contains_key(&channel_id) {
+        Some(app_data.insert_message(message))
+    } else {
+        if is_private {
+            app_data.latest_messages.insert(channel_id.clone(), message);
+        }
+        None
+    };
+
+    // Move private channels with a new message to the top of the list.
+    let private_channel = app_data
+        .private_channels
+        .iter_mut()
+        .find(|v| v.id == channel_id);
+
+    let moved = match private_channel {
+        Some(channel) if message_id > channel.sort_id => {
+            channel.sort_id = message_id;
+            channel.last_message_id = channel.last_message_id.max(message_id);
+            true
+        }
+        _ => false,
+    };
+    if moved {
+        app_data.sort_private_channels();
+    }
+
+    if let Some(guild) = json
+        .pointer("/d/guild_id")
+        .and_then(|v| v.as_str())
+        .and_then(|guild_id| app_data.guilds.iter_mut().find(|v| v.id == guild_id))
+    {
+        let last_message_id = guild
+            .channel_last_message_ids
+            .entry(channel_id.clone())
+            .or_default();
+        *last_message_id = (*last_message_id).max(message_id);
+    }
+
+    let current_user_id = app_data
+        .current_user
+        .as_ref()
+        .map(|user| user.id.clone())
+        .unwrap_or_default();
+    let is_own = author_id == current_user_id;
+    let is_viewed = app_data.selected_channel_id.as_deref() == Some(channel_id.as_str());
+    let mentions_user = json
+        .pointer("/d/mention_everyone")
+        .and_then(|v| v.as_bool())
+        .unwrap_or_default()
+        || json
+            .pointer("/d/mentions")
+            .and_then(|v| v.as_array())
+            .is_some_and(|mentions| {
+                mentions
+                    .iter()
+                    .any(|v| v.get("id").and_then(|v| v.as_str()) == Some(&current_user_id))
+            });
+
+    if is_own {
+        // Discord acknowledges the clients own messages.
+        let read_state = app_data.read_states.entry(channel_id.clone()).or_default();
+        read_state.last_message_id = read_state.last_message_id.max(message_id);
+    } else if !is_viewed {
+        // Every message in a private channel counts as a mention.
+        let mentioned = is_private || mentions_user;
+
+        let read_state = app_data
+            .read_states
+            .entry(channel_id.clone())
+            .or_insert(ReadState {
+                last_message_id: previous_last_message_id,
+                mention_count: 0,
+            });
+        if mentioned {
+            read_state.mention_count += 1;
+        }
+    }
+    drop(app_data);
+
+    if let Some(index) = index {
+        let _ = update_sender.send(UiUpdate::MessagesChanged(
+            channel_id.clone(),
+            index..index + 1,
+        ));
+    }
+    let _ = update_sender.send(UiUpdate::ReadStateChanged(channel_id.clone()));
+    if !is_own && notifications.should_notify(&channel_id, is_private, mentions_user || keyword) {
+        let _ = update_sender.send(UiUpdate::Notify(channel_id.clone()));
+    }
+
+    if is_viewed && !is_own {
+        tokio::spawn(ack_channel(app_state, update_sender, channel_id).in_current_span());
+    }
+}
@@ -0,0 +1,114 @@
+use serde_json::Value;
+
+use crate::{
+    messages::parse_message::parse_emoji,
+    state::{AppState, CustomEmoji, UiUpdate, UpdateSender},
+};
+
+/// Handles MESSAGE_REACTION_ADD (opcode 0).
+pub async fn message_reaction_add(json: &Value, app_state: AppState, update_sender: UpdateSender) {
+    update_reaction(json, app_state, update_sender, true).await;
+}
+
+/// Handles MESSAGE_REACTION_REMOVE (opcode 0).
+pub async fn message_reaction_remove(
+    json: &Value,
+    app_state: AppState,
+    update_sender: UpdateSender,
+) {
+    update_reaction(json, app_state, update_sender, false).await;
+}
+
+/// Handles MESSAGE_REACTION_REMOVE_ALL (opcode 0), all reactions were removed from a message.
+pub async fn message_reaction_remove_all(
+    json: &Value,
+    app_state: AppState,
+    update_sender: UpdateSender,
+) {
+    remove_reactions(json, app_state, update_sender, None).await;
+}
+
+/// Handles MESSAGE_REACTION_REMOVE_EMOJI (opcode 0), all reactions with one emoji were removed.
+pub async fn message_reaction_remove_emoji(
+    json: &Value,
+    app_state: AppState,
+    update_sender: UpdateSender,
+) {
+    let Some(emoji) = json.pointer("/d/emoji").map(parse_emoji) else {
+        return;
+    };
+
+    remove_reactions(json, app_state, update_sender, Some(emoji)).await;
+}
+
+async fn update_reaction(
+    json: &Value,
+    app_state: AppState,
+    update_sender: UpdateSender,
+    added: bool,
+) {
+    let (Some(channel_id), Some(message_id), Some(user_id), Some(emoji)) = (
+        json.pointer("/d/channel_id").and_then(|v| v.as_str()),
+        json.pointer("/d/message_id").and_then(|v| v.as_str()),
+        json.pointer("/d/user_id").and_then(|v| v.as_str()),
+        json.pointer("/d/emoji").map(parse_emoji),
+    ) else {
+        return;
+    };
+
+    let index = {
+        let mut app_data = app_state.write().await;
+        let me = app_data
+            .current_user
+            .as_ref()
+            .is_some_and(|v| v.id == user_id);
+
+        app_data.update_reaction(channel_id, message_id, &emoji, me, added)
+    };
+
+    if let Some(index) = index {
+        let _ = update_sender.send(UiUpdate::MessagesChanged(
+            channel_id.to_string(),
+            index..index + 1,
+        ));
+    }
+}
+
+/// Removes the reactions with the emoji, or all reactions if it is None.
+async fn remove_reactions(
+    json: &Value,
+    app_state: AppState,
+    update_sender: UpdateSender,
+    emoji: Option<CustomEmoji>,
+) {
+    let (Some(channel_id), Some(message_id)) = (
+        json.pointer("/d/channel_id").and_then(|v| v.as_str()),
+        json.pointer("/d/message_id").and_then(|v| v.as_str()),
+    ) else {
+        return;
+    };
+
+    let mut app_data = app_state.write().await;
+    let Some((index, message)) = app_data.messages.get_mut(channel_id).and_then(|messages| {
+        messages
+            .iter_mut()
+            .enumerate()
+            .find(|(_, v)| v.id == message_id)
+    }) else {
+        return;
+    };
+
+    match emoji {
+        Some(emoji) => {
+            let key = emoji.reaction_key();
+            message.reactions.retain(|v| v.emoji.reaction_key() != key);
+        }
+        None => message.reactions.clear(),
+    }
+
+    drop(app_data);
+    let _ = update_sender.send(UiUpdate::MessagesChanged(
+        channel_id.to_string(),
+        index..index + 1,
+    ));
+}
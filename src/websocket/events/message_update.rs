@@ -0,0 +1,61 @@
+use serde_json::Value;
+
+use crate::{
+    messages::{
+        parse_attachment::parse_attachment, parse_embed::parse_embed, parse_message::parse_user,
+    },
+    state::{AppState, UiUpdate, UpdateSender},
+};
+
+/// Handles MESSAGE_UPDATE (opcode 0).
+///
+/// The event can be partial (e.g. only embeds were added),
+/// so only the fields that are present are updated.
+pub async fn message_update(json: &Value, app_state: AppState, update_sender: UpdateSender) {
+    let (Some(id), Some(channel_id)) = (
+        json.pointer("/d/id").and_then(|v| v.as_str()),
+        json.pointer("/d/channel_id").and_then(|v| v.as_str()),
+    ) else {
+        return;
+    };
+
+    let mut app_data = app_state.write().await;
+
+    let Some((index, message)) = app_data
+        .messages
+        .get_mut(channel_id)
+        .and_then(|messages| messages.iter_mut().enumerate().find(|(_, v)| v.id == id))
+    else {
+        return;
+    };
+
+    if let Some(content) = json.pointer("/d/content").and_then(|v| v.as_str()) {
+        message.content = content.to_string();
+    }
+
+    if let Some(mentions) = json.pointer("/d/mentions").and_then(|v| v.as_array()) {
+        message.mentions = mentions.iter().map(parse_user).collect();
+    }
+
+    if let Some(embeds) = json.pointer("/d/embeds").and_then(|v| v.as_array()) {
+        message.embeds = embeds.iter().map(parse_embed).collect();
+    }
+
+    if let Some(attachments) = json.pointer("/d/attachments").and_then(|v| v.as_array()) {
+        message.attachments = attachments.iter().map(parse_attachment).collect();
+    }
+
+    if let Some(edited_timestamp) = json.pointer("/d/edited_timestamp").and_then(|v| v.as_str()) {
+        message.edited_timestamp = edited_timestamp.to_string();
+    }
+
+    if let Some(flags) = json.pointer("/d/flags").and_then(|v| v.as_u64()) {
+        message.flags = flags;
+    }
+
+    drop(app_data);
+    let _ = update_sender.send(UiUpdate::MessagesChanged(
+        channel_id.to_string(),
+        index..index + 1,
+    ));
+}
@@ -0,0 +1,10 @@
+pub mod channel_create;
+pub mod channel_delete;
+pub mod channel_recipient;
+pub mod channel_update;
+pub mod dispatch_event;
+pub mod message_ack;
+pub mod message_create;
+pub mod message_reaction;
+pub mod message_update;
+pub mod presence_update;
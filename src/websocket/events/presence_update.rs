@@ -0,0 +1,36 @@
+use serde_json::Value;
+
+use crate::{
+    state::{AppState, UiUpdate, UpdateSender},
+    websocket::load_initial_data::get_presences::{get_presence, get_presences},
+};
+
+/// Handles PRESENCE_UPDATE (opcode 0).
+pub async fn presence_update(json: &Value, app_state: AppState, update_sender: UpdateSender) {
+    let Some((user_id, status)) = json.get("d").and_then(get_presence) else {
+        return;
+    };
+
+    app_state
+        .write()
+        .await
+        .presences
+        .insert(user_id.clone(), status);
+
+    let _ = update_sender.send(UiUpdate::PresenceChanged(user_id));
+}
+
+/// Handles READY_SUPPLEMENTAL (opcode 0), which contains the presences of friends.
+pub async fn ready_supplemental(json: &Value, app_state: AppState, update_sender: UpdateSender) {
+    let Some(friends) = json.pointer("/d/merged_presences/friends") else {
+        return;
+    };
+
+    let presences = get_presences(friends);
+
+    let mut app_data = app_state.write().await;
+    for (user_id, status) in presences {
+        app_data.presences.insert(user_id.clone(), status);
+        let _ = update_sender.send(UiUpdate::PresenceChanged(user_id));
+    }
+}
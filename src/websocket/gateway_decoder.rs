@@ -0,0 +1,71 @@
+use flate2::{Decompress, FlushDecompress, Status};
+use tokio_tungstenite::tungstenite::Message;
+use tracing::warn;
+
+/// Ends every complete message of a zlib-stream connection.
+const ZLIB_SUFFIX: [u8; 4] = [0x00, 0x00, 0xff, 0xff];
+
+/// Turns gateway messages into json text.
+///
+/// With zlib-stream compression all messages of a connection are one zlib stream,
+/// a message can be split over several binary frames and is complete with the ZLIB_SUFFIX.
+pub struct GatewayDecoder {
+    /// None without compression.
+    inflater: Option<Decompress>,
+    buffer: Vec<u8>,
+}
+
+impl GatewayDecoder {
+    pub fn new(compressed: bool) -> Self {
+        Self {
+            inflater: compressed.then(|| Decompress::new(true)),
+            buffer: Vec::new(),
+        }
+    }
+
+    /// The text of a complete message, None while more frames are needed or for other messages.
+    pub fn decode(&mut self, message: Message) -> Option<String> {
+        match message {
+            Message::Text(text) => Some(text.to_string()),
+            Message::Binary(bytes) => {
+                let inflater = self.inflater.as_mut()?;
+
+                self.buffer.extend_from_slice(&bytes);
+                if !self.buffer.ends_with(&ZLIB_SUFFIX) {
+                    return None;
+                }
+
+                let result = inflate(inflater, &self.buffer);
+                self.buffer.clear();
+                match result {
+                    Ok(text) => Some(text),
+                    Err(e) => {
+                        warn!("Failed to decompress gateway message: {}", e);
+                        None
+                    }
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+fn inflate(inflater: &mut Decompress, input: &[u8]) -> Result<String, Box<dyn std::error::Error>> {
+    let mut output = Vec::with_capacity(input.len() * 4);
+    let mut read = 0;
+
+    loop {
+        let total_in = inflater.total_in();
+        let status = inflater.decompress_vec(&input[read..], &mut output, FlushDecompress::Sync)?;
+        read += (inflater.total_in() - total_in) as usize;
+
+        // Stopped early because the output was full.
+        let full = output.len() == output.capacity();
+        if status == Status::StreamEnd || (read == input.len() && !full) {
+            break;
+        }
+        output.reserve(output.capacity().max(1024));
+    }
+
+    Ok(String::from_utf8(output)?)
+}
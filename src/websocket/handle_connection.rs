@@ -1,13 +1,13 @@
 use futures_util::{stream::SplitStream, StreamExt};
 use std::{error::Error, sync::Arc};
 use tokio::{net::TcpStream, sync::mpsc::UnboundedSender};
-use tokio_tungstenite::{
-    tungstenite::{self, Message},
-    MaybeTlsStream, WebSocketStream,
-};
+use tokio_tungstenite::{tungstenite::Message, MaybeTlsStream, WebSocketStream};
+use tracing::debug;
 
 use crate::websocket::load_initial_data::send_identity::send_identity;
-use crate::websocket::{heartbeat::send_heartbeats, sequence_tracker::SequenceTracker};
+use crate::websocket::{
+    gateway_decoder::GatewayDecoder, heartbeat::send_heartbeats, sequence_tracker::SequenceTracker,
+};
 
 /// Handles websocket sonnection
 ///
@@ -16,15 +16,16 @@ use crate::websocket::{heartbeat::send_heartbeats, sequence_tracker::SequenceTra
 /// - send_heartbeats (with heartbeat_interval from Message)
 pub async fn handle_connection(
     read: &mut SplitStream<WebSocketStream<MaybeTlsStream<TcpStream>>>,
+    decoder: &mut GatewayDecoder,
     authorization_token: &str,
+    intents: u64,
     transmitter: UnboundedSender<Message>,
     sequence_tracker: Arc<SequenceTracker>,
 ) -> Result<(), Box<dyn Error>> {
     if let Some(message) = read.next().await {
-        println!("First message: {:?}", message);
         // Ok(Text(Utf8Bytes(b"{\"t\":null,\"s\":null,\"op\":10,\"d\":{\"heartbeat_interval\":41250,\"_trace\":[\"[\\\"gateway-prd-arm-us-east1-c-49x5\\\",{\\\"micros\\\":0.0}]\"]}}")))
 
-        if let Ok(tungstenite::Message::Text(text)) = message {
+        if let Some(text) = message.ok().and_then(|message| decoder.decode(message)) {
             if let Ok(json) = serde_json::from_str::<serde_json::Value>(&text) {
                 if let Some(op) = json["op"].as_u64() {
                     if op != 10 {
@@ -32,9 +33,9 @@ pub async fn handle_connection(
                     }
                 }
                 if let Some(heartbeat_interval) = json["d"]["heartbeat_interval"].as_u64() {
-                    println!("Heartbeat interval: {}", heartbeat_interval);
+                    debug!(heartbeat_interval, "Hello");
 
-                    send_identity(authorization_token, transmitter.clone()).await?;
+                    send_identity(authorization_token, intents, transmitter.clone()).await?;
 
                     send_heartbeats(transmitter.clone(), heartbeat_interval, sequence_tracker)?;
                 }
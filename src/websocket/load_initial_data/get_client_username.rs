@@ -1,4 +1,5 @@
 use serde_json::Value;
+use tracing::debug;
 
 use crate::state::User;
 
@@ -28,7 +29,7 @@ pub fn get_client_username(json: &Value) -> Option<User> {
             .unwrap_or_default()
             .to_string();
 
-        println!("Username: {}, Global Name: {}", username, global_name);
+        debug!(%id, %username, %global_name, "Logged in as");
 
         Some(User {
             id,
@@ -1,6 +1,14 @@
+use std::collections::HashMap;
+
+use futures_util::future::join_all;
 use serde_json::Value;
+use tokio::spawn;
+use tracing::warn;
 
-use crate::state::Guild;
+use crate::{
+    assets::asset_cache::Priority,
+    state::{AppState, Guild, UiUpdate, UpdateSender},
+};
 
 pub fn get_guilds(json: &Value) -> Vec<Guild> {
     let guilds = match json.pointer("/d/guilds").and_then(Value::as_array) {
@@ -10,12 +18,94 @@ pub fn get_guilds(json: &Value) -> Vec<Guild> {
 
     let mut result = Vec::with_capacity(guilds.len());
     result.extend(guilds.iter().map(|guild| {
+        let id = guild
+            .get("id")
+            .and_then(Value::as_str)
+            .map(str::to_owned)
+            .unwrap_or_default();
+
+        // User accounts get the guild fields in "properties".
         let name = guild
             .get("name")
+            .or_else(|| guild.pointer("/properties/name"))
             .and_then(Value::as_str)
             .map(str::to_owned)
-            .unwrap_or_default(); 
-        Guild { name }
+            .unwrap_or_default();
+
+        let icon_hash = guild
+            .get("icon")
+            .or_else(|| guild.pointer("/properties/icon"))
+            .and_then(Value::as_str)
+            .map(str::to_owned)
+            .unwrap_or_default();
+
+        let channel_last_message_ids: HashMap<String, u64> = guild
+            .get("channels")
+            .and_then(Value::as_array)
+            .map(|channels| {
+                channels
+                    .iter()
+                    .filter_map(|channel| {
+                        let id = channel.get("id").and_then(Value::as_str)?;
+                        let last_message_id = channel
+                            .get("last_message_id")
+                            .and_then(Value::as_str)
+                            .and_then(|v| v.parse::<u64>().ok())?;
+                        Some((id.to_owned(), last_message_id))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let channel_names = get_names(guild.get("channels"));
+        let role_names = get_names(guild.get("roles"));
+
+        Guild {
+            id,
+            name,
+            channel_last_message_ids,
+            channel_names,
+            role_names,
+            icon_hash,
+        }
     }));
     result
 }
+
+/// Name per id of an array of channels or roles.
+fn get_names(json: Option<&Value>) -> HashMap<String, String> {
+    json.and_then(Value::as_array)
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|item| {
+                    let id = item.get("id").and_then(Value::as_str)?;
+                    let name = item.get("name").and_then(Value::as_str)?;
+                    Some((id.to_owned(), name.to_owned()))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Downloads the icons of all guilds after READY.
+pub fn load_guild_icons(app_state: AppState, update_sender: UpdateSender) {
+    spawn(async move {
+        let guilds: Vec<Guild> = app_state.read().await.guilds.clone();
+
+        let futures = guilds.into_iter().map(|guild| {
+            let update_sender = update_sender.clone();
+            async move {
+                match guild.get_icon(Priority::Background).await {
+                    Ok(()) if !guild.icon_hash.is_empty() => {
+                        let _ = update_sender.send(UiUpdate::GuildIconReady(guild.id));
+                    }
+                    Ok(()) => {}
+                    Err(e) => warn!("Failed to download the icon of {}: {}", guild.name, e),
+                }
+            }
+        });
+
+        let _ = join_all(futures).await;
+    });
+}
@@ -0,0 +1,32 @@
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+/// Returns the status ("online", "idle", "dnd", "offline") per user id.
+///
+/// Accepts both presence shapes discord sends,
+/// {"user": {"id": ..}, "status": ..} in READY and PRESENCE_UPDATE,
+/// and {"user_id": .., "status": ..} in READY_SUPPLEMENTAL.
+pub fn get_presences(presences: &Value) -> HashMap<String, String> {
+    let Some(presences) = presences.as_array() else {
+        return HashMap::new();
+    };
+
+    presences.iter().filter_map(get_presence).collect()
+}
+
+pub fn get_presence(presence: &Value) -> Option<(String, String)> {
+    let user_id = presence
+        .pointer("/user/id")
+        .or_else(|| presence.get("user_id"))
+        .and_then(|v| v.as_str())?
+        .to_string();
+
+    let status = presence
+        .get("status")
+        .and_then(|v| v.as_str())
+        .unwrap_or("offline")
+        .to_string();
+
+    Some((user_id, status))
+}
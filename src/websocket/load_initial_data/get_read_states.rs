@@ -0,0 +1,50 @@
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::state::ReadState;
+
+/// Returns the read state per channel id.
+///
+/// read_state is either a list of entries, or {"entries": [..], "partial": .., "version": ..}.
+pub fn get_read_states(json: &Value) -> HashMap<String, ReadState> {
+    let Some(read_state) = json.pointer("/d/read_state") else {
+        return HashMap::new();
+    };
+
+    let entries = read_state
+        .get("entries")
+        .unwrap_or(read_state)
+        .as_array()
+        .cloned()
+        .unwrap_or_default();
+
+    entries
+        .iter()
+        .filter_map(|entry| {
+            let channel_id = entry.get("id").and_then(|v| v.as_str())?.to_string();
+
+            let last_message_id = entry
+                .get("last_message_id")
+                .and_then(|v| match v {
+                    Value::String(id) => id.parse::<u64>().ok(),
+                    Value::Number(id) => id.as_u64(),
+                    _ => None,
+                })
+                .unwrap_or_default();
+
+            let mention_count = entry
+                .get("mention_count")
+                .and_then(|v| v.as_u64())
+                .unwrap_or_default();
+
+            Some((
+                channel_id,
+                ReadState {
+                    last_message_id,
+                    mention_count,
+                },
+            ))
+        })
+        .collect()
+}
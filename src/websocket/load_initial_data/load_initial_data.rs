@@ -1,28 +1,44 @@
-use std::cmp::Reverse;
-
 use serde_json::Value;
 
+use crate::assets::{asset::PROFILE_AVATAR_SIZE, asset_cache::Priority};
 use crate::state::AppState;
 use crate::websocket::load_initial_data::get_client_username::get_client_username;
 use crate::websocket::load_initial_data::get_guilds::get_guilds;
+use crate::websocket::load_initial_data::get_presences::get_presences;
 use crate::websocket::load_initial_data::get_private_channels::get_private_channels;
+use crate::websocket::load_initial_data::get_read_states::get_read_states;
 
 /// load_initial_data loads data received from sending the initial intent message (opcode 2).
 pub async fn load_initial_data(json: &Value, app_state: AppState) {
     let client_user = get_client_username(json);
     let guilds = get_guilds(json);
+    let premium_type = json
+        .pointer("/d/user/premium_type")
+        .and_then(|v| v.as_u64())
+        .unwrap_or_default();
+
+    let presences = json
+        .pointer("/d/presences")
+        .map(get_presences)
+        .unwrap_or_default();
 
-    let mut private_channels = get_private_channels(json);
-    private_channels.sort_by_key(|v| Reverse(v.sort_id));
+    let private_channels = get_private_channels(json);
+    let read_states = get_read_states(json);
 
     let mut app_data = app_state.write().await;
 
     if let Some(user) = client_user {
-        app_data.current_user = Some(user.clone());
+        app_data.set_current_user(user.clone());
 
-        let _ = user.get_avatar().await;
+        let _ = user
+            .get_avatar(PROFILE_AVATAR_SIZE, Priority::Visible)
+            .await;
     }
 
     app_data.private_channels = private_channels;
+    app_data.sort_private_channels();
     app_data.guilds = guilds;
+    app_data.premium_type = premium_type;
+    app_data.presences.extend(presences);
+    app_data.read_states = read_states;
 }
@@ -1,6 +1,8 @@
 mod get_client_username;
 pub mod get_guilds;
+pub mod get_presences;
 pub mod get_private_channels;
+pub mod get_read_states;
+#[allow(clippy::module_inception)]
 pub mod load_initial_data;
 pub mod send_identity;
-
@@ -2,12 +2,14 @@ use serde_json::json;
 use std::error::Error;
 use tokio::sync::mpsc::UnboundedSender;
 use tokio_tungstenite::tungstenite::Message;
+use tracing::debug;
 
 /// Sends opcode 2,
 /// with authorization token,
 /// and intent (request certain information).
 pub async fn send_identity(
     authorization_token: &str,
+    intents: u64,
     transmitter: UnboundedSender<Message>,
 ) -> Result<(), Box<dyn Error>> {
     let identify = json!({
@@ -19,11 +21,11 @@ pub async fn send_identity(
                 "$browser": "blazingly-rust-discord-client",
                 "$device": "blazingly-rust-discord-client"
             },
-            "intents": (1 << 0) | (1 << 1) | (1 << 2) | (1 << 3) | (1 << 4) | (1 << 5) | (1 << 6) | (1 << 7) | (1 << 8) | (1 << 9) | (1 << 10) | (1 << 11) | (1 << 12) | (1 << 13) | (1 << 14) | (1 << 15) | (1 << 16) | (1 << 20) | (1 << 21) | (1 << 24) | (1 << 25)
+            "intents": intents
         }
     });
     transmitter.send(Message::Text(identify.to_string().into()))?;
-    println!("Sent IDENTIFY");
+    debug!(intents, "Sent IDENTIFY");
 
     Ok(())
 }
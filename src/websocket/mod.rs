@@ -1,7 +1,10 @@
 pub mod sequence_tracker;
+mod events;
+mod gateway_decoder;
 mod handle_connection;
 mod handle_incomming_messages;
 mod heartbeat;
-mod load_initial_data;
+pub mod load_initial_data;
+#[allow(clippy::module_inception)]
 pub mod websocket;
 mod writer_task;
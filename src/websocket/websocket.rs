@@ -1,15 +1,47 @@
 use futures_util::StreamExt;
 use std::sync::Arc;
-use std::{env, error::Error};
-use tokio::sync::mpsc::{self};
+use std::error::Error;
+use tokio::sync::{mpsc, oneshot};
 use tokio_tungstenite::{connect_async, tungstenite::Message};
+use tracing::{error, info, info_span, instrument, Instrument};
 
+use crate::config::{config::Compression, load_config::config};
 use crate::state::{AppState, UpdateSender};
+use crate::websocket::gateway_decoder::GatewayDecoder;
 use crate::websocket::handle_connection::handle_connection;
 use crate::websocket::handle_incomming_messages::handle_incomming_messages;
 use crate::websocket::writer_task::writer_task;
 use crate::websocket::sequence_tracker::SequenceTracker;
 
+/// Connects on a thread of its own, after the user has logged in.
+///
+/// The connection runs on its own runtime, its errors are not Send.
+/// It is closed through AppData::disconnect.
+pub fn spawn_connection(app_state: AppState, update_sender: UpdateSender) {
+    std::thread::spawn(move || {
+        let (disconnect, disconnected) = oneshot::channel();
+        let session_id = {
+            let mut app_data = app_state.blocking_write();
+            app_data.disconnect = Some(disconnect);
+            app_data.session_id
+        };
+        let span = info_span!("session", id = session_id);
+        let _entered = span.enter();
+
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            tokio::select! {
+                result = connect(app_state, update_sender) => {
+                    if let Err(e) = result {
+                        error!("WebSocket error: {}", e);
+                    }
+                }
+                _ = disconnected => info!("Disconnected"),
+            }
+        });
+    });
+}
+
 /// Connects to discords websocket.
 ///
 /// 1. Establishes a connection to the gateway.
@@ -18,27 +50,38 @@ use crate::websocket::sequence_tracker::SequenceTracker;
 /// 4. Sends heartbeats event heartbeat_interval (opcode 1).
 /// 5. Receives heartbeat ACK events (opcode 11). - NOT IMPLEMENTED
 /// 6. Receives messages/updates from discord (opcode 0 && seq_num > 0).
+#[instrument(name = "connection", skip_all)]
 pub async fn connect(app_state: AppState, update_sender: UpdateSender) -> Result<(), Box<dyn Error>> {
-    let authorization_token =
-        env::var("DISCORD_TOKEN").expect("DISCORD_TOKEN environment variable not set");
+    let authorization_token = app_state.read().await.token.clone();
+    if authorization_token.is_empty() {
+        return Err("Not logged in".into());
+    }
 
-    let gateway_url = "wss://gateway.discord.gg/?v=10&encoding=json";
+    let config = config();
+    let compressed = config.gateway.compression == Compression::ZlibStream;
+    let gateway_url = format!(
+        "{}/?v=10&encoding={}{}",
+        config.endpoints.gateway,
+        config.gateway.encoding.as_str(),
+        if compressed { "&compress=zlib-stream" } else { "" }
+    );
 
-    println!("Connecting to Discord Gateway...");
+    info!("Connecting to Discord Gateway...");
     let (ws_stream, _) = connect_async(gateway_url).await?;
-    println!("Websocket connected!");
+    info!("Websocket connected!");
 
     let (write, mut read) = ws_stream.split();
 
     let (transmitter, receiver) = mpsc::unbounded_channel::<Message>();
 
-    let writer = tokio::spawn(writer_task(write, receiver));
+    let writer = tokio::spawn(writer_task(write, receiver).in_current_span());
 
     let sequence_tracker = Arc::new(SequenceTracker::new());
+    let mut decoder = GatewayDecoder::new(compressed);
     
-    handle_connection(&mut read, &authorization_token, transmitter.clone(), sequence_tracker.clone()).await?;
+    handle_connection(&mut read, &mut decoder, &authorization_token, config.gateway.intents.0, transmitter.clone(), sequence_tracker.clone()).await?;
 
-    handle_incomming_messages(&mut read, sequence_tracker.clone(), app_state, update_sender).await?;
+    handle_incomming_messages(&mut read, &mut decoder, sequence_tracker.clone(), app_state, update_sender).await?;
 
     drop(transmitter);
     let _ = writer.await;